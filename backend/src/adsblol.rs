@@ -29,10 +29,10 @@ pub struct AdsbLolAircraft {
 }
 
 impl AdsbLolClient {
-    pub fn new() -> Self {
+    pub fn new(base_url: &str) -> Self {
         AdsbLolClient {
             client: Client::new(),
-            base_url: "https://api.adsb.lol/v2".to_string(),
+            base_url: base_url.to_string(),
         }
     }
 
@@ -110,6 +110,11 @@ impl AdsbLolClient {
                 distance: Some(dist_nm), // Populated!
                 advisory: None,
                 hold_time: None,
+                stand: None,
+                taxi_route: None,
+                conformance: None,
+                sequence: None,
+                phase_confidence: 0.0,
             })
         }).collect();
 
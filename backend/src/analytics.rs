@@ -0,0 +1,445 @@
+//! Delay and taxi-time analytics over the movement log.
+//!
+//! For each airport and hour or day: actual against unimpeded taxi-out and taxi-in
+//! times, how long departures held short, the departure queue, and how late arrivals
+//! landed against their unconstrained ETA. Served by `GET /api/analytics` and printed by
+//! `backend analytics`, as JSON or CSV.
+
+use chrono::{DateTime, NaiveDate};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::store::{MovementStore, Timeline};
+
+/// Taxi speed the unimpeded times assume over the planned route.
+const UNIMPEDED_TAXI_SPEED_KT: f64 = 15.0;
+const METRES_PER_SECOND_PER_KT: f64 = 0.514444;
+/// Largest report, in buckets per airport.
+const MAX_BUCKETS: i64 = 24 * 366;
+
+const USAGE: &str = "Usage: backend analytics [--airport ICAO] [--bucket hour|day] [--since TIME] [--until TIME] [--format json|csv]
+TIME is Unix seconds, a UTC date (2024-05-01) or an RFC 3339 timestamp. Without --since the
+report covers the last 24 hours, or the last 30 days by day.";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Bucket {
+    #[default]
+    Hour,
+    Day,
+}
+
+impl Bucket {
+    fn seconds(self) -> i64 {
+        match self {
+            Bucket::Hour => 3600,
+            Bucket::Day => 86400,
+        }
+    }
+
+    /// Buckets reported when no start is given.
+    fn default_count(self) -> i64 {
+        match self {
+            Bucket::Hour => 24,
+            Bucket::Day => 30,
+        }
+    }
+
+    fn label(self, start: i64) -> String {
+        let format = match self {
+            Bucket::Hour => "%Y-%m-%dT%H:%MZ",
+            Bucket::Day => "%Y-%m-%d",
+        };
+        DateTime::from_timestamp(start, 0).map(|t| t.format(format).to_string()).unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Format {
+    #[default]
+    Json,
+    Csv,
+}
+
+/// e.g. `?airport=EGSS&bucket=day&since=1714521600&format=csv`.
+#[derive(Debug, Default, Deserialize)]
+pub struct AnalyticsQuery {
+    pub airport: Option<String>,
+    /// Unix seconds, rounded down to the start of its bucket.
+    pub since: Option<i64>,
+    /// Unix seconds; defaults to now.
+    pub until: Option<i64>,
+    #[serde(default)]
+    pub bucket: Bucket,
+    #[serde(default)]
+    pub format: Format,
+}
+
+impl AnalyticsQuery {
+    /// The range `[since, until)` reported, starting on a bucket boundary.
+    pub fn range(&self, now: i64) -> Result<(i64, i64), String> {
+        let width = self.bucket.seconds();
+        let until = self.until.unwrap_or(now);
+        let since = match self.since {
+            Some(since) => since.div_euclid(width) * width,
+            None => ((until - 1).div_euclid(width) - (self.bucket.default_count() - 1)) * width,
+        };
+        if since >= until {
+            return Err("since must be before until".to_string());
+        }
+        if (until - since) / width > MAX_BUCKETS {
+            return Err(format!("A report covers at most {} buckets", MAX_BUCKETS));
+        }
+        Ok((since, until))
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub airport: Option<String>,
+    pub bucket: Bucket,
+    pub since: i64,
+    pub until: i64,
+    pub unimpeded_taxi_speed_kt: f64,
+    pub rows: Vec<Row>,
+}
+
+/// One airport over one hour or day. Durations are in seconds, averaged over the
+/// movements counted next to them, and empty when there were none.
+#[derive(Debug, Serialize)]
+pub struct Row {
+    pub airport: String,
+    pub start: i64,
+    pub period: String,
+    /// Departures off-block in the period.
+    pub departures: usize,
+    /// Arrivals touching down in the period.
+    pub arrivals: usize,
+    /// Off-block to take-off roll, against the planned route at the unimpeded speed.
+    pub taxi_out_count: usize,
+    pub taxi_out_avg_s: Option<f64>,
+    pub taxi_out_unimpeded_avg_s: Option<f64>,
+    pub taxi_out_additional_avg_s: Option<f64>,
+    /// Runway vacated to in-block, against the shortest route to the stand.
+    pub taxi_in_count: usize,
+    pub taxi_in_avg_s: Option<f64>,
+    pub taxi_in_unimpeded_avg_s: Option<f64>,
+    pub taxi_in_additional_avg_s: Option<f64>,
+    /// Holding point reached to line-up or take-off roll.
+    pub hold_count: usize,
+    pub hold_avg_s: Option<f64>,
+    pub hold_max_s: Option<f64>,
+    /// Departures between off-block and take-off roll, averaged over the period.
+    pub queue_avg: f64,
+    pub queue_max: usize,
+    /// Touchdown against the first ETA computed for the arrival; negative when early.
+    pub arrival_delay_count: usize,
+    pub arrival_delay_avg_s: Option<f64>,
+    pub arrival_delay_max_s: Option<f64>,
+}
+
+impl Report {
+    pub fn to_csv(&self) -> Result<String, String> {
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        for row in &self.rows {
+            writer.serialize(row).map_err(|e| e.to_string())?;
+        }
+        let bytes = writer.into_inner().map_err(|e| e.to_string())?;
+        String::from_utf8(bytes).map_err(|e| e.to_string())
+    }
+}
+
+struct Taxi {
+    actual: f64,
+    unimpeded: Option<f64>,
+}
+
+/// What happened in one bucket, keyed by when it started.
+#[derive(Default)]
+struct Samples {
+    departures: usize,
+    arrivals: usize,
+    taxi_out: Vec<Taxi>,
+    taxi_in: Vec<Taxi>,
+    holds: Vec<f64>,
+    delays: Vec<f64>,
+}
+
+#[derive(Default)]
+struct Activity {
+    buckets: BTreeMap<i64, Samples>,
+    /// Off-block to take-off roll of every departure.
+    queue: Vec<(i64, i64)>,
+}
+
+impl Activity {
+    fn samples(&mut self, bucket: Option<i64>) -> Option<&mut Samples> {
+        bucket.map(|start| self.buckets.entry(start).or_default())
+    }
+}
+
+/// Builds the report for `[since, until)` from `AnalyticsQuery::range`.
+pub fn report(store: &MovementStore, query: &AnalyticsQuery, since: i64, until: i64) -> rusqlite::Result<Report> {
+    let width = query.bucket.seconds();
+    let mut airports: BTreeMap<String, Activity> = BTreeMap::new();
+    if let Some(airport) = &query.airport {
+        airports.entry(airport.to_ascii_uppercase()).or_default();
+    }
+
+    for timeline in store.timelines(query.airport.as_deref(), since, until)? {
+        let activity = airports.entry(timeline.movement.airport.clone()).or_default();
+        let bucket = |time: i64| (since..until).contains(&time).then(|| time.div_euclid(width) * width);
+
+        if timeline.movement.kind == "departure" {
+            let off_block = first(&timeline, "off_block", i64::MIN);
+            let take_off = off_block.and_then(|o| first(&timeline, "take_off_roll", o));
+            if let Some(samples) = activity.samples(off_block.and_then(bucket)) {
+                samples.departures += 1;
+                if let Some((off_block, take_off)) = off_block.zip(take_off) {
+                    let unimpeded = timeline.movement.taxi_out_m.map(unimpeded_s);
+                    samples.taxi_out.push(Taxi { actual: (take_off - off_block) as f64, unimpeded });
+                }
+            }
+            for hold in timeline.milestones.iter().filter(|m| m.milestone == "holding") {
+                let released = [first(&timeline, "line_up", hold.time), first(&timeline, "take_off_roll", hold.time)]
+                    .into_iter().flatten().min();
+                if let (Some(released), Some(samples)) = (released, activity.samples(bucket(hold.time))) {
+                    samples.holds.push((released - hold.time) as f64);
+                }
+            }
+            if let Some(off_block) = off_block {
+                let end = take_off.or(timeline.movement.completed_at).unwrap_or(timeline.movement.last_seen);
+                activity.queue.push((off_block, end));
+            }
+        } else {
+            let touchdown = first(&timeline, "touchdown", i64::MIN);
+            if let Some(samples) = activity.samples(touchdown.and_then(bucket)) {
+                samples.arrivals += 1;
+                if let Some((touchdown, eta)) = touchdown.zip(timeline.movement.unconstrained_eta) {
+                    samples.delays.push((touchdown - eta) as f64);
+                }
+            }
+            let vacated = first(&timeline, "runway_vacated", i64::MIN);
+            let in_block = vacated.and_then(|v| first(&timeline, "in_block", v));
+            if let (Some(vacated), Some(in_block)) = (vacated, in_block) {
+                if let Some(samples) = activity.samples(bucket(vacated)) {
+                    let unimpeded = timeline.movement.taxi_in_m.map(unimpeded_s);
+                    samples.taxi_in.push(Taxi { actual: (in_block - vacated) as f64, unimpeded });
+                }
+            }
+        }
+    }
+
+    let mut rows = Vec::new();
+    for (airport, activity) in &airports {
+        for start in (since..until).step_by(width as usize) {
+            let empty = Samples::default();
+            let samples = activity.buckets.get(&start).unwrap_or(&empty);
+            let (queue_avg, queue_max) = occupancy(&activity.queue, start, (start + width).min(until));
+            let (taxi_out_avg_s, taxi_out_unimpeded_avg_s, taxi_out_additional_avg_s) = taxi_averages(&samples.taxi_out);
+            let (taxi_in_avg_s, taxi_in_unimpeded_avg_s, taxi_in_additional_avg_s) = taxi_averages(&samples.taxi_in);
+            rows.push(Row {
+                airport: airport.clone(),
+                start,
+                period: query.bucket.label(start),
+                departures: samples.departures,
+                arrivals: samples.arrivals,
+                taxi_out_count: samples.taxi_out.len(),
+                taxi_out_avg_s,
+                taxi_out_unimpeded_avg_s,
+                taxi_out_additional_avg_s,
+                taxi_in_count: samples.taxi_in.len(),
+                taxi_in_avg_s,
+                taxi_in_unimpeded_avg_s,
+                taxi_in_additional_avg_s,
+                hold_count: samples.holds.len(),
+                hold_avg_s: mean(samples.holds.iter().copied()),
+                hold_max_s: samples.holds.iter().copied().reduce(f64::max),
+                queue_avg: round(queue_avg),
+                queue_max,
+                arrival_delay_count: samples.delays.len(),
+                arrival_delay_avg_s: mean(samples.delays.iter().copied()),
+                arrival_delay_max_s: samples.delays.iter().copied().reduce(f64::max),
+            });
+        }
+    }
+
+    Ok(Report {
+        airport: query.airport.as_ref().map(|a| a.to_ascii_uppercase()),
+        bucket: query.bucket,
+        since,
+        until,
+        unimpeded_taxi_speed_kt: UNIMPEDED_TAXI_SPEED_KT,
+        rows,
+    })
+}
+
+/// Time of the first `milestone` at or after `after`.
+fn first(timeline: &Timeline, milestone: &str, after: i64) -> Option<i64> {
+    timeline.milestones.iter().find(|m| m.milestone == milestone && m.time >= after).map(|m| m.time)
+}
+
+fn unimpeded_s(length_m: f64) -> f64 {
+    length_m / (UNIMPEDED_TAXI_SPEED_KT * METRES_PER_SECOND_PER_KT)
+}
+
+/// Average actual time, and the average unimpeded and additional time over the taxis
+/// with a known route.
+fn taxi_averages(taxis: &[Taxi]) -> (Option<f64>, Option<f64>, Option<f64>) {
+    let planned = || taxis.iter().filter_map(|t| Some((t.actual, t.unimpeded?)));
+    (
+        mean(taxis.iter().map(|t| t.actual)),
+        mean(planned().map(|(_, unimpeded)| unimpeded)),
+        mean(planned().map(|(actual, unimpeded)| actual - unimpeded)),
+    )
+}
+
+fn mean(values: impl Iterator<Item = f64>) -> Option<f64> {
+    let (sum, count) = values.fold((0.0, 0), |(sum, count), v| (sum + v, count + 1));
+    (count > 0).then(|| round(sum / count as f64))
+}
+
+fn round(value: f64) -> f64 {
+    (value * 10.0).round() / 10.0
+}
+
+/// Time-weighted average and peak number of the intervals open during `[start, end)`.
+fn occupancy(intervals: &[(i64, i64)], start: i64, end: i64) -> (f64, usize) {
+    let mut open: i64 = 0;
+    let mut changes = Vec::new();
+    for &(from, to) in intervals {
+        if to <= start || from >= end || to <= from {
+            continue;
+        }
+        if from <= start {
+            open += 1;
+        } else {
+            changes.push((from, 1));
+        }
+        if to < end {
+            changes.push((to, -1));
+        }
+    }
+    // Departures leave before others join at the same second
+    changes.sort();
+
+    let (mut area, mut peak, mut at) = (0, open, start);
+    for (time, change) in changes {
+        area += open * (time - at);
+        at = time;
+        open += change;
+        peak = peak.max(open);
+    }
+    area += open * (end - at);
+    (area as f64 / (end - start) as f64, peak as usize)
+}
+
+/// Runs `backend analytics ...` against the log at `database` and returns the report.
+pub fn run_command(args: &[String], database: &Path) -> Result<String, String> {
+    let mut query = AnalyticsQuery::default();
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{} needs a value\n{}", flag, USAGE));
+        match flag.as_str() {
+            "--airport" => query.airport = Some(value()?.clone()),
+            "--bucket" => query.bucket = choice(flag, value()?)?,
+            "--since" => query.since = Some(parse_time(value()?)?),
+            "--until" => query.until = Some(parse_time(value()?)?),
+            "--format" => query.format = choice(flag, value()?)?,
+            "--help" | "-h" => return Ok(format!("{}\n", USAGE)),
+            other => return Err(format!("Unknown option {}\n{}", other, USAGE)),
+        }
+    }
+
+    let (since, until) = query.range(chrono::Utc::now().timestamp())?;
+    let store = MovementStore::open_read_only(database)?;
+    let report = report(&store, &query, since, until).map_err(|e| e.to_string())?;
+    match query.format {
+        Format::Json => serde_json::to_string_pretty(&report).map(|json| json + "\n").map_err(|e| e.to_string()),
+        Format::Csv => report.to_csv(),
+    }
+}
+
+/// A `--bucket` or `--format` value, spelled as in the API.
+fn choice<T: DeserializeOwned>(flag: &str, value: &str) -> Result<T, String> {
+    serde_json::from_value(serde_json::Value::String(value.to_ascii_lowercase()))
+        .map_err(|_| format!("Invalid {} {}\n{}", flag, value, USAGE))
+}
+
+fn parse_time(value: &str) -> Result<i64, String> {
+    if let Ok(seconds) = value.parse::<i64>() {
+        return Ok(seconds);
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc().timestamp());
+    }
+    DateTime::parse_from_rfc3339(value)
+        .map(|t| t.timestamp())
+        .map_err(|_| format!("Invalid time {}: expected Unix seconds, YYYY-MM-DD or RFC 3339", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR: i64 = 3600;
+    const DAY: i64 = 86400;
+
+    #[test]
+    fn range_rounds_since_down_to_the_bucket() {
+        let query = AnalyticsQuery { since: Some(10 * HOUR + 1800), until: Some(12 * HOUR), ..Default::default() };
+        assert_eq!(query.range(0), Ok((10 * HOUR, 12 * HOUR)));
+
+        let query = AnalyticsQuery { since: Some(3 * DAY + 5), until: Some(4 * DAY), bucket: Bucket::Day, ..Default::default() };
+        assert_eq!(query.range(0), Ok((3 * DAY, 4 * DAY)));
+    }
+
+    #[test]
+    fn range_defaults_to_the_last_day_ending_now() {
+        let now = 100 * HOUR + 600;
+        let query = AnalyticsQuery::default();
+        // 24 buckets, the last one the hour in progress
+        assert_eq!(query.range(now), Ok((77 * HOUR, now)));
+
+        let query = AnalyticsQuery { bucket: Bucket::Day, ..Default::default() };
+        assert_eq!(query.range(40 * DAY), Ok((10 * DAY, 40 * DAY)));
+    }
+
+    #[test]
+    fn range_rejects_empty_and_oversized_reports() {
+        let query = AnalyticsQuery { since: Some(5 * HOUR), until: Some(5 * HOUR), ..Default::default() };
+        assert!(query.range(0).is_err());
+
+        let query = AnalyticsQuery { since: Some(0), until: Some((MAX_BUCKETS + 1) * HOUR), ..Default::default() };
+        assert!(query.range(0).is_err());
+        let query = AnalyticsQuery { since: Some(0), until: Some(MAX_BUCKETS * HOUR), ..Default::default() };
+        assert!(query.range(0).is_ok());
+    }
+
+    #[test]
+    fn occupancy_weights_by_time_open() {
+        // One departure all hour, another for the second half
+        let (average, peak) = occupancy(&[(0, 3600), (1800, 5000)], 0, 3600);
+        assert_eq!(average, 1.5);
+        assert_eq!(peak, 2);
+    }
+
+    #[test]
+    fn occupancy_counts_only_the_part_inside_the_bucket() {
+        let (average, peak) = occupancy(&[(-100, 900), (3000, 9000)], 0, 3600);
+        assert_eq!(average, (900.0 + 600.0) / 3600.0);
+        assert_eq!(peak, 1);
+        assert_eq!(occupancy(&[(3600, 4000), (-50, 0), (10, 10)], 0, 3600), (0.0, 0));
+    }
+
+    #[test]
+    fn occupancy_leaves_before_joining_at_the_same_second() {
+        // The first departure takes off as the second pushes back: never two at once
+        let (average, peak) = occupancy(&[(0, 1800), (1800, 3600)], 0, 3600);
+        assert_eq!(average, 1.0);
+        assert_eq!(peak, 1);
+    }
+}
@@ -1,45 +1,617 @@
+use std::collections::HashSet;
 use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
 use dotenvy::dotenv;
+use serde::Deserialize;
 
-#[derive(Debug, Clone)]
+use crate::logic::airport::{AirportData, RunwayConfig};
+use crate::logic::separation::SeparationScheme;
+use crate::weather::WeatherSource;
+
+/// Looked for in the working directory and the repo root when `ATC_CONFIG` is unset.
+const CONFIG_FILE_NAMES: [&str; 2] = ["atc.toml", "../atc.toml"];
+/// Used when no config file is found, so a fresh checkout still starts.
+const DEFAULT_CONFIG: &str = r#"
+[[airports]]
+code = "EGSS"
+lat = 51.885
+lon = 0.235
+
+[[airports]]
+code = "KLAX"
+lat = 33.942
+lon = -118.407
+"#;
+
+const DEFAULT_SERVER_PORT: u16 = 3000;
+const DEFAULT_POLL_INTERVAL_S: u64 = 2;
+const DEFAULT_RADIUS_NM: u32 = 25;
+/// adsb.lol rejects larger point queries.
+const MAX_RADIUS_NM: u32 = 250;
+const DEFAULT_SPURIOUS_GROUND_KM: f64 = 5.5;
+const DEFAULT_STALE_AFTER_S: i64 = 60;
+const DEFAULT_SOURCE_NAME: &str = "adsb.lol";
+const DEFAULT_SOURCE_URL: &str = "https://api.adsb.lol/v2";
+const DEFAULT_DATABASE: &str = "movements.db";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SurveillanceKind {
+    /// adsb.lol v2 `point` API, or anything serving the same format.
+    AdsbLol,
+}
+
+/// A feed of aircraft positions that airports poll.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SurveillanceSource {
+    pub name: String,
+    pub kind: SurveillanceKind,
+    pub url: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct AirportConfig {
     pub code: String,
     pub lat: f64,
     pub lon: f64,
+    /// Surveillance query radius in nm.
     pub radius: u32,
+    /// Sector files for the ground model.
+    pub data_dir: PathBuf,
+    /// Runways in use at startup; the `.rwy` defaults when unset.
+    pub runways: Option<RunwayConfig>,
+    pub separation: SeparationScheme,
+    /// On-ground reports further than this from the reference point are dropped.
+    pub spurious_ground_km: f64,
+    /// Aircraft not heard from for this long are dropped.
+    pub stale_after_s: i64,
+    pub surveillance: SurveillanceSource,
+    pub poll_interval_s: u64,
+}
+
+/// Someone allowed to send commands, identified by a bearer token.
+#[derive(Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Controller {
+    pub name: String,
+    pub token: String,
 }
 
+impl fmt::Debug for Controller {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Controller").field("name", &self.name).finish_non_exhaustive()
+    }
+}
+
+/// Shortest bearer token accepted.
+const MIN_TOKEN_LEN: usize = 16;
+
 #[derive(Debug, Clone)]
 pub struct Config {
     pub server_port: u16,
+    pub weather_source: WeatherSource,
     pub airports: Vec<AirportConfig>,
+    /// Commands are refused while this is empty.
+    pub controllers: Vec<Controller>,
+    /// SQLite movement log.
+    pub database: PathBuf,
+    /// File the settings were read from, or `None` for the built-in defaults.
+    pub path: Option<PathBuf>,
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io { path: PathBuf, source: std::io::Error },
+    Parse { path: PathBuf, source: toml::de::Error },
+    Invalid { path: Option<PathBuf>, problems: Vec<String> },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io { path, source } => write!(f, "failed to read {}: {}", path.display(), source),
+            ConfigError::Parse { path, source } => write!(f, "failed to parse {}: {}", path.display(), source),
+            ConfigError::Invalid { path, problems } => {
+                let origin = path.as_ref().map(|p| p.display().to_string()).unwrap_or_else(|| "built-in defaults".to_string());
+                write!(f, "invalid configuration in {}:", origin)?;
+                for problem in problems {
+                    write!(f, "\n  - {}", problem)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// The config file as written. Optional fields fall back to the defaults above.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    server_port: Option<u16>,
+    airport_data_dir: Option<PathBuf>,
+    /// Raw METAR/TAF file, or the base URL of a local HTTP stand-in.
+    weather_source: Option<String>,
+    poll_interval_s: Option<u64>,
+    /// SQLite movement log; created if missing.
+    database: Option<PathBuf>,
+    #[serde(default)]
+    surveillance: Vec<SourceFile>,
+    #[serde(default)]
+    airports: Vec<AirportFile>,
+    #[serde(default)]
+    controllers: Vec<Controller>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SourceFile {
+    name: String,
+    kind: SurveillanceKind,
+    url: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AirportFile {
+    code: String,
+    lat: f64,
+    lon: f64,
+    radius_nm: Option<u32>,
+    data_dir: Option<PathBuf>,
+    runways: Option<RunwayConfig>,
+    separation: Option<SeparationScheme>,
+    spurious_ground_km: Option<f64>,
+    stale_after_s: Option<i64>,
+    /// Name of a `[[surveillance]]` source; the first one when unset.
+    surveillance: Option<String>,
+    poll_interval_s: Option<u64>,
 }
 
 impl Config {
-    pub fn from_env() -> Self {
+    /// Reads the file named by `ATC_CONFIG`, else `atc.toml` in the working directory or
+    /// repo root, else the built-in defaults. `SERVER_PORT`, `AIRPORT_DATA_DIR`,
+    /// `WEATHER_SOURCE` and `DATABASE_PATH` override the file.
+    pub fn load() -> Result<Self, ConfigError> {
         dotenv().ok();
-        
-        let server_port = env::var("SERVER_PORT")
-            .unwrap_or_else(|_| "3000".to_string())
-            .parse()
-            .expect("SERVER_PORT must be a number");
 
-        Config {
+        let path = env::var("ATC_CONFIG").map(PathBuf::from).ok().or_else(|| {
+            CONFIG_FILE_NAMES.iter().map(PathBuf::from).find(|p| p.is_file())
+        });
+        Self::read(path)
+    }
+
+    /// Reads the same source again, for a reload.
+    pub fn reread(&self) -> Result<Self, ConfigError> {
+        Self::read(self.path.clone())
+    }
+
+    fn read(path: Option<PathBuf>) -> Result<Self, ConfigError> {
+        match path {
+            Some(path) => {
+                let text = fs::read_to_string(&path).map_err(|source| ConfigError::Io { path: path.clone(), source })?;
+                Self::parse(&text, Some(path))
+            }
+            None => Self::parse(DEFAULT_CONFIG, None),
+        }
+    }
+
+    /// Parses and validates config text; relative paths resolve against the file's directory.
+    pub fn parse(text: &str, path: Option<PathBuf>) -> Result<Self, ConfigError> {
+        let file: ConfigFile = toml::from_str(text).map_err(|source| ConfigError::Parse {
+            path: path.clone().unwrap_or_default(),
+            source,
+        })?;
+        let base = path.as_ref().and_then(|p| p.parent()).map(Path::to_path_buf);
+        let resolve = |p: PathBuf| match &base {
+            Some(base) if p.is_relative() => base.join(p),
+            _ => p,
+        };
+        let mut problems = Vec::new();
+
+        let server_port = match env::var("SERVER_PORT") {
+            Ok(port) => port.parse().unwrap_or_else(|_| {
+                problems.push(format!("SERVER_PORT must be a port number, got {:?}", port));
+                DEFAULT_SERVER_PORT
+            }),
+            Err(_) => file.server_port.unwrap_or(DEFAULT_SERVER_PORT),
+        };
+
+        // Sector data lives at the repo root; fall back to it when run from backend/
+        let airport_data_dir = env::var("AIRPORT_DATA_DIR").map(PathBuf::from).ok()
+            .or(file.airport_data_dir.map(resolve))
+            .unwrap_or_else(|| {
+                ["AirportData", "../AirportData"]
+                    .iter()
+                    .map(Path::new)
+                    .find(|p| p.is_dir())
+                    .unwrap_or(Path::new("AirportData"))
+                    .to_path_buf()
+            });
+
+        let weather_source = match (env::var("WEATHER_SOURCE"), file.weather_source) {
+            (Ok(s), _) => WeatherSource::from_setting(&s),
+            (Err(_), Some(s)) => match WeatherSource::from_setting(&s) {
+                WeatherSource::File(p) => WeatherSource::File(resolve(p)),
+                http => http,
+            },
+            (Err(_), None) => WeatherSource::File(airport_data_dir.join("weather.txt")),
+        };
+
+        let database = env::var("DATABASE_PATH").map(PathBuf::from).ok()
+            .unwrap_or_else(|| resolve(file.database.unwrap_or_else(|| PathBuf::from(DEFAULT_DATABASE))));
+
+        let poll_interval_s = file.poll_interval_s.unwrap_or(DEFAULT_POLL_INTERVAL_S);
+        if poll_interval_s == 0 {
+            problems.push("poll_interval_s must be at least 1".to_string());
+        }
+
+        let mut sources = Vec::new();
+        for (i, source) in file.surveillance.into_iter().enumerate() {
+            let at = format!("surveillance[{}] ({})", i, source.name);
+            if source.name.trim().is_empty() {
+                problems.push(format!("surveillance[{}]: name must not be empty", i));
+            }
+            if sources.iter().any(|s: &SurveillanceSource| s.name == source.name) {
+                problems.push(format!("{}: duplicate source name", at));
+            }
+            if !(source.url.starts_with("http://") || source.url.starts_with("https://")) {
+                problems.push(format!("{}: url must start with http:// or https://, got {:?}", at, source.url));
+            }
+            sources.push(SurveillanceSource {
+                name: source.name,
+                kind: source.kind,
+                url: source.url.trim_end_matches('/').to_string(),
+            });
+        }
+        if sources.is_empty() {
+            sources.push(SurveillanceSource {
+                name: DEFAULT_SOURCE_NAME.to_string(),
+                kind: SurveillanceKind::AdsbLol,
+                url: DEFAULT_SOURCE_URL.to_string(),
+            });
+        }
+
+        if file.airports.is_empty() {
+            problems.push("at least one [[airports]] entry is required".to_string());
+        }
+        let mut codes = HashSet::new();
+        let mut airports = Vec::new();
+        for (i, airport) in file.airports.into_iter().enumerate() {
+            let code = airport.code.trim().to_ascii_uppercase();
+            let at = format!("airports[{}] ({})", i, code);
+
+            if code.len() != 4 || !code.chars().all(|c| c.is_ascii_alphanumeric()) {
+                problems.push(format!("{}: code must be a 4-character ICAO code", at));
+            }
+            if !codes.insert(code.clone()) {
+                problems.push(format!("{}: duplicate airport code", at));
+            }
+            if !(-90.0..=90.0).contains(&airport.lat) {
+                problems.push(format!("{}: lat must be between -90 and 90, got {}", at, airport.lat));
+            }
+            if !(-180.0..=180.0).contains(&airport.lon) {
+                problems.push(format!("{}: lon must be between -180 and 180, got {}", at, airport.lon));
+            }
+
+            let radius = airport.radius_nm.unwrap_or(DEFAULT_RADIUS_NM);
+            if radius == 0 || radius > MAX_RADIUS_NM {
+                problems.push(format!("{}: radius_nm must be between 1 and {}, got {}", at, MAX_RADIUS_NM, radius));
+            }
+
+            let spurious_ground_km = airport.spurious_ground_km.unwrap_or(DEFAULT_SPURIOUS_GROUND_KM);
+            if spurious_ground_km <= 0.0 || !spurious_ground_km.is_finite() {
+                problems.push(format!("{}: spurious_ground_km must be positive, got {}", at, spurious_ground_km));
+            }
+
+            let stale_after_s = airport.stale_after_s.unwrap_or(DEFAULT_STALE_AFTER_S);
+            if stale_after_s <= 0 {
+                problems.push(format!("{}: stale_after_s must be positive, got {}", at, stale_after_s));
+            }
+
+            let airport_poll_s = airport.poll_interval_s.unwrap_or(poll_interval_s);
+            if airport_poll_s == 0 {
+                problems.push(format!("{}: poll_interval_s must be at least 1", at));
+            }
+            if airport_poll_s as i64 >= stale_after_s {
+                problems.push(format!(
+                    "{}: stale_after_s ({}) must be longer than the poll interval ({} s)",
+                    at, stale_after_s, airport_poll_s
+                ));
+            }
+
+            let surveillance = match &airport.surveillance {
+                Some(name) => sources.iter().find(|s| &s.name == name).cloned().unwrap_or_else(|| {
+                    problems.push(format!("{}: unknown surveillance source {:?}", at, name));
+                    sources[0].clone()
+                }),
+                None => sources[0].clone(),
+            };
+
+            // An explicit directory must exist; the default may simply have no ground data
+            let data_dir = match airport.data_dir {
+                Some(dir) => {
+                    let dir = resolve(dir);
+                    if !dir.is_dir() {
+                        problems.push(format!("{}: data_dir {} is not a directory", at, dir.display()));
+                    }
+                    dir
+                }
+                None => airport_data_dir.join(&code),
+            };
+
+            airports.push(AirportConfig {
+                code,
+                lat: airport.lat,
+                lon: airport.lon,
+                radius,
+                data_dir,
+                runways: airport.runways,
+                separation: airport.separation.unwrap_or_default(),
+                spurious_ground_km,
+                stale_after_s,
+                surveillance,
+                poll_interval_s: airport_poll_s,
+            });
+        }
+
+        for (i, controller) in file.controllers.iter().enumerate() {
+            let at = format!("controllers[{}] ({})", i, controller.name);
+            if controller.name.trim().is_empty() {
+                problems.push(format!("controllers[{}]: name must not be empty", i));
+            }
+            if controller.token.len() < MIN_TOKEN_LEN {
+                problems.push(format!("{}: token must be at least {} characters", at, MIN_TOKEN_LEN));
+            }
+            if file.controllers[..i].iter().any(|c| c.name == controller.name) {
+                problems.push(format!("{}: duplicate controller name", at));
+            }
+            if file.controllers[..i].iter().any(|c| c.token == controller.token) {
+                problems.push(format!("{}: token is already used by another controller", at));
+            }
+        }
+
+        if !problems.is_empty() {
+            return Err(ConfigError::Invalid { path, problems });
+        }
+
+        Ok(Config {
             server_port,
-            airports: vec![
-                AirportConfig {
-                    code: "EGSS".to_string(),
-                    lat: 51.885,
-                    lon: 0.235,
-                    radius: 25,
-                },
-                AirportConfig {
-                    code: "KLAX".to_string(),
-                    lat: 33.942,
-                    lon: -118.407,
-                    radius: 25,
+            weather_source,
+            airports,
+            controllers: file.controllers,
+            database,
+            path,
+        })
+    }
+
+    /// Controller holding `token`. Every token is compared in full so timing does not
+    /// reveal how much of a guess matched.
+    pub fn controller(&self, token: &str) -> Option<&Controller> {
+        let matches = |known: &str| {
+            known.len() == token.len() && known.bytes().zip(token.bytes()).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+        };
+        self.controllers.iter().fold(None, |found, c| if matches(&c.token) { Some(c) } else { found })
+    }
+
+    pub fn airport(&self, code: &str) -> Option<&AirportConfig> {
+        self.airports.iter().find(|a| a.code.eq_ignore_ascii_case(code))
+    }
+
+    /// Checks configured runways against the loaded ground data, which is only
+    /// available once the sector files have been read.
+    pub fn check_runways(&self, data: &AirportData) -> Result<(), ConfigError> {
+        let mut problems = Vec::new();
+        for (i, airport) in self.airports.iter().enumerate() {
+            let Some(runways) = &airport.runways else {
+                continue;
+            };
+            let at = format!("airports[{}] ({})", i, airport.code);
+            let Some(ground) = data.get(&airport.code) else {
+                problems.push(format!("{}: runways are set but no ground data was loaded from {}", at, airport.data_dir.display()));
+                continue;
+            };
+            for end in [&runways.arrival, &runways.departure] {
+                if ground.runway_end(end).is_none() {
+                    let known: Vec<&str> = ground.runways.iter().flat_map(|r| r.ends.iter().map(|e| e.name.as_str())).collect();
+                    problems.push(format!("{}: unknown runway end {:?} (known: {})", at, end, known.join(", ")));
                 }
-            ],
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError::Invalid { path: self.path.clone(), problems })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AIRPORT: &str = "[[airports]]\ncode = \"EGSS\"\nlat = 51.885\nlon = 0.235\n";
+
+    fn problems(text: &str) -> Vec<String> {
+        match Config::parse(text, Some(PathBuf::from("/srv/atc/atc.toml"))) {
+            Err(ConfigError::Invalid { problems, .. }) => problems,
+            other => panic!("expected validation problems, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn defaults_are_valid() {
+        let config = Config::parse(DEFAULT_CONFIG, None).unwrap();
+        assert_eq!(config.airports.len(), 2);
+        assert_eq!(config.airports[0].radius, DEFAULT_RADIUS_NM);
+        assert_eq!(config.airports[0].surveillance.url, DEFAULT_SOURCE_URL);
+        assert!(config.controllers.is_empty());
+    }
+
+    #[test]
+    fn reports_each_problem() {
+        let controllers = "[[controllers]]\nname = \"tower\"\ntoken = \"tower-token-0123456789\"\n";
+        let cases: Vec<(String, String)> = vec![
+            (
+                format!("poll_interval_s = 0\n{}poll_interval_s = 2\n", AIRPORT),
+                "poll_interval_s must be at least 1".to_string(),
+            ),
+            (String::new(), "at least one [[airports]] entry is required".to_string()),
+            (
+                "[[surveillance]]\nname = \" \"\nkind = \"adsb_lol\"\nurl = \"https://a\"\n".to_string(),
+                "surveillance[0]: name must not be empty".to_string(),
+            ),
+            (
+                "[[surveillance]]\nname = \"a\"\nkind = \"adsb_lol\"\nurl = \"https://a\"\n\
+                 [[surveillance]]\nname = \"a\"\nkind = \"adsb_lol\"\nurl = \"https://b\"\n".to_string(),
+                "surveillance[1] (a): duplicate source name".to_string(),
+            ),
+            (
+                "[[surveillance]]\nname = \"a\"\nkind = \"adsb_lol\"\nurl = \"ftp://a\"\n".to_string(),
+                "surveillance[0] (a): url must start with http:// or https://, got \"ftp://a\"".to_string(),
+            ),
+            (
+                "[[airports]]\ncode = \"EGS\"\nlat = 0.0\nlon = 0.0\n".to_string(),
+                "airports[0] (EGS): code must be a 4-character ICAO code".to_string(),
+            ),
+            (
+                "[[airports]]\ncode = \"eg-s\"\nlat = 0.0\nlon = 0.0\n".to_string(),
+                "airports[0] (EG-S): code must be a 4-character ICAO code".to_string(),
+            ),
+            (
+                format!("{}[[airports]]\ncode = \"egss\"\nlat = 0.0\nlon = 0.0\n", AIRPORT),
+                "airports[1] (EGSS): duplicate airport code".to_string(),
+            ),
+            (
+                "[[airports]]\ncode = \"EGSS\"\nlat = 91.0\nlon = 0.0\n".to_string(),
+                "airports[0] (EGSS): lat must be between -90 and 90, got 91".to_string(),
+            ),
+            (
+                "[[airports]]\ncode = \"EGSS\"\nlat = 0.0\nlon = -180.5\n".to_string(),
+                "airports[0] (EGSS): lon must be between -180 and 180, got -180.5".to_string(),
+            ),
+            (
+                format!("{}radius_nm = 0\n", AIRPORT),
+                format!("airports[0] (EGSS): radius_nm must be between 1 and {}, got 0", MAX_RADIUS_NM),
+            ),
+            (
+                format!("{}radius_nm = 251\n", AIRPORT),
+                format!("airports[0] (EGSS): radius_nm must be between 1 and {}, got 251", MAX_RADIUS_NM),
+            ),
+            (
+                format!("{}spurious_ground_km = -1.0\n", AIRPORT),
+                "airports[0] (EGSS): spurious_ground_km must be positive, got -1".to_string(),
+            ),
+            (
+                format!("{}poll_interval_s = 0\n", AIRPORT),
+                "airports[0] (EGSS): poll_interval_s must be at least 1".to_string(),
+            ),
+            (
+                format!("{}poll_interval_s = 60\nstale_after_s = 60\n", AIRPORT),
+                "airports[0] (EGSS): stale_after_s (60) must be longer than the poll interval (60 s)".to_string(),
+            ),
+            (
+                format!("{}surveillance = \"opensky\"\n", AIRPORT),
+                "airports[0] (EGSS): unknown surveillance source \"opensky\"".to_string(),
+            ),
+            (
+                format!("{}data_dir = \"no-such-dir\"\n", AIRPORT),
+                "airports[0] (EGSS): data_dir /srv/atc/no-such-dir is not a directory".to_string(),
+            ),
+            (
+                format!("{}[[controllers]]\nname = \"\"\ntoken = \"tower-token-0123456789\"\n", AIRPORT),
+                "controllers[0]: name must not be empty".to_string(),
+            ),
+            (
+                format!("{}[[controllers]]\nname = \"tower\"\ntoken = \"short\"\n", AIRPORT),
+                format!("controllers[0] (tower): token must be at least {} characters", MIN_TOKEN_LEN),
+            ),
+            (
+                format!("{}{}[[controllers]]\nname = \"tower\"\ntoken = \"ground-token-0123456789\"\n", AIRPORT, controllers),
+                "controllers[1] (tower): duplicate controller name".to_string(),
+            ),
+            (
+                format!("{}{}[[controllers]]\nname = \"ground\"\ntoken = \"tower-token-0123456789\"\n", AIRPORT, controllers),
+                "controllers[1] (ground): token is already used by another controller".to_string(),
+            ),
+        ];
+        for (text, expected) in cases {
+            // Surveillance cases still need an airport to be otherwise valid
+            let text = if text.starts_with("[[surveillance]]") { text + AIRPORT } else { text };
+            assert_eq!(problems(&text), vec![expected], "{}", text);
+        }
+    }
+
+    #[test]
+    fn a_non_positive_stale_time_is_also_shorter_than_the_poll_interval() {
+        assert_eq!(problems(&format!("{}stale_after_s = 0\n", AIRPORT)), vec![
+            "airports[0] (EGSS): stale_after_s must be positive, got 0",
+            "airports[0] (EGSS): stale_after_s (0) must be longer than the poll interval (2 s)",
+        ]);
+    }
+
+    #[test]
+    fn reports_every_problem_at_once() {
+        let found = problems("[[airports]]\ncode = \"X\"\nlat = 100.0\nlon = 0.0\nspurious_ground_km = 0.0\n");
+        assert_eq!(found, vec![
+            "airports[0] (X): code must be a 4-character ICAO code",
+            "airports[0] (X): lat must be between -90 and 90, got 100",
+            "airports[0] (X): spurious_ground_km must be positive, got 0",
+        ]);
+    }
+
+    #[test]
+    fn syntax_errors_and_unknown_keys_are_parse_errors() {
+        for text in ["server_port = ", "server_prot = 3000", "[[airports]]\ncode = \"EGSS\"\nlat = 1.0\nlon = 1.0\nradius = 5\n"] {
+            let result = Config::parse(text, Some(PathBuf::from("/srv/atc/atc.toml")));
+            assert!(matches!(result, Err(ConfigError::Parse { .. })), "{}", text);
+        }
+    }
+
+    #[test]
+    fn relative_paths_resolve_against_the_config_file() {
+        let manifest = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let text = format!(
+            "airport_data_dir = \"data\"\nweather_source = \"wx/weather.txt\"\ndatabase = \"log.db\"\n\
+             {}data_dir = \"src\"\n[[airports]]\ncode = \"KLAX\"\nlat = 33.942\nlon = -118.407\n",
+            AIRPORT
+        );
+        let config = Config::parse(&text, Some(manifest.join("atc.toml"))).unwrap();
+        assert_eq!(config.airports[0].data_dir, manifest.join("src"));
+        assert_eq!(config.airports[1].data_dir, manifest.join("data").join("KLAX"));
+        if env::var("WEATHER_SOURCE").is_err() {
+            assert_eq!(config.weather_source, WeatherSource::File(manifest.join("wx/weather.txt")));
+        }
+        if env::var("DATABASE_PATH").is_err() {
+            assert_eq!(config.database, manifest.join("log.db"));
+        }
+    }
+
+    #[test]
+    fn absolute_paths_and_urls_are_kept() {
+        let text = format!(
+            "airport_data_dir = \"/opt/data\"\nweather_source = \"http://localhost:8099/\"\ndatabase = \"/var/lib/atc/log.db\"\n{}",
+            AIRPORT
+        );
+        let config = Config::parse(&text, Some(PathBuf::from("/srv/atc/atc.toml"))).unwrap();
+        assert_eq!(config.airports[0].data_dir, Path::new("/opt/data/EGSS"));
+        if env::var("WEATHER_SOURCE").is_err() {
+            assert_eq!(config.weather_source, WeatherSource::Http("http://localhost:8099".to_string()));
+        }
+        if env::var("DATABASE_PATH").is_err() {
+            assert_eq!(config.database, Path::new("/var/lib/atc/log.db"));
+        }
+    }
+
+    #[test]
+    fn built_in_defaults_keep_relative_paths() {
+        let config = Config::parse(&format!("database = \"log.db\"\n{}", AIRPORT), None).unwrap();
+        if env::var("DATABASE_PATH").is_err() {
+            assert_eq!(config.database, Path::new("log.db"));
         }
     }
 }
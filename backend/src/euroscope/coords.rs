@@ -0,0 +1,97 @@
+use crate::logic::airport::Coordinate;
+
+/// Parses a single EuroScope coordinate such as `N051.53.05.169` or `E000.13.50.011`.
+///
+/// Degrees may be written with two or three digits (`N51.11.56.00` appears in the UK
+/// sector file), and the decimal degree form `N051.885` is accepted as well.
+/// Returns `None` for anything else, including the `S999.00.00.000` placeholders
+/// EuroScope uses for section headers.
+pub fn parse_coordinate(token: &str) -> Option<f64> {
+    let token = token.trim();
+    let mut chars = token.chars();
+    let hemisphere = chars.next()?.to_ascii_uppercase();
+    let rest = chars.as_str();
+
+    let (sign, max) = match hemisphere {
+        'N' => (1.0, 90.0),
+        'S' => (-1.0, 90.0),
+        'E' => (1.0, 180.0),
+        'W' => (-1.0, 180.0),
+        _ => return None,
+    };
+
+    let parts: Vec<&str> = rest.split('.').collect();
+    let value = match parts.as_slice() {
+        [deg, min, sec, frac] => {
+            let deg: f64 = deg.parse().ok()?;
+            let min: f64 = min.parse().ok()?;
+            let sec: f64 = format!("{}.{}", sec, frac).parse().ok()?;
+            if min >= 60.0 || sec >= 60.0 {
+                return None;
+            }
+            deg + min / 60.0 + sec / 3600.0
+        }
+        [deg, min, sec] => {
+            let deg: f64 = deg.parse().ok()?;
+            let min: f64 = min.parse().ok()?;
+            let sec: f64 = sec.parse().ok()?;
+            if min >= 60.0 || sec >= 60.0 {
+                return None;
+            }
+            deg + min / 60.0 + sec / 3600.0
+        }
+        [deg, frac] => format!("{}.{}", deg, frac).parse().ok()?,
+        _ => return None,
+    };
+
+    if value > max {
+        return None;
+    }
+
+    Some(sign * value)
+}
+
+/// Parses a latitude/longitude token pair, e.g. `N051.53.05.169 E000.13.50.011`.
+pub fn parse_position(lat: &str, lon: &str) -> Option<Coordinate> {
+    if !matches!(lat.chars().next(), Some('N' | 'S' | 'n' | 's')) {
+        return None;
+    }
+    if !matches!(lon.chars().next(), Some('E' | 'W' | 'e' | 'w')) {
+        return None;
+    }
+
+    Some(Coordinate {
+        lat: parse_coordinate(lat)?,
+        lon: parse_coordinate(lon)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_degrees_minutes_seconds_and_decimal_degrees() {
+        for (token, expected) in [
+            ("N051.53.00.000", 51.883333),
+            ("E000.13.22.300", 0.222861),
+            ("W000.03.09.520", -0.052644),
+            ("S33.52.00.00", -33.866667),
+            ("n051.53.00", 51.883333),
+            ("N051.885", 51.885),
+        ] {
+            let value = parse_coordinate(token).unwrap();
+            assert!((value - expected).abs() < 1e-6, "{} gave {}", token, value);
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_coordinates() {
+        for token in ["", "N", "X051.53.00.000", "N051.60.00.000", "N051.53.60.000", "Nabc.53.00.000", "N051.53.00.000.0", "S999.00.00.000", "E181.00.00.000"] {
+            assert_eq!(parse_coordinate(token), None, "{}", token);
+        }
+        // Latitude and longitude must come in that order
+        assert!(parse_position("E000.13.22.300", "N051.52.37.340").is_none());
+        assert!(parse_position("N051.52.37.340", "E000.13.22.300").is_some());
+    }
+}
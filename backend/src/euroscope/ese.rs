@@ -0,0 +1,43 @@
+use super::{Procedure, ProcedureKind};
+
+/// Reads the `[SIDSSTARS]` section of an `.ese` file for one airport.
+///
+/// `STAR:EGSS:22:ABBOT1Z:ABBOT TABIS BUSTA LOREL`
+pub fn parse_procedures(text: &str, icao: &str) -> Vec<Procedure> {
+    let mut procedures = Vec::new();
+    let mut in_section = false;
+
+    for raw in text.lines() {
+        let line = raw.trim();
+        if line.starts_with('[') {
+            in_section = line.eq_ignore_ascii_case("[SIDSSTARS]");
+            continue;
+        }
+        if !in_section || line.starts_with(';') {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.splitn(5, ':').collect();
+        let [kind, airport, runway, name, route] = fields.as_slice() else {
+            continue;
+        };
+        if !airport.eq_ignore_ascii_case(icao) {
+            continue;
+        }
+
+        let kind = match *kind {
+            "SID" => ProcedureKind::Sid,
+            "STAR" => ProcedureKind::Star,
+            _ => continue,
+        };
+
+        procedures.push(Procedure {
+            kind,
+            runway: runway.to_string(),
+            name: name.to_string(),
+            fixes: route.split_whitespace().map(|f| f.to_string()).collect(),
+        });
+    }
+
+    procedures
+}
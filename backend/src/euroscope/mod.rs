@@ -0,0 +1,206 @@
+//! Readers for EuroScope sector data.
+//!
+//! An airport directory such as `AirportData/EGSS` holds the per-airport SMR fragments
+//! from the VATSIM UK sector file (`Geo.txt`, `Labels.txt`, `Regions.txt`, `Runway.txt`)
+//! alongside the compiled `.sct`, `.ese` and `.rwy` files. Fragments have no section
+//! headers (`Fixes.txt` carries the `[FIXES]` entries the airport needs, such as
+//! its holding fixes); a full `.sct` is only consulted for its `[AIRPORT]` and `[RUNWAY]` entries,
+//! since its SMR sections cover every airport in the package. Optional site files such
+//! as `Zones.txt` and `Aerodrome.txt` add airport knowledge that has no home in the
+//! sector file.
+
+pub mod coords;
+pub mod ese;
+pub mod rwy;
+pub mod sct;
+pub mod site;
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::logic::airport::Coordinate;
+
+/// A single line from a `[GEO]` section, e.g. a taxiway centreline segment.
+#[derive(Debug, Clone)]
+pub struct GeoSegment {
+    pub from: Coordinate,
+    pub to: Coordinate,
+    pub colour: String,
+}
+
+/// Consecutive `[GEO]` lines grouped under a `;Section` / `; - Name` comment pair.
+#[derive(Debug, Clone)]
+pub struct GeoFeature {
+    pub section: String,
+    pub name: String,
+    pub segments: Vec<GeoSegment>,
+}
+
+/// A `[LABELS]` entry, e.g. `"H1" N051.52.29.850 E000.13.16.200 smrStandHold`.
+#[derive(Debug, Clone)]
+pub struct Label {
+    pub section: String,
+    pub text: String,
+    pub position: Coordinate,
+}
+
+/// A `[FIXES]` entry, e.g. `ABBOT N052.00.58.000 E000.35.58.490`.
+#[derive(Debug, Clone)]
+pub struct Fix {
+    pub name: String,
+    pub position: Coordinate,
+}
+
+/// A filled `[REGIONS]` polygon.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Region {
+    pub name: String,
+    pub colour: String,
+    pub points: Vec<Coordinate>,
+}
+
+/// A `[RUNWAY]` line describing both ends of one physical runway.
+#[derive(Debug, Clone)]
+pub struct RunwayLine {
+    pub designators: [String; 2],
+    pub thresholds: [Coordinate; 2],
+    pub airport: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProcedureKind {
+    Sid,
+    Star,
+}
+
+/// A SID or STAR from the `.ese` `[SIDSSTARS]` section.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Procedure {
+    pub kind: ProcedureKind,
+    pub runway: String,
+    pub name: String,
+    pub fixes: Vec<String>,
+}
+
+/// An `ACTIVE_RUNWAY` entry from the `.rwy` file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActiveRunway {
+    pub runway: String,
+    pub departure: bool,
+}
+
+/// A `Zones.txt` entry attaching semantic tags to a named zone.
+#[derive(Debug, Clone)]
+pub struct ZoneTags {
+    pub kind: String,
+    pub name: String,
+    pub tags: Vec<String>,
+}
+
+/// Physical data for one runway end from `Aerodrome.txt`.
+#[derive(Debug, Clone)]
+pub struct RunwayEndInfo {
+    pub designator: String,
+    pub width_m: Option<f64>,
+    pub displaced_m: Option<f64>,
+    pub elevation_ft: Option<f64>,
+    pub glideslope_deg: Option<f64>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AerodromeInfo {
+    pub elevation_ft: Option<f64>,
+    pub runway_ends: Vec<RunwayEndInfo>,
+    /// (normal hold, CAT II/III hold) pairs used under low visibility procedures.
+    pub lvp_holds: Vec<(String, String)>,
+    /// Fixes with a published arrival holding pattern.
+    pub stacks: Vec<String>,
+}
+
+/// Everything read from one airport directory, already filtered to that airport.
+#[derive(Debug, Clone, Default)]
+pub struct SectorData {
+    pub icao: String,
+    pub reference: Option<Coordinate>,
+    pub geo: Vec<GeoFeature>,
+    pub labels: Vec<Label>,
+    pub fixes: Vec<Fix>,
+    pub regions: Vec<Region>,
+    pub runways: Vec<RunwayLine>,
+    pub procedures: Vec<Procedure>,
+    pub active_runways: Vec<ActiveRunway>,
+    pub zone_tags: Vec<ZoneTags>,
+    pub aerodrome: AerodromeInfo,
+}
+
+#[derive(Debug)]
+pub enum SectorError {
+    Io { path: PathBuf, source: std::io::Error },
+    NoSectorFiles(PathBuf),
+}
+
+impl fmt::Display for SectorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SectorError::Io { path, source } => write!(f, "failed to read {}: {}", path.display(), source),
+            SectorError::NoSectorFiles(dir) => write!(f, "no sector files found in {}", dir.display()),
+        }
+    }
+}
+
+impl std::error::Error for SectorError {}
+
+/// Reads every recognised sector file in `dir` for the airport `icao`.
+pub fn load_sector_dir(dir: &Path, icao: &str) -> Result<SectorData, SectorError> {
+    let entries = fs::read_dir(dir).map_err(|source| SectorError::Io { path: dir.to_path_buf(), source })?;
+
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.is_file())
+        .collect();
+    paths.sort();
+
+    let mut data = SectorData {
+        icao: icao.to_string(),
+        ..Default::default()
+    };
+    let mut recognised = 0;
+
+    for path in paths {
+        let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default().to_ascii_lowercase();
+        let extension = path.extension().and_then(|e| e.to_str()).unwrap_or_default().to_ascii_lowercase();
+
+        match (file_name.as_str(), extension.as_str()) {
+            ("geo.txt", _) => data.geo.extend(sct::parse_geo(&read_latin1(&path)?)),
+            ("labels.txt", _) => data.labels.extend(sct::parse_labels(&read_latin1(&path)?)),
+            ("fixes.txt", _) => data.fixes.extend(sct::parse_fixes(&read_latin1(&path)?)),
+            ("regions.txt", _) => data.regions.extend(sct::parse_regions(&read_latin1(&path)?)),
+            ("aerodrome.txt", _) => data.aerodrome = site::parse_aerodrome(&read_latin1(&path)?),
+            ("zones.txt", _) => data.zone_tags.extend(site::parse_zone_tags(&read_latin1(&path)?)),
+            ("runway.txt", _) => data.runways.extend(sct::parse_runways(&read_latin1(&path)?, icao)),
+            (_, "sct") => {
+                let full = sct::parse_sector_file(&read_latin1(&path)?, icao);
+                data.runways.extend(full.runways);
+                data.reference = data.reference.or(full.reference);
+            }
+            (_, "ese") => data.procedures.extend(ese::parse_procedures(&read_latin1(&path)?, icao)),
+            (_, "rwy") => data.active_runways.extend(rwy::parse_active_runways(&read_latin1(&path)?, icao)),
+            _ => continue,
+        }
+        recognised += 1;
+    }
+
+    if recognised == 0 {
+        return Err(SectorError::NoSectorFiles(dir.to_path_buf()));
+    }
+
+    Ok(data)
+}
+
+/// Sector files are distributed as Windows-1252/Latin-1, not UTF-8.
+fn read_latin1(path: &Path) -> Result<String, SectorError> {
+    let bytes = fs::read(path).map_err(|source| SectorError::Io { path: path.to_path_buf(), source })?;
+    Ok(bytes.iter().map(|&b| b as char).collect())
+}
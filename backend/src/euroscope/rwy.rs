@@ -0,0 +1,18 @@
+use super::ActiveRunway;
+
+/// Reads `ACTIVE_RUNWAY:<icao>:<runway>:<flag>` entries, where a flag of `1` marks the
+/// departure runway and `0` the arrival runway.
+pub fn parse_active_runways(text: &str, icao: &str) -> Vec<ActiveRunway> {
+    text.lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.trim().split(':').collect();
+            match fields.as_slice() {
+                ["ACTIVE_RUNWAY", airport, runway, flag] if airport.eq_ignore_ascii_case(icao) => Some(ActiveRunway {
+                    runway: runway.to_string(),
+                    departure: *flag == "1",
+                }),
+                _ => None,
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,308 @@
+use super::coords::{parse_coordinate, parse_position};
+use super::{Fix, GeoFeature, GeoSegment, Label, Region, RunwayLine};
+use crate::logic::airport::Coordinate;
+
+/// The subset of a full `.sct` file that is specific to one airport.
+#[derive(Debug, Default)]
+pub struct SectorFile {
+    pub reference: Option<Coordinate>,
+    pub runways: Vec<RunwayLine>,
+}
+
+/// Parses a `[GEO]` fragment such as `Geo.txt`.
+///
+/// `;Heading` comments open a new section and `; - Name` comments name the feature
+/// that follows; commented-out coordinate lines are disabled geometry and are skipped.
+pub fn parse_geo(text: &str) -> Vec<GeoFeature> {
+    let mut features: Vec<GeoFeature> = Vec::new();
+    let mut section = String::new();
+    let mut name = String::new();
+
+    for raw in text.lines() {
+        let line = raw.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(comment) = line.strip_prefix(';') {
+            if parse_geo_line(comment).is_some() {
+                continue;
+            }
+            if let Some(feature_name) = comment.strip_prefix(" - ") {
+                name = feature_name.trim().to_string();
+            } else {
+                section = comment.trim().to_string();
+                name = section.clone();
+            }
+            continue;
+        }
+
+        let Some(segment) = parse_geo_line(line) else {
+            continue;
+        };
+
+        match features.last_mut() {
+            Some(last) if last.section == section && last.name == name => last.segments.push(segment),
+            _ => features.push(GeoFeature {
+                section: section.clone(),
+                name: name.clone(),
+                segments: vec![segment],
+            }),
+        }
+    }
+
+    features
+}
+
+fn parse_geo_line(line: &str) -> Option<GeoSegment> {
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+    if tokens.len() < 4 {
+        return None;
+    }
+
+    Some(GeoSegment {
+        from: parse_position(tokens[0], tokens[1])?,
+        to: parse_position(tokens[2], tokens[3])?,
+        colour: tokens.get(4).map(|c| c.to_string()).unwrap_or_default(),
+    })
+}
+
+/// Parses a `[LABELS]` fragment such as `Labels.txt`, using `;Heading` comments as sections.
+pub fn parse_labels(text: &str) -> Vec<Label> {
+    let mut labels = Vec::new();
+    let mut section = String::new();
+
+    for raw in text.lines() {
+        let line = raw.trim();
+        if let Some(comment) = line.strip_prefix(';') {
+            section = comment.trim().to_string();
+            continue;
+        }
+
+        let Some(rest) = line.strip_prefix('"') else {
+            continue;
+        };
+        let Some((label, rest)) = rest.split_once('"') else {
+            continue;
+        };
+
+        let tokens: Vec<&str> = rest.split_whitespace().collect();
+        if tokens.len() < 2 {
+            continue;
+        }
+        let Some(position) = parse_position(tokens[0], tokens[1]) else {
+            continue;
+        };
+
+        labels.push(Label {
+            section: section.clone(),
+            text: label.to_string(),
+            position,
+        });
+    }
+
+    labels
+}
+
+/// Parses a `[FIXES]` fragment such as `Fixes.txt`: `NAME <lat> <lon>` per line.
+pub fn parse_fixes(text: &str) -> Vec<Fix> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with(';'))
+        .filter_map(|line| {
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            if tokens.len() < 3 {
+                return None;
+            }
+            Some(Fix {
+                name: tokens[0].to_string(),
+                position: parse_position(tokens[1], tokens[2])?,
+            })
+        })
+        .collect()
+}
+
+/// Parses a `[REGIONS]` fragment such as `Regions.txt`.
+///
+/// A region starts at `REGIONNAME`; its first point carries the fill colour and the
+/// remaining points follow one per line. A preceding `; - Name` comment is preferred
+/// over the `REGIONNAME`, which is usually just the airport name.
+pub fn parse_regions(text: &str) -> Vec<Region> {
+    let mut regions: Vec<Region> = Vec::new();
+    let mut pending_name: Option<String> = None;
+
+    for raw in text.lines() {
+        let line = raw.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(comment) = line.strip_prefix(';') {
+            let comment = comment.trim();
+            pending_name = Some(comment.strip_prefix("- ").unwrap_or(comment).trim().to_string());
+            continue;
+        }
+
+        if let Some(region_name) = line.strip_prefix("REGIONNAME") {
+            regions.push(Region {
+                name: pending_name.take().unwrap_or_else(|| region_name.trim().to_string()),
+                colour: String::new(),
+                points: Vec::new(),
+            });
+            continue;
+        }
+
+        let Some(region) = regions.last_mut() else {
+            continue;
+        };
+
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        match tokens.as_slice() {
+            [colour, lat, lon, ..] if parse_coordinate(colour).is_none() => {
+                if let Some(point) = parse_position(lat, lon) {
+                    region.colour = colour.to_string();
+                    region.points.push(point);
+                }
+            }
+            [lat, lon, ..] => {
+                if let Some(point) = parse_position(lat, lon) {
+                    region.points.push(point);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    regions.retain(|r| r.points.len() >= 3);
+    regions
+}
+
+/// Parses `[RUNWAY]` lines, keeping those for `icao` or with no airport given.
+///
+/// `04 22 042 222 N051.52.37.340 E000.13.22.300 N051.53.42.570 E000.15.00.160 EGSS London Stansted`
+///
+/// The magnetic headings are ignored; true headings are derived from the thresholds.
+pub fn parse_runways(text: &str, icao: &str) -> Vec<RunwayLine> {
+    text.lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            if line.starts_with(';') {
+                return None;
+            }
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            if tokens.len() < 8 {
+                return None;
+            }
+
+            let runway = RunwayLine {
+                designators: [tokens[0].to_string(), tokens[1].to_string()],
+                thresholds: [parse_position(tokens[4], tokens[5])?, parse_position(tokens[6], tokens[7])?],
+                airport: tokens.get(8).map(|a| a.to_string()),
+            };
+
+            match runway.airport.as_deref() {
+                Some(a) if !a.eq_ignore_ascii_case(icao) => None,
+                _ => Some(runway),
+            }
+        })
+        .collect()
+}
+
+/// Extracts the `[AIRPORT]` reference point and `[RUNWAY]` lines for `icao` from a full `.sct`.
+pub fn parse_sector_file(text: &str, icao: &str) -> SectorFile {
+    let mut result = SectorFile::default();
+    let mut section = String::new();
+    let mut runway_lines = String::new();
+
+    for raw in text.lines() {
+        let line = raw.trim();
+        if line.starts_with('[') && line.ends_with(']') {
+            section = line.to_ascii_uppercase();
+            continue;
+        }
+
+        match section.as_str() {
+            "[AIRPORT]" => {
+                // EGSS 123.805 N051.53.06.000 E000.14.06.000 E
+                let tokens: Vec<&str> = line.split_whitespace().collect();
+                if tokens.len() >= 4 && tokens[0].eq_ignore_ascii_case(icao) {
+                    result.reference = parse_position(tokens[2], tokens[3]);
+                }
+            }
+            "[RUNWAY]" => {
+                runway_lines.push_str(line);
+                runway_lines.push('\n');
+            }
+            _ => {}
+        }
+    }
+
+    result.runways = parse_runways(&runway_lines, icao)
+        .into_iter()
+        .filter(|r| r.airport.is_some())
+        .collect();
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::euroscope::SectorData;
+    use crate::logic::airport::Airport;
+
+    const GEO: &str = "\
+;Taxiway Centrelines
+; - J
+; - Taxiway_Centrelines_-__-_J
+N051.52.48.000 E000.13.48.000 N051.52.48.000 E000.14.00.000 smrSSWhite
+;N051.52.48.000 E000.14.00.000 N051.52.48.000 E000.14.12.000 smrSSWhite
+N051.52.48.000 E000.14.00.000 N051.52.48.000 E000.14.12.000 smrSSWhite
+";
+    const LABELS: &str = "\
+;Holding Points
+\"H1\" N051.52.48.000 E000.14.12.000 smrStandHold
+;Stands
+\"10\" N051.52.46.000 E000.13.48.000 smrStand
+\"bad\" N051.99.00.000 E000.13.48.000 smrStand
+";
+    const RUNWAYS: &str = "\
+; comment
+04 22 042 222 N051.52.37.340 E000.13.22.300 N051.53.42.570 E000.15.00.160 EGSS London Stansted
+05 23 052 232 N051.08.48.000 W000.11.26.000 N051.09.44.000 W000.09.38.000 EGKK London Gatwick
+";
+
+    #[test]
+    fn parses_a_runway_a_stand_and_a_taxiway() {
+        let geo = parse_geo(GEO);
+        assert_eq!(geo.len(), 1);
+        assert_eq!((geo[0].section.as_str(), geo[0].name.as_str()), ("Taxiway Centrelines", "Taxiway_Centrelines_-__-_J"));
+        // The commented-out segment is disabled geometry
+        assert_eq!(geo[0].segments.len(), 2);
+        assert_eq!(geo[0].segments[0].colour, "smrSSWhite");
+
+        let labels = parse_labels(LABELS);
+        let names: Vec<(&str, &str)> = labels.iter().map(|l| (l.section.as_str(), l.text.as_str())).collect();
+        assert_eq!(names, [("Holding Points", "H1"), ("Stands", "10")]);
+
+        let runways = parse_runways(RUNWAYS, "EGSS");
+        assert_eq!(runways.len(), 1);
+        assert_eq!(runways[0].designators, ["04".to_string(), "22".to_string()]);
+        assert!((runways[0].thresholds[0].lat - 51.877039).abs() < 1e-6);
+        assert!((runways[0].thresholds[1].lon - 0.250044).abs() < 1e-6);
+
+        let airport = Airport::from_sector(&SectorData { icao: "EGSS".to_string(), geo, labels, runways, ..Default::default() });
+        assert!(airport.runway_end("04").is_some() && airport.runway_end("22").is_some());
+        assert_eq!(airport.stands.iter().map(|s| s.name.as_str()).collect::<Vec<_>>(), ["10"]);
+        assert_eq!(airport.holds.iter().map(|h| h.name.as_str()).collect::<Vec<_>>(), ["H1"]);
+        assert_eq!(airport.taxiways.iter().map(|t| t.name.as_str()).collect::<Vec<_>>(), ["J"]);
+    }
+
+    #[test]
+    fn takes_the_airport_and_its_runways_from_a_full_sector_file() {
+        let text = format!("[AIRPORT]\nEGKK 126.830 N051.08.53.000 W000.11.25.000 E\nEGSS 123.805 N051.53.06.000 E000.14.06.000 E\n\n[RUNWAY]\n{}\n[VOR]\nBPK 117.500 N051.44.59.600 W000.06.24.300\n", RUNWAYS);
+        let sector = parse_sector_file(&text, "egss");
+        let reference = sector.reference.unwrap();
+        assert!((reference.lat - 51.885).abs() < 1e-6 && (reference.lon - 0.235).abs() < 1e-6);
+        assert_eq!(sector.runways.iter().map(|r| r.designators[0].as_str()).collect::<Vec<_>>(), ["04"]);
+    }
+}
@@ -0,0 +1,62 @@
+//! Site files kept next to the sector fragments in the same colon-separated style as
+//! the `.ese`. They carry airport knowledge the sector file has no place for.
+
+use super::{AerodromeInfo, RunwayEndInfo, ZoneTags};
+
+/// Reads `Zones.txt` lines of the form `<kind>:<name>:<tag>[,<tag>...]`, e.g.
+/// `TAXIWAY:J:ARRIVAL-ONLY`. Tags are kept as written and validated by the caller.
+pub fn parse_zone_tags(text: &str) -> Vec<ZoneTags> {
+    text.lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            if line.starts_with(';') {
+                return None;
+            }
+            let fields: Vec<&str> = line.split(':').map(str::trim).collect();
+            match fields.as_slice() {
+                [kind, name, tags] if !kind.is_empty() && !name.is_empty() => Some(ZoneTags {
+                    kind: kind.to_ascii_uppercase(),
+                    name: name.to_string(),
+                    tags: tags.split(',').map(str::trim).filter(|t| !t.is_empty()).map(str::to_string).collect(),
+                }),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Reads `Aerodrome.txt`: `ELEVATION:<ft>` for the aerodrome and one
+/// `RUNWAY:<designator>:<width m>:<displaced threshold m>:<threshold elevation ft>:<glideslope deg>`
+/// line per runway end. Empty fields are left unset. `LVPHOLD:<hold>:<CAT II/III hold>`
+/// names the holding point used in place of `<hold>` under low visibility procedures, and
+/// `STACK:<fix>` marks a fix with a published arrival holding pattern.
+pub fn parse_aerodrome(text: &str) -> AerodromeInfo {
+    let mut info = AerodromeInfo::default();
+
+    for line in text.lines() {
+        let line = line.trim();
+        if line.starts_with(';') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split(':').map(str::trim).collect();
+        let number = |i: usize| fields.get(i).and_then(|f| f.parse::<f64>().ok());
+
+        match fields.first().map(|f| f.to_ascii_uppercase()).as_deref() {
+            Some("ELEVATION") => info.elevation_ft = number(1),
+            Some("RUNWAY") if fields.len() >= 2 && !fields[1].is_empty() => info.runway_ends.push(RunwayEndInfo {
+                designator: fields[1].to_string(),
+                width_m: number(2),
+                displaced_m: number(3),
+                elevation_ft: number(4),
+                glideslope_deg: number(5),
+            }),
+            Some("LVPHOLD") if fields.len() >= 3 && !fields[1].is_empty() && !fields[2].is_empty() => {
+                info.lvp_holds.push((fields[1].to_string(), fields[2].to_string()))
+            }
+            Some("STACK") if fields.len() >= 2 && !fields[1].is_empty() => info.stacks.push(fields[1].to_ascii_uppercase()),
+            _ => {}
+        }
+    }
+
+    info
+}
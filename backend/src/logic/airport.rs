@@ -1,8 +1,12 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::fs;
 use std::path::Path;
 
+use crate::euroscope::{self, ActiveRunway, Procedure, Region, RunwayEndInfo, RunwayLine, SectorData};
+use crate::logic::geofence::{haversine_distance, initial_bearing, AirportZones, LocalFrame};
+use crate::logic::routing::{TaxiGraph, TaxiRoute};
+use crate::logic::spatial::PointIndex;
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Coordinate {
     pub lat: f64,
@@ -12,7 +16,8 @@ pub struct Coordinate {
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Taxiway {
     pub name: String,
-    pub type_: String, // "gap" or "taxiway"; mapped from "type" in json
+    #[serde(rename = "type")]
+    pub type_: String, // "Taxiway", "OneWay" or "LeadIn"
     pub coordinates: Vec<Vec<Vec<f64>>>, // GeoJSON MultiLineString-ish
 }
 
@@ -25,78 +30,388 @@ pub struct Node {
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Airport {
+    pub icao: String,
+    pub elevation_ft: f64,
     pub taxiways: Vec<Taxiway>,
     pub holds: Vec<Node>,
     pub stands: Vec<Node>,
     pub runways: Vec<Runway>,
+    pub regions: Vec<Region>,
+    pub procedures: Vec<Procedure>,
+    pub active_runways: Vec<ActiveRunway>,
+    /// Normal holding point to the CAT II/III holding point used under LVP.
+    pub lvp_holds: HashMap<String, String>,
+    /// Arrival holding fixes.
+    pub stacks: Vec<Node>,
+    #[serde(skip)]
+    pub taxi_graph: TaxiGraph,
+    #[serde(skip)]
+    pub zones: AirportZones,
+    #[serde(skip)]
+    stand_index: PointIndex,
+    #[serde(skip)]
+    hold_index: PointIndex,
 }
 
+/// Width assumed when `Aerodrome.txt` does not give one.
+const DEFAULT_RUNWAY_WIDTH_M: f64 = 45.0;
+const DEFAULT_GLIDESLOPE_DEG: f64 = 3.0;
+/// Threshold crossing height of the glidepath.
+const THRESHOLD_CROSSING_HEIGHT_FT: f64 = 50.0;
+/// Half-width of the ILS sensitive area either side of the centreline. Normal holds
+/// lie inside it, CAT II/III holds outside.
+const ILS_SENSITIVE_AREA_HALF_WIDTH_M: f64 = 110.0;
+
+/// One physical runway, e.g. 04/22, with an entry for each direction of use.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Runway {
     pub name: String,
+    pub ends: [RunwayEnd; 2],
+    pub length_m: f64,
+    pub width_m: f64,
+}
+
+/// A runway in one direction of use.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RunwayEnd {
+    pub name: String,
+    /// Start of the pavement in this direction, where the take-off run begins.
     pub threshold: [f64; 2],
+    /// Landing threshold, moved down the runway by `displaced_m`.
+    pub landing_threshold: [f64; 2],
+    pub displaced_m: f64,
     pub heading: f64,
+    pub elevation_ft: f64,
+    pub glideslope_deg: f64,
+    /// Take-off run available.
+    pub tora_m: f64,
+    /// Landing distance available.
+    pub lda_m: f64,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+impl RunwayEnd {
+    /// Distance in nautical miles from a position to the landing threshold.
+    pub fn distance_nm(&self, lat: f64, lon: f64) -> f64 {
+        haversine_distance(lat, lon, self.landing_threshold[0], self.landing_threshold[1]) / 1.852
+    }
+
+    /// Position relative to the extended centreline on the approach side, in metres:
+    /// distance out from the landing threshold (negative once past it) and lateral offset.
+    pub fn approach_offset_m(&self, lat: f64, lon: f64) -> (f64, f64) {
+        let frame = LocalFrame::new(self.landing_threshold[0], self.landing_threshold[1]);
+        let (x, y) = frame.to_xy(lat, lon);
+        let outbound = (self.heading + 180.0).to_radians();
+        let (ux, uy) = (outbound.sin(), outbound.cos());
+        (x * ux + y * uy, (x * uy - y * ux).abs())
+    }
+
+    /// Height above the threshold of the nominal glidepath at a distance from it.
+    pub fn glidepath_height_ft(&self, distance_nm: f64) -> f64 {
+        let distance_ft = distance_nm * 1852.0 * 3.28084;
+        THRESHOLD_CROSSING_HEIGHT_FT + distance_ft * self.glideslope_deg.to_radians().tan()
+    }
+}
+
+/// Runway ends in use for arrivals and departures, e.g. 22/22.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct RunwayConfig {
+    pub arrival: String,
+    pub departure: String,
+}
+
+impl RunwayConfig {
+    pub fn arrival_end<'a>(&self, airport: &'a Airport) -> Option<&'a RunwayEnd> {
+        airport.runway_end(&self.arrival)
+    }
+}
+
+/// Ground models keyed by airport code.
+#[derive(Debug, Clone, Default)]
 pub struct AirportData {
-    #[serde(rename = "EGSS")]
-    pub egss: Airport,
+    pub airports: HashMap<String, Airport>,
+}
+
+impl AirportData {
+    pub fn get(&self, code: &str) -> Option<&Airport> {
+        self.airports.get(&code.to_ascii_uppercase())
+    }
 }
 
 impl Airport {
-    pub fn find_nearest_stand(&self, lat: f64, lon: f64) -> Option<(&Node, f64)> {
-        let mut nearest = None;
-        let mut min_dist = f64::MAX;
-
-        for stand in &self.stands {
-            let dist = ((stand.lat - lat).powi(2) + (stand.lon - lon).powi(2)).sqrt();
-            if dist < min_dist {
-                min_dist = dist;
-                nearest = Some(stand);
+    /// Builds the ground model from parsed sector data.
+    ///
+    /// Taxiways are the features in the centreline section of `Geo.txt`, plus any
+    /// top-level feature drawn in a centreline colour outside the hold/stopbar sections
+    /// (one-way lead-ins such as `A_Southbound_Eastbound_J` and stand lead-in lines).
+    pub fn from_sector(sector: &SectorData) -> Self {
+        let is_centreline_section = |s: &str| s.to_ascii_lowercase().contains("centreline");
+        let is_hold_section = |s: &str| {
+            let s = s.to_ascii_lowercase();
+            s.contains("hold") || s.contains("stopbar")
+        };
+
+        let centreline_colours: Vec<&str> = sector.geo.iter()
+            .filter(|f| is_centreline_section(&f.section))
+            .flat_map(|f| f.segments.iter().map(|s| s.colour.as_str()))
+            .collect();
+
+        let mut taxiways: Vec<Taxiway> = Vec::new();
+        for feature in &sector.geo {
+            let is_taxiway = if is_centreline_section(&feature.section) {
+                true
+            } else {
+                !is_hold_section(&feature.section)
+                    && feature.section == feature.name
+                    && feature.segments.iter().all(|s| centreline_colours.contains(&s.colour.as_str()))
+            };
+            if !is_taxiway {
+                continue;
+            }
+
+            // "Taxiway_Centrelines_-__-_F" -> "F"
+            let name = feature.name.rsplit("_-_").next().unwrap_or(&feature.name).to_string();
+            let type_ = if name.to_ascii_lowercase().contains("bound") {
+                "OneWay"
+            } else if feature.section.starts_with("Stand") {
+                "LeadIn"
+            } else {
+                "Taxiway"
+            };
+            let coordinates = feature.segments.iter()
+                .map(|s| vec![vec![s.from.lat, s.from.lon], vec![s.to.lat, s.to.lon]]);
+
+            match taxiways.iter_mut().find(|t| t.name == name) {
+                Some(existing) => existing.coordinates.extend(coordinates),
+                None => taxiways.push(Taxiway {
+                    name,
+                    type_: type_.to_string(),
+                    coordinates: coordinates.collect(),
+                }),
             }
         }
-        
-        nearest.map(|n| (n, min_dist))
+
+        let labels_in = |keyword: &str| -> Vec<Node> {
+            sector.labels.iter()
+                .filter(|l| l.section.to_ascii_lowercase().contains(keyword))
+                .map(|l| Node { name: l.text.clone(), lat: l.position.lat, lon: l.position.lon })
+                .collect()
+        };
+
+        let elevation_ft = sector.aerodrome.elevation_ft.unwrap_or(0.0);
+        let runways: Vec<Runway> = sector.runways.iter()
+            .map(|line| Runway::from_line(line, &sector.aerodrome.runway_ends, elevation_ft))
+            .collect();
+
+        let stacks = sector.aerodrome.stacks.iter()
+            .filter_map(|name| {
+                let fix = sector.fixes.iter().find(|f| f.name.eq_ignore_ascii_case(name));
+                if fix.is_none() {
+                    println!("{}: holding fix {} not found in the sector fixes", sector.icao, name);
+                }
+                fix.map(|f| Node { name: f.name.clone(), lat: f.position.lat, lon: f.position.lon })
+            })
+            .collect();
+
+        let holds = labels_in("hold");
+        let stands = labels_in("stand");
+        let taxi_graph = TaxiGraph::build(&taxiways, &holds, &stands);
+
+        let reference = sector.reference.clone()
+            .or_else(|| stands.first().map(|s| Coordinate { lat: s.lat, lon: s.lon }))
+            .unwrap_or(Coordinate { lat: 0.0, lon: 0.0 });
+        let frame = LocalFrame::new(reference.lat, reference.lon);
+        let stand_index = PointIndex::build(frame, stands.iter().map(|s| (s.lat, s.lon)));
+        let hold_index = PointIndex::build(frame, holds.iter().map(|h| (h.lat, h.lon)));
+        let zones = AirportZones::build(frame, &runways, &taxiways, &sector.regions, &sector.zone_tags);
+
+        Airport {
+            icao: sector.icao.clone(),
+            elevation_ft,
+            taxiways,
+            holds,
+            stands,
+            runways,
+            regions: sector.regions.clone(),
+            procedures: sector.procedures.clone(),
+            active_runways: sector.active_runways.clone(),
+            lvp_holds: sector.aerodrome.lvp_holds.iter().cloned().collect(),
+            stacks,
+            taxi_graph,
+            zones,
+            stand_index,
+            hold_index,
+        }
     }
 
+    pub fn runway_end(&self, name: &str) -> Option<&RunwayEnd> {
+        self.runways.iter().flat_map(|r| r.ends.iter()).find(|e| e.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Runway configuration from the `.rwy` file. A missing arrival or departure entry
+    /// falls back to the other, then to the first runway end.
+    pub fn default_runway_config(&self) -> Option<RunwayConfig> {
+        let fallback = self.runways.first().map(|r| r.ends[0].name.clone());
+        let active = |departure: bool| {
+            let by_flag = |flag: bool| self.active_runways.iter().filter(move |r| r.departure == flag);
+            by_flag(departure)
+                .chain(by_flag(!departure))
+                .find_map(|r| self.runway_end(&r.runway))
+                .map(|e| e.name.clone())
+                .or_else(|| fallback.clone())
+        };
+        Some(RunwayConfig { arrival: active(false)?, departure: active(true)? })
+    }
+
+    /// Holding point closest to the start of the take-off run on the given runway end,
+    /// or its CAT II/III counterpart when `lvp` is set.
+    pub fn departure_hold(&self, runway: &str, lvp: bool) -> Option<&Node> {
+        let runway = self.runway_end(runway)?;
+
+        let hold = self.holds.iter()
+            .filter(|h| self.taxi_graph.hold_node(&h.name).is_some())
+            .filter(|h| !self.lvp_holds.values().any(|lvp_hold| *lvp_hold == h.name))
+            .min_by(|a, b| {
+                let da = haversine_distance(a.lat, a.lon, runway.threshold[0], runway.threshold[1]);
+                let db = haversine_distance(b.lat, b.lon, runway.threshold[0], runway.threshold[1]);
+                da.partial_cmp(&db).unwrap_or(std::cmp::Ordering::Equal)
+            })?;
+
+        if !lvp {
+            return Some(hold);
+        }
+        self.lvp_holds.get(&hold.name)
+            .and_then(|name| self.holds.iter().find(|h| h.name == *name))
+            .filter(|h| self.taxi_graph.hold_node(&h.name).is_some())
+            .or(Some(hold))
+    }
+
+    /// Whether two holds are the normal and CAT II/III holding points of the same entry.
+    pub fn is_lvp_pair(&self, a: &str, b: &str) -> bool {
+        self.lvp_holds.get(a).is_some_and(|h| h == b) || self.lvp_holds.get(b).is_some_and(|h| h == a)
+    }
+
+    /// Runway that has `end` as one of its ends.
+    pub fn runway_of(&self, end: &str) -> Option<&Runway> {
+        self.runways.iter().find(|r| r.ends.iter().any(|e| e.name.eq_ignore_ascii_case(end)))
+    }
+
+    /// Runway whose pavement contains the position.
+    pub fn runway_at(&self, lat: f64, lon: f64) -> Option<&Runway> {
+        self.runways.iter().find(|r| r.centreline_offset_m(lat, lon).is_some_and(|offset| offset < r.width_m / 2.0))
+    }
+
+    /// Whether a position is inside the ILS sensitive area alongside the runway of `end`.
+    pub fn in_ils_sensitive_area(&self, end: &RunwayEnd, lat: f64, lon: f64) -> bool {
+        self.runways.iter()
+            .find(|r| r.ends.iter().any(|e| e.name == end.name))
+            .and_then(|r| r.centreline_offset_m(lat, lon))
+            .is_some_and(|offset| offset < ILS_SENSITIVE_AREA_HALF_WIDTH_M)
+    }
+
+    /// Route from the stand (or the current position when the stand is unknown) to the
+    /// departure hold for `runway`.
+    pub fn departure_route(&self, runway: &str, lvp: bool, stand: Option<&str>, lat: f64, lon: f64) -> Option<TaxiRoute> {
+        let hold = self.taxi_graph.hold_node(&self.departure_hold(runway, lvp)?.name)?;
+        let origin = stand
+            .and_then(|s| self.taxi_graph.stand_node(s))
+            .or_else(|| self.taxi_graph.nearest_node(lat, lon))?;
+        self.taxi_graph.route(origin, hold)
+    }
+
+    /// Nearest stand and its distance in metres.
+    pub fn find_nearest_stand(&self, lat: f64, lon: f64) -> Option<(&Node, f64)> {
+        self.stand_index.nearest(lat, lon).map(|(i, d)| (&self.stands[i], d))
+    }
+
+    /// Nearest holding point and its distance in metres.
     pub fn find_nearest_hold(&self, lat: f64, lon: f64) -> Option<(&Node, f64)> {
-        let mut nearest = None;
-        let mut min_dist = f64::MAX;
-
-        for hold in &self.holds {
-            let dist = ((hold.lat - lat).powi(2) + (hold.lon - lon).powi(2)).sqrt();
-            if dist < min_dist {
-                min_dist = dist;
-                nearest = Some(hold);
+        self.hold_index.nearest(lat, lon).map(|(i, d)| (&self.holds[i], d))
+    }
+}
+
+impl Runway {
+    /// Distance in metres from the centreline for a position abeam the runway,
+    /// `None` beyond either end.
+    pub fn centreline_offset_m(&self, lat: f64, lon: f64) -> Option<f64> {
+        let [a, b] = [self.ends[0].threshold, self.ends[1].threshold];
+        let frame = LocalFrame::new(a[0], a[1]);
+        let (bx, by) = frame.to_xy(b[0], b[1]);
+        let (px, py) = frame.to_xy(lat, lon);
+        let length = bx.hypot(by);
+        if length == 0.0 {
+            return None;
+        }
+        let along = (px * bx + py * by) / length;
+        if !(0.0..=length).contains(&along) {
+            return None;
+        }
+        Some(((px * by - py * bx) / length).abs())
+    }
+
+    /// Builds both ends from a `[RUNWAY]` line. The length is the distance between the two
+    /// coordinates, which are the pavement ends; `Aerodrome.txt` supplies the rest.
+    fn from_line(line: &RunwayLine, info: &[RunwayEndInfo], aerodrome_elevation_ft: f64) -> Self {
+        let [a, b] = &line.thresholds;
+        let length_m = haversine_distance(a.lat, a.lon, b.lat, b.lon) * 1000.0;
+        let info_for = |name: &str| info.iter().find(|i| i.designator.eq_ignore_ascii_case(name));
+        let width_m = line.designators.iter()
+            .find_map(|d| info_for(d).and_then(|i| i.width_m))
+            .unwrap_or(DEFAULT_RUNWAY_WIDTH_M);
+
+        let end = |name: &str, from: &Coordinate, to: &Coordinate| {
+            let info = info_for(name);
+            let displaced_m = info.and_then(|i| i.displaced_m).unwrap_or(0.0).clamp(0.0, length_m);
+            let frame = LocalFrame::new(from.lat, from.lon);
+            let (x, y) = frame.to_xy(to.lat, to.lon);
+            let fraction = if length_m > 0.0 { displaced_m / length_m } else { 0.0 };
+            let (lat, lon) = frame.to_lat_lon(x * fraction, y * fraction);
+
+            RunwayEnd {
+                name: name.to_string(),
+                threshold: [from.lat, from.lon],
+                landing_threshold: [lat, lon],
+                displaced_m,
+                heading: initial_bearing(from.lat, from.lon, to.lat, to.lon),
+                elevation_ft: info.and_then(|i| i.elevation_ft).unwrap_or(aerodrome_elevation_ft),
+                glideslope_deg: info.and_then(|i| i.glideslope_deg).unwrap_or(DEFAULT_GLIDESLOPE_DEG),
+                tora_m: length_m,
+                lda_m: length_m - displaced_m,
             }
+        };
+
+        Runway {
+            name: line.designators.join("/"),
+            ends: [end(&line.designators[0], a, b), end(&line.designators[1], b, a)],
+            length_m,
+            width_m,
         }
-        
-        nearest.map(|n| (n, min_dist))
     }
 }
 
-pub fn load_airport_data() -> Option<AirportData> {
-    // Path relative to backend execution usually
-    let paths = [
-        "../frontend/src/airport_data.json",
-        "frontend/src/airport_data.json", 
-        "/home/akra/Dev/Automated-Traffic-Coordinator/frontend/src/airport_data.json"
-    ];
-
-    for p in paths {
-        if Path::new(p).exists() {
-            if let Ok(content) = fs::read_to_string(p) {
-                if let Ok(data) = serde_json::from_str::<AirportData>(&content) {
-                    println!("Loaded airport data from {}", p);
-                    return Some(data);
-                } else {
-                    println!("Failed to parse JSON from {}", p);
-                }
+/// Loads the sector directory of each airport code. Airports without one are skipped with a warning and simply run without ground logic.
+pub fn load_airport_data<'a>(airports: impl IntoIterator<Item = (&'a str, &'a Path)>) -> AirportData {
+    let mut data = AirportData::default();
+
+    for (code, dir) in airports {
+        let code = code.to_ascii_uppercase();
+        if !dir.is_dir() {
+            println!("No sector data for {} (looked in {})", code, dir.display());
+            continue;
+        }
+
+        match euroscope::load_sector_dir(dir, &code) {
+            Ok(sector) => {
+                let airport = Airport::from_sector(&sector);
+                println!(
+                    "Loaded {} from {} ({} taxiways, {} holds, {} stands, {} runways, {} zones)",
+                    code, dir.display(), airport.taxiways.len(), airport.holds.len(), airport.stands.len(), airport.runways.len(),
+                    airport.zones.zones.len()
+                );
+                data.airports.insert(code, airport);
             }
+            Err(e) => println!("Failed to load sector data for {}: {}", code, e),
         }
     }
-    
-    println!("Could not find airport_data.json");
-    None
+
+    data
 }
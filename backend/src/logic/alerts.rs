@@ -0,0 +1,439 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::models::Aircraft;
+
+/// Cleared alerts kept per airport for `GET /api/alerts?cleared=true`.
+const CLEARED_HISTORY: usize = 200;
+
+/// Alert and inhibition ids are unique across airports and restart with the process.
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+fn next_id() -> u64 {
+    NEXT_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Info,
+    Caution,
+    Warning,
+    Emergency,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertKind {
+    /// Squawk 7700.
+    Emergency,
+    /// Squawk 7600.
+    LostComms,
+    /// Squawk 7500.
+    UnlawfulInterference,
+    /// Runway closed for an emergency inbound to it or on it.
+    RunwayClosed,
+    /// Arrival inside the minimum spacing behind the one ahead.
+    LossOfSeparation,
+    /// Departure cleared onto the runway with an arrival inside final protection.
+    TrafficOnFinal,
+    /// Aircraft inside the ILS sensitive area under LVP with an arrival on final.
+    IlsSensitiveArea,
+    UnclearedHold,
+    WrongTurn,
+    RouteDeviation,
+    /// Go-around rejoining the landing sequence.
+    GoAround,
+    RejectedTakeOff,
+    /// Departure waiting at the holding point for longer than expected.
+    HoldTimeout,
+}
+
+impl AlertKind {
+    pub fn severity(self) -> Severity {
+        match self {
+            AlertKind::Emergency | AlertKind::LostComms | AlertKind::UnlawfulInterference => Severity::Emergency,
+            AlertKind::RunwayClosed
+            | AlertKind::LossOfSeparation
+            | AlertKind::TrafficOnFinal
+            | AlertKind::IlsSensitiveArea
+            | AlertKind::UnclearedHold
+            | AlertKind::RejectedTakeOff => Severity::Warning,
+            AlertKind::WrongTurn | AlertKind::RouteDeviation | AlertKind::GoAround => Severity::Caution,
+            AlertKind::HoldTimeout => Severity::Info,
+        }
+    }
+}
+
+/// A condition the engine found in this cycle. Conditions with the same kind and
+/// subject aircraft in consecutive cycles are the same alert.
+#[derive(Debug, Clone)]
+pub struct AlertCondition {
+    pub kind: AlertKind,
+    pub severity: Severity,
+    /// Subject first, then any other aircraft involved.
+    pub aircraft: Vec<String>,
+    pub callsign: Option<String>,
+    /// Any change in the message is reported as an update, so it must not carry live
+    /// figures such as a distance that changes every cycle.
+    pub message: String,
+}
+
+impl AlertCondition {
+    pub fn new(kind: AlertKind, subject: &Aircraft, message: impl Into<String>) -> Self {
+        AlertCondition {
+            kind,
+            severity: kind.severity(),
+            aircraft: vec![subject.icao24.clone()],
+            callsign: subject.callsign.as_ref().map(|c| c.trim().to_string()),
+            message: message.into(),
+        }
+    }
+
+    /// Adds another aircraft involved, e.g. the arrival a departure conflicts with.
+    pub fn involving(mut self, icao24: &str) -> Self {
+        if !self.aircraft.iter().any(|a| a == icao24) {
+            self.aircraft.push(icao24.to_string());
+        }
+        self
+    }
+
+    fn subject(&self) -> &str {
+        &self.aircraft[0]
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Acknowledgement {
+    pub by: String,
+    pub at: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Alert {
+    pub id: u64,
+    pub kind: AlertKind,
+    pub severity: Severity,
+    /// icao24 of the subject first, then any other aircraft involved.
+    pub aircraft: Vec<String>,
+    pub callsign: Option<String>,
+    pub message: String,
+    pub raised_at: i64,
+    pub updated_at: i64,
+    pub cleared_at: Option<i64>,
+    /// Reset when the alert escalates.
+    pub acknowledged: Option<Acknowledgement>,
+    /// Tracked but kept off the controller's display by an inhibition.
+    pub inhibited: bool,
+    /// Raised by a one-off event: stays active until acknowledged rather than until the
+    /// condition goes away.
+    pub latched: bool,
+}
+
+impl Alert {
+    fn subject(&self) -> &str {
+        &self.aircraft[0]
+    }
+
+    fn matches(&self, condition: &AlertCondition) -> bool {
+        self.kind == condition.kind && self.subject() == condition.subject()
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.cleared_at.is_none()
+    }
+
+    /// Acknowledged or inhibited, so the controller does not need to see it again.
+    pub fn is_quiet(&self) -> bool {
+        self.acknowledged.is_some() || self.inhibited
+    }
+}
+
+/// Suppresses one kind of alert, for one aircraft or all, until it expires or is lifted.
+#[derive(Debug, Clone, Serialize)]
+pub struct Inhibition {
+    /// Assigned when the inhibition is added.
+    pub id: u64,
+    pub kind: AlertKind,
+    pub icao24: Option<String>,
+    pub until: Option<i64>,
+    pub by: String,
+    pub reason: Option<String>,
+}
+
+impl Inhibition {
+    fn covers(&self, kind: AlertKind, subject: &str) -> bool {
+        self.kind == kind && self.icao24.as_deref().is_none_or(|i| i.eq_ignore_ascii_case(subject))
+    }
+}
+
+/// Alerts at one airport. The engine reports every condition it finds each cycle;
+/// conditions seen again update their alert, and alerts whose condition is gone clear.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AlertBoard {
+    pub active: Vec<Alert>,
+    pub cleared: VecDeque<Alert>,
+    pub inhibitions: Vec<Inhibition>,
+    /// Alerts raised, updated, acknowledged or cleared since the last drain.
+    #[serde(skip)]
+    changes: Vec<Alert>,
+}
+
+impl AlertBoard {
+    /// Applies this cycle's conditions. Latched alerts outlive their condition until
+    /// acknowledged, and every alert clears once its subject is no longer tracked.
+    pub fn update(&mut self, conditions: Vec<AlertCondition>, aircraft_map: &HashMap<String, Aircraft>, now: i64) {
+        self.inhibitions.retain(|i| i.until.is_none_or(|until| until > now));
+
+        let mut seen = HashSet::new();
+        for condition in conditions {
+            // The first report of a condition in a cycle wins
+            if seen.insert((condition.kind, condition.subject().to_string())) {
+                self.raise(condition, false, now);
+            }
+        }
+
+        let (active, cleared): (Vec<Alert>, Vec<Alert>) = std::mem::take(&mut self.active).into_iter().partition(|a| {
+            let tracked = aircraft_map.contains_key(a.subject());
+            let current = seen.contains(&(a.kind, a.subject().to_string()));
+            tracked && (current || (a.latched && a.acknowledged.is_none()))
+        });
+        self.active = active;
+        for mut alert in cleared {
+            alert.cleared_at = Some(now);
+            self.changes.push(alert.clone());
+            self.cleared.push_back(alert);
+        }
+        while self.cleared.len() > CLEARED_HISTORY {
+            self.cleared.pop_front();
+        }
+    }
+
+    /// Raises an alert for a one-off event, e.g. a rejected take-off.
+    pub fn latch(&mut self, condition: AlertCondition, now: i64) {
+        self.raise(condition, true, now);
+    }
+
+    fn raise(&mut self, condition: AlertCondition, latched: bool, now: i64) {
+        let inhibited = condition.severity < Severity::Emergency
+            && self.inhibitions.iter().any(|i| i.covers(condition.kind, condition.subject()));
+
+        if let Some(alert) = self.active.iter_mut().find(|a| a.matches(&condition)) {
+            let escalated = condition.severity > alert.severity;
+            let changed = escalated || latched || condition.severity != alert.severity || condition.message != alert.message
+                || condition.aircraft != alert.aircraft || inhibited != alert.inhibited;
+            if !changed {
+                return;
+            }
+            if escalated || latched {
+                alert.acknowledged = None;
+            }
+            alert.severity = condition.severity;
+            alert.message = condition.message;
+            alert.aircraft = condition.aircraft;
+            alert.callsign = condition.callsign;
+            alert.inhibited = inhibited;
+            alert.latched |= latched;
+            alert.updated_at = now;
+            self.changes.push(alert.clone());
+            return;
+        }
+
+        let alert = Alert {
+            id: next_id(),
+            kind: condition.kind,
+            severity: condition.severity,
+            aircraft: condition.aircraft,
+            callsign: condition.callsign,
+            message: condition.message,
+            raised_at: now,
+            updated_at: now,
+            cleared_at: None,
+            acknowledged: None,
+            inhibited,
+            latched,
+        };
+        println!("Alert {} raised: {:?} {}", alert.id, alert.severity, alert.message);
+        self.changes.push(alert.clone());
+        self.active.push(alert);
+    }
+
+    pub fn get(&self, id: u64) -> Option<&Alert> {
+        self.active.iter().find(|a| a.id == id).or_else(|| self.cleared.iter().find(|a| a.id == id))
+    }
+
+    /// Active alert the aircraft's advisory came from, if any.
+    pub fn for_message(&self, icao24: &str, message: &str) -> Option<&Alert> {
+        self.active.iter().find(|a| a.subject() == icao24 && a.message == message)
+    }
+
+    pub fn acknowledge(&mut self, id: u64, by: &str, now: i64) -> Result<Alert, String> {
+        let alert = self.active.iter_mut().find(|a| a.id == id).ok_or_else(|| format!("No active alert {}", id))?;
+        alert.acknowledged = Some(Acknowledgement { by: by.to_string(), at: now });
+        alert.updated_at = now;
+        self.changes.push(alert.clone());
+        Ok(alert.clone())
+    }
+
+    /// Acknowledges every unacknowledged alert about the aircraft; returns how many.
+    pub fn acknowledge_aircraft(&mut self, icao24: &str, by: &str, now: i64) -> usize {
+        let ids: Vec<u64> = self.active.iter()
+            .filter(|a| a.subject() == icao24 && a.acknowledged.is_none())
+            .map(|a| a.id)
+            .collect();
+        for id in &ids {
+            let _ = self.acknowledge(*id, by, now);
+        }
+        ids.len()
+    }
+
+    /// Adds an inhibition and applies it to matching active alerts. Emergencies cannot
+    /// be inhibited.
+    pub fn inhibit(&mut self, mut inhibition: Inhibition, now: i64) -> Result<Inhibition, String> {
+        if inhibition.kind.severity() >= Severity::Emergency {
+            return Err(format!("{:?} alerts cannot be inhibited", inhibition.kind));
+        }
+        if inhibition.until.is_some_and(|until| until <= now) {
+            return Err("Inhibition would already have expired".to_string());
+        }
+        inhibition.id = next_id();
+        inhibition.icao24 = inhibition.icao24.map(|i| i.to_ascii_lowercase());
+        for alert in self.active.iter_mut().filter(|a| inhibition.covers(a.kind, &a.aircraft[0]) && !a.inhibited) {
+            alert.inhibited = true;
+            alert.updated_at = now;
+            self.changes.push(alert.clone());
+        }
+        self.inhibitions.push(inhibition.clone());
+        Ok(inhibition)
+    }
+
+    /// Lifts an inhibition; alerts it covered show again from the next cycle.
+    pub fn release(&mut self, id: u64) -> Option<Inhibition> {
+        let index = self.inhibitions.iter().position(|i| i.id == id)?;
+        Some(self.inhibitions.remove(index))
+    }
+
+    /// Alerts that changed since the last call, for the event feed.
+    pub fn drain_changes(&mut self) -> Vec<Alert> {
+        std::mem::take(&mut self.changes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tracked(icao24s: &[&str]) -> HashMap<String, Aircraft> {
+        icao24s.iter()
+            .map(|i| (i.to_string(), Aircraft { icao24: i.to_string(), callsign: Some(format!(" {} ", i.to_uppercase())), ..Aircraft::default() }))
+            .collect()
+    }
+
+    fn condition(kind: AlertKind, aircraft: &HashMap<String, Aircraft>, icao24: &str, message: &str) -> AlertCondition {
+        AlertCondition::new(kind, &aircraft[icao24], message)
+    }
+
+    fn inhibition(kind: AlertKind, icao24: Option<&str>) -> Inhibition {
+        Inhibition { id: 0, kind, icao24: icao24.map(str::to_string), until: None, by: "tower".to_string(), reason: None }
+    }
+
+    #[test]
+    fn raise_update_acknowledge_clear() {
+        let aircraft = tracked(&["aaa001", "aaa002"]);
+        let mut board = AlertBoard::default();
+        let traffic = |message: &str| condition(AlertKind::TrafficOnFinal, &aircraft, "aaa001", message).involving("aaa002");
+
+        board.update(vec![traffic("CAUTION - Hold Short - Traffic Final 22")], &aircraft, 100);
+        let raised = board.drain_changes();
+        assert_eq!(raised.len(), 1);
+        let id = raised[0].id;
+        assert_eq!((raised[0].severity, raised[0].aircraft.clone()), (Severity::Warning, vec!["aaa001".to_string(), "aaa002".to_string()]));
+        assert_eq!(raised[0].callsign.as_deref(), Some("AAA001"));
+
+        // The same condition again is not a change
+        board.update(vec![traffic("CAUTION - Hold Short - Traffic Final 22")], &aircraft, 102);
+        assert!(board.drain_changes().is_empty());
+
+        board.update(vec![traffic("CAUTION - Hold Short - Traffic Final 04")], &aircraft, 104);
+        let updated = board.drain_changes();
+        assert_eq!((updated.len(), updated[0].id, updated[0].raised_at, updated[0].updated_at), (1, id, 100, 104));
+
+        assert_eq!(board.acknowledge_aircraft("aaa001", "tower", 105), 1);
+        assert_eq!(board.acknowledge_aircraft("aaa001", "tower", 106), 0);
+        assert_eq!(board.get(id).unwrap().acknowledged.as_ref().map(|a| a.by.as_str()), Some("tower"));
+        assert!(board.get(id).unwrap().is_quiet());
+        assert!(board.acknowledge(999_999, "tower", 106).is_err());
+
+        board.update(Vec::new(), &aircraft, 108);
+        assert!(board.active.is_empty());
+        assert_eq!(board.get(id).unwrap().cleared_at, Some(108));
+        assert_eq!(board.drain_changes().len(), 2);
+    }
+
+    #[test]
+    fn escalation_clears_an_acknowledgement() {
+        let aircraft = tracked(&["aaa001"]);
+        let mut board = AlertBoard::default();
+        board.update(vec![condition(AlertKind::WrongTurn, &aircraft, "aaa001", "WRONG TURN")], &aircraft, 100);
+        board.acknowledge_aircraft("aaa001", "ground", 101);
+
+        // Same kind and severity: still acknowledged
+        board.update(vec![condition(AlertKind::WrongTurn, &aircraft, "aaa001", "WRONG TURN - EXPECT B")], &aircraft, 102);
+        assert!(board.active[0].acknowledged.is_some());
+
+        let mut escalated = condition(AlertKind::WrongTurn, &aircraft, "aaa001", "WRONG TURN - EXPECT B");
+        escalated.severity = Severity::Warning;
+        board.update(vec![escalated], &aircraft, 104);
+        assert_eq!(board.active[0].severity, Severity::Warning);
+        assert!(board.active[0].acknowledged.is_none());
+    }
+
+    #[test]
+    fn inhibitions_never_suppress_emergencies() {
+        let aircraft = tracked(&["aaa001", "aaa002"]);
+        let mut board = AlertBoard::default();
+        assert!(board.inhibit(inhibition(AlertKind::Emergency, None), 100).is_err());
+        assert!(board.inhibit(inhibition(AlertKind::LostComms, Some("aaa001")), 100).is_err());
+        board.inhibit(inhibition(AlertKind::WrongTurn, Some("AAA001")), 100).unwrap();
+
+        let mut escalated = condition(AlertKind::WrongTurn, &aircraft, "aaa002", "WRONG TURN");
+        escalated.severity = Severity::Emergency;
+        board.update(vec![
+            condition(AlertKind::WrongTurn, &aircraft, "aaa001", "WRONG TURN"),
+            condition(AlertKind::Emergency, &aircraft, "aaa001", "EMERGENCY AAA001 - SQUAWK 7700"),
+            escalated,
+        ], &aircraft, 102);
+
+        let inhibited = |kind: AlertKind, icao24: &str| board.active.iter().find(|a| a.kind == kind && a.aircraft[0] == icao24).unwrap().inhibited;
+        assert!(inhibited(AlertKind::WrongTurn, "aaa001"));
+        assert!(!inhibited(AlertKind::Emergency, "aaa001"));
+        assert!(!inhibited(AlertKind::WrongTurn, "aaa002"));
+
+        // An inhibition for every aircraft still leaves the emergency-severity condition showing
+        board.inhibit(inhibition(AlertKind::WrongTurn, None), 103).unwrap();
+        let mut escalated = condition(AlertKind::WrongTurn, &aircraft, "aaa002", "WRONG TURN");
+        escalated.severity = Severity::Emergency;
+        board.update(vec![condition(AlertKind::WrongTurn, &aircraft, "aaa001", "WRONG TURN"), escalated], &aircraft, 104);
+        let inhibited = |kind: AlertKind, icao24: &str| board.active.iter().find(|a| a.kind == kind && a.aircraft[0] == icao24).unwrap().inhibited;
+        assert!(inhibited(AlertKind::WrongTurn, "aaa001"));
+        assert!(!inhibited(AlertKind::WrongTurn, "aaa002"));
+    }
+
+    #[test]
+    fn latched_alerts_last_until_acknowledged_or_the_aircraft_is_gone() {
+        let aircraft = tracked(&["aaa001"]);
+        let mut board = AlertBoard::default();
+        board.latch(condition(AlertKind::RejectedTakeOff, &aircraft, "aaa001", "REJECTED TAKE-OFF AAA001 RUNWAY 22"), 100);
+        board.update(Vec::new(), &aircraft, 102);
+        assert_eq!(board.active.len(), 1);
+
+        board.acknowledge_aircraft("aaa001", "tower", 103);
+        board.update(Vec::new(), &aircraft, 104);
+        assert!(board.active.is_empty());
+
+        board.latch(condition(AlertKind::RejectedTakeOff, &aircraft, "aaa001", "REJECTED TAKE-OFF AAA001 RUNWAY 22"), 106);
+        board.update(Vec::new(), &HashMap::new(), 108);
+        assert!(board.active.is_empty());
+    }
+}
@@ -0,0 +1,144 @@
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+use crate::models::{Aircraft, Phase};
+
+/// Time a go-around is expected to take to fly the circuit back onto final; its ETA
+/// until it is back on approach.
+const GO_AROUND_CIRCUIT_S: i64 = 600;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SequenceEntry {
+    pub icao24: String,
+    pub callsign: Option<String>,
+    pub position: usize,
+    pub eta: i64,
+    /// Set after a go-around until the aircraft is back on approach.
+    pub rejoining: bool,
+    /// Position set by a controller rather than by ETA.
+    pub fixed: bool,
+}
+
+/// Arrival manager sequence: arrivals in landing order by ETA. Aircraft that go
+/// around leave the landing order and are held with an estimated rejoin time, which
+/// places them back in the sequence behind traffic already closer in.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ArrivalSequence {
+    pub entries: Vec<SequenceEntry>,
+}
+
+impl ArrivalSequence {
+    /// Refreshes ETAs from the current traffic, drops aircraft that have landed or
+    /// disappeared and renumbers the sequence. Priority traffic (emergencies) goes first,
+    /// positions fixed by a controller are kept, and everyone else is ordered by ETA
+    /// around them.
+    pub fn update(&mut self, aircraft_map: &HashMap<String, Aircraft>, fixed: &HashMap<String, usize>, priority: &HashSet<String>) {
+        let previous: HashMap<String, SequenceEntry> = self.entries.drain(..).map(|e| (e.icao24.clone(), e)).collect();
+
+        for aircraft in aircraft_map.values() {
+            let old = previous.get(&aircraft.icao24);
+            let arriving = matches!(aircraft.phase, Phase::Approach | Phase::Final | Phase::Landing);
+            let entry = match (arriving, old) {
+                // Back on approach: sequenced on its own ETA again
+                (true, _) => aircraft.eta.or(old.map(|o| o.eta)).map(|eta| (eta, false)),
+                (false, Some(old)) if old.rejoining && !aircraft.on_ground => Some((old.eta, true)),
+                _ => None,
+            };
+            if let Some((eta, rejoining)) = entry {
+                self.entries.push(SequenceEntry {
+                    icao24: aircraft.icao24.clone(),
+                    callsign: aircraft.callsign.clone(),
+                    position: 0,
+                    eta,
+                    rejoining,
+                    fixed: false,
+                });
+            }
+        }
+
+        self.entries.sort_by_key(|e| (!priority.contains(&e.icao24), e.eta));
+        let mut pinned: Vec<(usize, SequenceEntry)> = Vec::new();
+        self.entries.retain(|e| match fixed.get(&e.icao24) {
+            Some(&position) => {
+                pinned.push((position, SequenceEntry { fixed: true, ..e.clone() }));
+                false
+            }
+            None => true,
+        });
+        pinned.sort_by_key(|(position, entry)| (*position, entry.eta));
+        for (position, entry) in pinned {
+            let index = position.saturating_sub(1).min(self.entries.len());
+            self.entries.insert(index, entry);
+        }
+        for (i, entry) in self.entries.iter_mut().enumerate() {
+            entry.position = i + 1;
+        }
+    }
+
+    /// Takes a go-around out of the landing order and re-inserts it at its expected
+    /// return to final.
+    pub fn rejoin(&mut self, aircraft: &Aircraft, now: i64) {
+        self.entries.retain(|e| e.icao24 != aircraft.icao24);
+        self.entries.push(SequenceEntry {
+            icao24: aircraft.icao24.clone(),
+            callsign: aircraft.callsign.clone(),
+            position: 0,
+            eta: now + GO_AROUND_CIRCUIT_S,
+            rejoining: true,
+            fixed: false,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn arrival(icao24: &str, phase: Phase, eta: Option<i64>) -> Aircraft {
+        Aircraft { icao24: icao24.to_string(), phase, eta, ..Aircraft::default() }
+    }
+
+    fn order(sequence: &ArrivalSequence) -> Vec<(&str, usize, bool)> {
+        sequence.entries.iter().map(|e| (e.icao24.as_str(), e.position, e.rejoining)).collect()
+    }
+
+    fn update(sequence: &mut ArrivalSequence, aircraft: &[Aircraft]) {
+        let map = aircraft.iter().map(|a| (a.icao24.clone(), a.clone())).collect();
+        sequence.update(&map, &HashMap::new(), &HashSet::new());
+    }
+
+    #[test]
+    fn a_go_around_leaves_the_landing_order_and_rejoins_behind_closer_traffic() {
+        let mut sequence = ArrivalSequence::default();
+        let first = arrival("aaa001", Phase::Final, Some(1050));
+        let second = arrival("aaa002", Phase::Approach, Some(1100));
+        let third = arrival("aaa003", Phase::Approach, Some(1300));
+        update(&mut sequence, &[first.clone(), second.clone(), third.clone()]);
+        assert_eq!(order(&sequence), vec![("aaa001", 1, false), ("aaa002", 2, false), ("aaa003", 3, false)]);
+
+        let going_around = arrival("aaa001", Phase::Climb, None);
+        sequence.rejoin(&going_around, 1000);
+        assert_eq!(sequence.entries.iter().find(|e| e.icao24 == "aaa001").map(|e| e.eta), Some(1000 + GO_AROUND_CIRCUIT_S));
+
+        update(&mut sequence, &[going_around, second.clone(), third.clone()]);
+        assert_eq!(order(&sequence), vec![("aaa002", 1, false), ("aaa003", 2, false), ("aaa001", 3, true)]);
+
+        // Back on approach it is sequenced on its own ETA again
+        let back = arrival("aaa001", Phase::Approach, Some(1250));
+        update(&mut sequence, &[back.clone(), second.clone(), third.clone()]);
+        assert_eq!(order(&sequence), vec![("aaa002", 1, false), ("aaa001", 2, false), ("aaa003", 3, false)]);
+
+        // Landed and off the runway
+        let landed = Aircraft { on_ground: true, ..arrival("aaa001", Phase::TaxiIn, None) };
+        update(&mut sequence, &[landed, second, third]);
+        assert_eq!(order(&sequence), vec![("aaa002", 1, false), ("aaa003", 2, false)]);
+    }
+
+    #[test]
+    fn a_rejoining_aircraft_that_lands_elsewhere_is_dropped() {
+        let mut sequence = ArrivalSequence::default();
+        sequence.rejoin(&arrival("aaa001", Phase::Climb, None), 1000);
+        update(&mut sequence, &[Aircraft { on_ground: true, ..arrival("aaa001", Phase::Unknown, None) }]);
+        assert!(sequence.entries.is_empty());
+    }
+}
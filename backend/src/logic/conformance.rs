@@ -0,0 +1,166 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use crate::logic::routing::{EdgeKind, TaxiGraph, TaxiNodeKind, TaxiRoute};
+use crate::models::Aircraft;
+
+/// Positions further than this from every arc cannot be placed on the network (aprons, grass).
+const MAX_MATCH_OFFSET_M: f64 = 40.0;
+/// Within this distance of a route arc the aircraft is considered to be following the route,
+/// which absorbs position noise and corner cutting at junctions.
+const ROUTE_TOLERANCE_M: f64 = 25.0;
+/// Distance at which an uncleared holding point ahead is reported.
+const HOLD_WARNING_M: f64 = 80.0;
+/// Distance past the clearance-limit hold, along the route's last arc, that counts as
+/// crossing it rather than position noise while stopping.
+const HOLD_OVERRUN_M: f64 = 5.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ConformanceStatus {
+    Conforming,
+    /// Left the cleared route at a junction that is on it.
+    WrongTurn,
+    /// Away from the cleared route altogether.
+    OffRoute,
+    /// Heading for a holding point that is not part of the clearance.
+    UnclearedHold,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Conformance {
+    pub status: ConformanceStatus,
+    /// Taxiway the aircraft was matched to, if any.
+    pub taxiway: Option<String>,
+    pub message: Option<String>,
+}
+
+impl Conformance {
+    fn conforming(taxiway: Option<String>) -> Self {
+        Conformance { status: ConformanceStatus::Conforming, taxiway, message: None }
+    }
+}
+
+/// Compares a taxiing aircraft's position with its cleared route.
+///
+/// The position is map-matched onto the taxiway graph the route was planned on. A
+/// wrong turn is reported while the aircraft is on an arc branching off a route node
+/// (and stays reported until it is back on the route); anything else away from the
+/// route is a deviation. Holding points come first, as they are the more urgent: one at
+/// the end of the matched arc that the route does not pass through, and the clearance
+/// limit itself once the aircraft is past it without a line-up or take-off clearance.
+pub fn check_conformance(graph: &TaxiGraph, route: &TaxiRoute, aircraft: &Aircraft, cleared_onto_runway: bool) -> Option<Conformance> {
+    let (lat, lon) = (aircraft.latitude?, aircraft.longitude?);
+    if route.arcs.is_empty() || route.arcs.iter().any(|&a| a >= graph.arcs.len()) {
+        return None;
+    }
+
+    let matched = graph.match_position(lat, lon, aircraft.true_track, MAX_MATCH_OFFSET_M);
+    let taxiway = matched
+        .map(|a| &graph.arcs[a])
+        .filter(|arc| arc.kind == EdgeKind::Taxiway)
+        .map(|arc| arc.taxiway.clone());
+
+    let route_nodes: HashSet<usize> = route.arcs.iter().flat_map(|&a| [graph.arcs[a].from, graph.arcs[a].to]).collect();
+    let callsign = aircraft.callsign.as_deref().unwrap_or(&aircraft.icao24).trim();
+    let uncleared_hold = |hold: &str, taxiway: Option<String>| Conformance {
+        status: ConformanceStatus::UnclearedHold,
+        taxiway,
+        message: Some(format!("STOP {} - NOT CLEARED PAST {}", callsign, hold)),
+    };
+
+    // Past the clearance limit, i.e. onto the runway
+    let last = route.arcs[route.arcs.len() - 1];
+    if let TaxiNodeKind::Hold(hold) = &graph.nodes[graph.arcs[last].to].kind {
+        if !cleared_onto_runway && graph.beyond_arc_end_m(lat, lon, last) > HOLD_OVERRUN_M {
+            return Some(uncleared_hold(hold, taxiway));
+        }
+    }
+
+    if let Some(a) = matched {
+        let arc = &graph.arcs[a];
+        if let TaxiNodeKind::Hold(hold) = &graph.nodes[arc.to].kind {
+            if !route_nodes.contains(&arc.to) && graph.distance_to_node(lat, lon, arc.to) <= HOLD_WARNING_M {
+                return Some(uncleared_hold(hold, taxiway));
+            }
+        }
+    }
+
+    if route.arcs.iter().any(|&a| graph.offset_from_arc(lat, lon, a) <= ROUTE_TOLERANCE_M) {
+        return Some(Conformance::conforming(taxiway));
+    }
+
+    if let Some(a) = matched {
+        let arc = &graph.arcs[a];
+
+        let previously_wrong_turn = matches!(&aircraft.conformance, Some(c) if c.status == ConformanceStatus::WrongTurn);
+        if route_nodes.contains(&arc.from) || previously_wrong_turn {
+            let message = match &taxiway {
+                Some(t) => format!("WRONG TURN {} - ONTO {}, CLEARED VIA {}", callsign, t, route.taxiways.join(", ")),
+                None => format!("WRONG TURN {} - CLEARED VIA {}", callsign, route.taxiways.join(", ")),
+            };
+            return Some(Conformance { status: ConformanceStatus::WrongTurn, taxiway, message: Some(message) });
+        }
+    }
+
+    Some(Conformance {
+        status: ConformanceStatus::OffRoute,
+        message: Some(format!("ROUTE DEVIATION {} - CLEARED VIA {}", callsign, route.taxiways.join(", "))),
+        taxiway,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logic::airport::{Node, Taxiway};
+
+    /// Taxiway A running north from stand 1 through holding point A1 towards a runway.
+    fn graph() -> TaxiGraph {
+        let taxiway = Taxiway {
+            name: "A".to_string(),
+            type_: "Taxiway".to_string(),
+            coordinates: vec![vec![vec![51.880, 0.230], vec![51.890, 0.230]]],
+        };
+        let hold = Node { name: "A1".to_string(), lat: 51.885, lon: 0.230 };
+        let stand = Node { name: "1".to_string(), lat: 51.880, lon: 0.230 };
+        TaxiGraph::build(&[taxiway], &[hold], &[stand])
+    }
+
+    fn at(lat: f64) -> Aircraft {
+        Aircraft {
+            icao24: "400abc".to_string(),
+            callsign: Some("EZY12".to_string()),
+            latitude: Some(lat),
+            longitude: Some(0.230),
+            true_track: Some(0.0),
+            on_ground: true,
+            ..Aircraft::default()
+        }
+    }
+
+    #[test]
+    fn short_of_the_clearance_limit_conforms() {
+        let graph = graph();
+        let route = graph.route_between("Stand 1", "A1").unwrap();
+        let conformance = check_conformance(&graph, &route, &at(51.8848), false).unwrap();
+        assert_eq!(conformance.status, ConformanceStatus::Conforming);
+    }
+
+    #[test]
+    fn just_past_the_clearance_limit_is_an_uncleared_hold() {
+        let graph = graph();
+        let route = graph.route_between("Stand 1", "A1").unwrap();
+        // About 11 m past A1, inside the route tolerance
+        let conformance = check_conformance(&graph, &route, &at(51.8851), false).unwrap();
+        assert_eq!(conformance.status, ConformanceStatus::UnclearedHold);
+        assert_eq!(conformance.message.as_deref(), Some("STOP EZY12 - NOT CLEARED PAST A1"));
+    }
+
+    #[test]
+    fn past_the_clearance_limit_with_a_runway_clearance_conforms() {
+        let graph = graph();
+        let route = graph.route_between("Stand 1", "A1").unwrap();
+        let conformance = check_conformance(&graph, &route, &at(51.8851), true).unwrap();
+        assert_eq!(conformance.status, ConformanceStatus::Conforming);
+    }
+}
@@ -0,0 +1,400 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+
+use crate::logic::airport::Airport;
+use crate::logic::alerts::AlertBoard;
+use crate::models::{Aircraft, Phase};
+
+/// Commands kept for `GET /api/airports/:code/commands`.
+const COMMAND_LOG_LEN: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClearanceKind {
+    Pushback,
+    Taxi,
+    LineUp,
+    TakeOff,
+    Land,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Clearance {
+    pub kind: ClearanceKind,
+    /// Taxi clearance limit, a holding point or stand.
+    pub limit: Option<String>,
+    pub issued_by: String,
+    pub issued_at: i64,
+}
+
+/// What controllers have told the engine about one aircraft. These take precedence
+/// over what the engine would infer, until revoked or used up.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AircraftInputs {
+    pub clearances: Vec<Clearance>,
+    /// Revoked clearances the engine must not issue on its own again.
+    pub withheld: Vec<ClearanceKind>,
+    pub stand: Option<String>,
+    /// Runway end to use instead of the one in use at the airport.
+    pub runway: Option<String>,
+    pub sequence_position: Option<usize>,
+    pub phase: Option<Phase>,
+}
+
+impl AircraftInputs {
+    pub fn clearance(&self, kind: ClearanceKind) -> Option<&Clearance> {
+        self.clearances.iter().find(|c| c.kind == kind)
+    }
+
+    pub fn has(&self, kind: ClearanceKind) -> bool {
+        self.clearance(kind).is_some()
+    }
+
+    pub fn withholds(&self, kind: ClearanceKind) -> bool {
+        self.withheld.contains(&kind)
+    }
+
+    /// Drops clearances that have been used, e.g. pushback once taxiing.
+    pub fn consume(&mut self, kinds: &[ClearanceKind]) {
+        self.clearances.retain(|c| !kinds.contains(&c.kind));
+        self.withheld.retain(|k| !kinds.contains(k));
+    }
+
+    fn is_empty(&self) -> bool {
+        self.clearances.is_empty() && self.withheld.is_empty() && self.stand.is_none() && self.runway.is_none()
+            && self.sequence_position.is_none() && self.phase.is_none()
+    }
+}
+
+/// A controller instruction to the engine. Fields set to `null` remove an assignment.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum Command {
+    IssueClearance {
+        icao24: String,
+        clearance: ClearanceKind,
+        #[serde(default)]
+        limit: Option<String>,
+    },
+    RevokeClearance { icao24: String, clearance: ClearanceKind },
+    AssignStand { icao24: String, stand: Option<String> },
+    AssignRunway { icao24: String, runway: Option<String> },
+    FixSequence { icao24: String, position: Option<usize> },
+    OverridePhase { icao24: String, phase: Option<Phase> },
+    AcknowledgeAlert { icao24: String },
+}
+
+impl Command {
+    pub fn icao24(&self) -> &str {
+        match self {
+            Command::IssueClearance { icao24, .. }
+            | Command::RevokeClearance { icao24, .. }
+            | Command::AssignStand { icao24, .. }
+            | Command::AssignRunway { icao24, .. }
+            | Command::FixSequence { icao24, .. }
+            | Command::OverridePhase { icao24, .. }
+            | Command::AcknowledgeAlert { icao24 } => icao24,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CommandRecord {
+    pub time: i64,
+    pub controller: String,
+    pub command: Command,
+}
+
+/// Controller inputs for every aircraft at one airport, and the commands that set them.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ControllerInputs {
+    pub aircraft: HashMap<String, AircraftInputs>,
+    pub log: VecDeque<CommandRecord>,
+}
+
+impl ControllerInputs {
+    pub fn get(&self, icao24: &str) -> Option<&AircraftInputs> {
+        self.aircraft.get(icao24)
+    }
+
+    pub fn get_mut(&mut self, icao24: &str) -> Option<&mut AircraftInputs> {
+        self.aircraft.get_mut(icao24)
+    }
+
+    /// Forgets aircraft that are no longer tracked.
+    pub fn retain(&mut self, aircraft_map: &HashMap<String, Aircraft>) {
+        self.aircraft.retain(|icao24, inputs| aircraft_map.contains_key(icao24) && !inputs.is_empty());
+    }
+
+    /// Pinned sequence positions by aircraft.
+    pub fn fixed_positions(&self) -> HashMap<String, usize> {
+        self.aircraft.iter()
+            .filter_map(|(icao24, i)| i.sequence_position.map(|p| (icao24.clone(), p)))
+            .collect()
+    }
+
+    /// Validates a command against the aircraft and airport, records it and applies its
+    /// immediate effect on the aircraft's instructions and alerts.
+    pub fn apply(
+        &mut self,
+        aircraft_map: &mut HashMap<String, Aircraft>,
+        airport: Option<&Airport>,
+        alerts: &mut AlertBoard,
+        command: Command,
+        controller: &str,
+        now: i64,
+    ) -> Result<AircraftInputs, String> {
+        let icao24 = command.icao24().to_ascii_lowercase();
+        let aircraft = aircraft_map.get_mut(&icao24).ok_or_else(|| format!("Unknown aircraft {}", icao24))?;
+        let name = aircraft.callsign.clone().unwrap_or_else(|| icao24.clone());
+        // Worked on a copy so a rejected command leaves nothing behind
+        let mut inputs = self.aircraft.get(&icao24).cloned().unwrap_or_default();
+
+        match &command {
+            Command::IssueClearance { clearance, limit, .. } => {
+                let ground = airport.ok_or("No ground data for this airport")?;
+                let limit = match (clearance, limit) {
+                    (ClearanceKind::Taxi, Some(limit)) => {
+                        let node = ground.taxi_graph.named_node(limit).ok_or_else(|| format!("Unknown taxi limit {}", limit))?;
+                        Some(ground.taxi_graph.node_name(node))
+                    }
+                    (ClearanceKind::Taxi, None) => return Err("A taxi clearance needs a limit, a holding point or stand".to_string()),
+                    (_, Some(_)) => return Err("Only a taxi clearance takes a limit".to_string()),
+                    (_, None) => None,
+                };
+                if *clearance == ClearanceKind::Land && aircraft.on_ground {
+                    return Err(format!("{} is on the ground", name));
+                }
+                if *clearance != ClearanceKind::Land && !aircraft.on_ground {
+                    return Err(format!("{} is airborne", name));
+                }
+
+                inputs.clearances.retain(|c| c.kind != *clearance);
+                inputs.withheld.retain(|k| k != clearance);
+                inputs.clearances.push(Clearance { kind: *clearance, limit: limit.clone(), issued_by: controller.to_string(), issued_at: now });
+
+                let runway = inputs.runway.clone();
+                aircraft.atc_message = Some(match clearance {
+                    ClearanceKind::Pushback => "Pushback Approved".to_string(),
+                    ClearanceKind::Taxi => {
+                        let (lat, lon) = (aircraft.latitude.unwrap_or(0.0), aircraft.longitude.unwrap_or(0.0));
+                        let origin = ground.taxi_graph.nearest_node(lat, lon);
+                        let destination = limit.as_deref().and_then(|l| ground.taxi_graph.named_node(l));
+                        aircraft.taxi_route = origin.zip(destination).and_then(|(o, d)| ground.taxi_graph.route(o, d));
+                        aircraft.conformance = None;
+                        match &aircraft.taxi_route {
+                            Some(route) => route.instruction(),
+                            None => "Taxi Approved".to_string(),
+                        }
+                    }
+                    ClearanceKind::LineUp => "Line Up & Wait".to_string(),
+                    ClearanceKind::TakeOff => "Cleared for Takeoff".to_string(),
+                    ClearanceKind::Land => match runway {
+                        Some(runway) => format!("Cleared to Land Runway {}", runway),
+                        None => "Cleared to Land".to_string(),
+                    },
+                });
+            }
+            Command::RevokeClearance { clearance, .. } => {
+                inputs.clearances.retain(|c| c.kind != *clearance);
+                if !inputs.withheld.contains(clearance) {
+                    inputs.withheld.push(*clearance);
+                }
+                aircraft.atc_message = Some(match clearance {
+                    ClearanceKind::Pushback => "Pushback Cancelled - Hold Position",
+                    ClearanceKind::Taxi => {
+                        aircraft.taxi_route = None;
+                        aircraft.conformance = None;
+                        "Taxi Clearance Cancelled - Hold Position"
+                    }
+                    ClearanceKind::LineUp => "Line-up Cancelled - Hold Short",
+                    ClearanceKind::TakeOff => "Take-off Clearance Cancelled - Hold Position",
+                    ClearanceKind::Land => "Landing Clearance Cancelled - Go Around",
+                }.to_string());
+            }
+            Command::AssignStand { stand, .. } => {
+                if let Some(stand) = stand {
+                    let ground = airport.ok_or("No ground data for this airport")?;
+                    let node = ground.stands.iter().find(|s| s.name.eq_ignore_ascii_case(stand))
+                        .ok_or_else(|| format!("Unknown stand {}", stand))?;
+                    aircraft.stand = Some(node.name.clone());
+                }
+                inputs.stand = stand.as_ref().and_then(|_| aircraft.stand.clone());
+            }
+            Command::AssignRunway { runway, .. } => {
+                inputs.runway = match runway {
+                    Some(runway) => {
+                        let ground = airport.ok_or("No ground data for this airport")?;
+                        let end = ground.runway_end(runway).ok_or_else(|| format!("Unknown runway {}", runway))?;
+                        Some(end.name.clone())
+                    }
+                    None => None,
+                };
+                // Departure routes are re-planned to the new runway by the next cycle
+                if aircraft.on_ground && !inputs.has(ClearanceKind::Taxi) {
+                    aircraft.taxi_route = None;
+                    aircraft.conformance = None;
+                }
+            }
+            Command::FixSequence { position, .. } => {
+                if *position == Some(0) {
+                    return Err("Sequence positions start at 1".to_string());
+                }
+                inputs.sequence_position = *position;
+            }
+            Command::OverridePhase { phase, .. } => {
+                inputs.phase = *phase;
+                if let Some(phase) = phase {
+                    aircraft.phase = *phase;
+                }
+            }
+            Command::AcknowledgeAlert { .. } => {
+                if alerts.acknowledge_aircraft(&icao24, controller, now) == 0 {
+                    return Err(format!("{} has no unacknowledged alert", name));
+                }
+                let shown = aircraft.advisory.as_deref().and_then(|a| alerts.for_message(&icao24, a));
+                if shown.is_some_and(|alert| alert.is_quiet()) {
+                    aircraft.advisory = None;
+                }
+            }
+        }
+
+        if inputs.is_empty() {
+            self.aircraft.remove(&icao24);
+        } else {
+            self.aircraft.insert(icao24.clone(), inputs.clone());
+        }
+        self.log.push_back(CommandRecord { time: now, controller: controller.to_string(), command });
+        while self.log.len() > COMMAND_LOG_LEN {
+            self.log.pop_front();
+        }
+        Ok(inputs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::euroscope::{GeoFeature, GeoSegment, Label, SectorData};
+    use crate::logic::airport::Coordinate;
+    use crate::logic::sequencing::{process_ground_traffic, RunwayContext};
+
+    const STAND_10: (f64, f64) = (51.8795, 0.2302);
+
+    /// Stand 10 beside taxiway J, which runs east to holding point H1.
+    fn airport() -> Airport {
+        let at = |(lat, lon): (f64, f64)| Coordinate { lat, lon };
+        let label = |section: &str, text: &str, position| Label { section: section.to_string(), text: text.to_string(), position: at(position) };
+        Airport::from_sector(&SectorData {
+            icao: "EGSS".to_string(),
+            geo: vec![GeoFeature {
+                section: "Taxiway_Centrelines".to_string(),
+                name: "Taxiway_Centrelines_-__-_J".to_string(),
+                segments: vec![GeoSegment { from: at((51.880, 0.230)), to: at((51.880, 0.236)), colour: "smrCentreline".to_string() }],
+            }],
+            labels: vec![label("Holding Points", "H1", (51.880, 0.236)), label("Stands", "10", STAND_10)],
+            ..Default::default()
+        })
+    }
+
+    fn aircraft(icao24: &str, on_ground: bool) -> Aircraft {
+        Aircraft {
+            icao24: icao24.to_string(),
+            callsign: Some("EZY12".to_string()),
+            on_ground,
+            latitude: Some(STAND_10.0),
+            longitude: Some(STAND_10.1),
+            velocity: Some(0.0),
+            ..Aircraft::default()
+        }
+    }
+
+    fn clearance(icao24: &str, clearance: ClearanceKind, limit: Option<&str>) -> Command {
+        Command::IssueClearance { icao24: icao24.to_string(), clearance, limit: limit.map(str::to_string) }
+    }
+
+    #[test]
+    fn rejected_commands_leave_nothing_behind() {
+        let airport = airport();
+        let mut aircraft = HashMap::from([
+            ("aaa001".to_string(), aircraft("aaa001", true)),
+            ("aaa002".to_string(), aircraft("aaa002", false)),
+        ]);
+        let mut control = ControllerInputs::default();
+        let mut alerts = AlertBoard::default();
+
+        let cases = [
+            (clearance("bbb001", ClearanceKind::Pushback, None), "Unknown aircraft bbb001"),
+            (clearance("aaa001", ClearanceKind::Land, None), "EZY12 is on the ground"),
+            (clearance("aaa002", ClearanceKind::TakeOff, None), "EZY12 is airborne"),
+            (clearance("aaa001", ClearanceKind::Taxi, Some("Z9")), "Unknown taxi limit Z9"),
+            (clearance("aaa001", ClearanceKind::Taxi, None), "A taxi clearance needs a limit, a holding point or stand"),
+            (clearance("aaa001", ClearanceKind::Pushback, Some("H1")), "Only a taxi clearance takes a limit"),
+            (Command::AssignStand { icao24: "aaa001".to_string(), stand: Some("99".to_string()) }, "Unknown stand 99"),
+            (Command::AssignRunway { icao24: "aaa001".to_string(), runway: Some("09".to_string()) }, "Unknown runway 09"),
+            (Command::FixSequence { icao24: "aaa002".to_string(), position: Some(0) }, "Sequence positions start at 1"),
+            (Command::AcknowledgeAlert { icao24: "aaa001".to_string() }, "EZY12 has no unacknowledged alert"),
+        ];
+        for (command, error) in cases {
+            let result = control.apply(&mut aircraft, Some(&airport), &mut alerts, command, "tower", 100);
+            assert_eq!(result.unwrap_err(), error);
+        }
+        assert!(control.aircraft.is_empty());
+        assert!(control.log.is_empty());
+        assert!(aircraft.values().all(|a| a.atc_message.is_none() && a.taxi_route.is_none()));
+
+        // Without ground data nothing that needs it is accepted
+        let result = control.apply(&mut aircraft, None, &mut alerts, clearance("aaa001", ClearanceKind::Pushback, None), "tower", 100);
+        assert_eq!(result.unwrap_err(), "No ground data for this airport");
+        assert!(control.aircraft.is_empty());
+    }
+
+    #[test]
+    fn a_taxi_clearance_plans_the_route_to_its_limit() {
+        let airport = airport();
+        let mut aircraft = HashMap::from([("aaa001".to_string(), aircraft("aaa001", true))]);
+        let mut control = ControllerInputs::default();
+        let inputs = control.apply(&mut aircraft, Some(&airport), &mut AlertBoard::default(), clearance("AAA001", ClearanceKind::Taxi, Some("H1")), "ground", 100).unwrap();
+
+        let issued = inputs.clearance(ClearanceKind::Taxi).unwrap();
+        assert_eq!((issued.limit.as_deref(), issued.issued_by.as_str()), (Some("H1"), "ground"));
+        assert_eq!(aircraft["aaa001"].atc_message.as_deref(), Some("Taxi via J to H1"));
+        assert_eq!(aircraft["aaa001"].taxi_route.as_ref().map(|r| r.destination.as_str()), Some("H1"));
+        assert_eq!(control.log.len(), 1);
+    }
+
+    #[test]
+    fn controller_inputs_survive_the_ground_cycle() {
+        let airport = airport();
+        let mut context = RunwayContext::default();
+        let mut aircraft = HashMap::from([
+            ("aaa001".to_string(), Aircraft { ground_state: Some("Pushback".to_string()), velocity: Some(8.0), ..aircraft("aaa001", true) }),
+            ("aaa002".to_string(), Aircraft { icao24: "aaa002".to_string(), ..aircraft("aaa002", true) }),
+        ]);
+        let mut alerts = AlertBoard::default();
+        let mut apply = |context: &mut RunwayContext, aircraft: &mut HashMap<String, Aircraft>, command| {
+            context.control.apply(aircraft, Some(&airport), &mut alerts, command, "tower", 100).unwrap();
+        };
+        apply(&mut context, &mut aircraft, clearance("aaa001", ClearanceKind::Taxi, Some("H1")));
+        apply(&mut context, &mut aircraft, Command::OverridePhase { icao24: "aaa001".to_string(), phase: Some(Phase::TaxiOut) });
+        apply(&mut context, &mut aircraft, Command::RevokeClearance { icao24: "aaa002".to_string(), clearance: ClearanceKind::Pushback });
+
+        for _ in 0..2 {
+            process_ground_traffic(&mut aircraft, &airport, &mut context, 100);
+        }
+
+        // The engine moved the aircraft on to taxiing but kept the controller's route
+        let taxiing = &aircraft["aaa001"];
+        assert_eq!(taxiing.ground_state.as_deref(), Some("Taxiing"));
+        assert_eq!(taxiing.taxi_route.as_ref().map(|r| r.instruction()).as_deref(), Some("Taxi via J to H1"));
+        let inputs = context.control.get("aaa001").unwrap();
+        assert!(inputs.has(ClearanceKind::Taxi));
+        assert_eq!(inputs.phase, Some(Phase::TaxiOut));
+
+        // A revoked pushback is not granted when the aircraft starts moving
+        aircraft.get_mut("aaa002").unwrap().velocity = Some(3.0);
+        process_ground_traffic(&mut aircraft, &airport, &mut context, 100);
+        assert_eq!(aircraft["aaa002"].atc_message.as_deref(), Some("Pushback Not Approved - Hold Position"));
+        assert!(context.control.get("aaa002").unwrap().withholds(ClearanceKind::Pushback));
+    }
+}
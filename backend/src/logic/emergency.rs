@@ -0,0 +1,438 @@
+use serde::Serialize;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::logic::airport::{Airport, RunwayEnd};
+use crate::logic::alerts::{AlertCondition, AlertKind};
+use crate::logic::control::ControllerInputs;
+use crate::logic::phases::final_approach_end;
+use crate::models::{Aircraft, Phase};
+
+/// Ended emergencies kept per airport for `/api/airports/:code/emergencies`.
+const ENDED_HISTORY: usize = 50;
+/// An arrival not yet established on final is inbound to its runway inside this range.
+const INBOUND_RANGE_NM: f64 = 15.0;
+
+/// Steps for a 7500 squawk, worked through by the controller.
+const SECURITY_WORKFLOW: [&str; 5] = [
+    "Confirm the code discreetly; do not refer to it unless the crew does",
+    "Notify airport security and the police",
+    "Give priority to land and keep other traffic clear",
+    "Assign the isolated aircraft parking position",
+    "Hold the aircraft there until security takes over",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EmergencyKind {
+    /// 7700
+    General,
+    /// 7600
+    LostComms,
+    /// 7500
+    UnlawfulInterference,
+}
+
+impl EmergencyKind {
+    pub fn from_squawk(squawk: &str) -> Option<Self> {
+        match squawk {
+            "7700" => Some(EmergencyKind::General),
+            "7600" => Some(EmergencyKind::LostComms),
+            "7500" => Some(EmergencyKind::UnlawfulInterference),
+            _ => None,
+        }
+    }
+
+    fn alert_kind(self) -> AlertKind {
+        match self {
+            EmergencyKind::General => AlertKind::Emergency,
+            EmergencyKind::LostComms => AlertKind::LostComms,
+            EmergencyKind::UnlawfulInterference => AlertKind::UnlawfulInterference,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkflowStep {
+    pub action: &'static str,
+    pub done_by: Option<String>,
+    pub done_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Emergency {
+    pub icao24: String,
+    pub callsign: Option<String>,
+    pub kind: EmergencyKind,
+    pub started_at: i64,
+    pub ended_at: Option<i64>,
+    /// Runway end the aircraft is inbound to, while it is.
+    pub inbound_to: Option<String>,
+    /// Runway end it is expected to land on: the one assigned, else the arrival runway.
+    pub expected_runway: Option<String>,
+    /// Runway closed for the aircraft: the one it is inbound to or on.
+    pub closes_runway: Option<String>,
+    /// Security workflow, for unlawful interference only.
+    pub workflow: Vec<WorkflowStep>,
+}
+
+impl Emergency {
+    fn callsign(&self) -> &str {
+        self.callsign.as_deref().unwrap_or(&self.icao24).trim()
+    }
+
+    fn set_kind(&mut self, kind: EmergencyKind) {
+        self.kind = kind;
+        if kind == EmergencyKind::UnlawfulInterference && self.workflow.is_empty() {
+            self.workflow = SECURITY_WORKFLOW.iter()
+                .map(|&action| WorkflowStep { action, done_by: None, done_at: None })
+                .collect();
+        }
+    }
+
+    /// Alert for the emergency itself, with what to expect of the aircraft.
+    fn condition(&self, aircraft: &Aircraft) -> AlertCondition {
+        let callsign = self.callsign();
+        let message = match self.kind {
+            EmergencyKind::General => match &self.inbound_to {
+                Some(end) => format!("EMERGENCY {} - SQUAWK 7700, PRIORITY LANDING RUNWAY {}", callsign, end),
+                None => format!("EMERGENCY {} - SQUAWK 7700", callsign),
+            },
+            EmergencyKind::LostComms if aircraft.on_ground => {
+                format!("LOST COMMS {} - LIGHT SIGNALS ONLY", callsign)
+            }
+            EmergencyKind::LostComms => match (self.inbound_to.as_deref().or(self.expected_runway.as_deref()), aircraft.eta) {
+                (Some(end), Some(eta)) => format!("LOST COMMS {} - EXPECT APPROACH RUNWAY {}, ETA {}", callsign, end, format_time(eta)),
+                (Some(end), None) => format!("LOST COMMS {} - EXPECT APPROACH RUNWAY {}", callsign, end),
+                (None, _) => format!("LOST COMMS {} - EXPECT LAST CLEARANCE TO BE FOLLOWED", callsign),
+            },
+            EmergencyKind::UnlawfulInterference => {
+                let done = self.workflow.iter().filter(|s| s.done_at.is_some()).count();
+                match self.workflow.iter().find(|s| s.done_at.is_none()) {
+                    Some(next) => format!("UNLAWFUL INTERFERENCE {} - SECURITY {}/{}: {}", callsign, done, self.workflow.len(), next.action.to_uppercase()),
+                    None => format!("UNLAWFUL INTERFERENCE {} - SECURITY WORKFLOW COMPLETE", callsign),
+                }
+            }
+        };
+        AlertCondition::new(self.kind.alert_kind(), aircraft, message)
+    }
+}
+
+fn format_time(timestamp: i64) -> String {
+    chrono::DateTime::from_timestamp(timestamp, 0)
+        .map(|t| t.format("%H%MZ").to_string())
+        .unwrap_or_default()
+}
+
+/// Emergencies at one airport, from the first emergency squawk until the aircraft
+/// squawks something else or is no longer tracked. A missing squawk does not end one.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct EmergencyTracker {
+    pub active: Vec<Emergency>,
+    pub ended: VecDeque<Emergency>,
+}
+
+impl EmergencyTracker {
+    /// Starts, updates and ends emergencies from the current squawks, and works out which
+    /// runway each closes. Only an aircraft inbound to a runway, or on it, closes it.
+    pub fn update(
+        &mut self,
+        aircraft_map: &HashMap<String, Aircraft>,
+        airport: &Airport,
+        control: &ControllerInputs,
+        arrival_end: Option<&RunwayEnd>,
+        now: i64,
+    ) {
+        for aircraft in aircraft_map.values() {
+            let Some(kind) = aircraft.squawk.as_deref().and_then(EmergencyKind::from_squawk) else {
+                continue;
+            };
+            match self.active.iter_mut().find(|e| e.icao24 == aircraft.icao24) {
+                Some(emergency) if emergency.kind != kind => {
+                    println!("{}: emergency {} now squawking {}", airport.icao, emergency.callsign(), aircraft.squawk.as_deref().unwrap_or_default());
+                    emergency.set_kind(kind);
+                }
+                Some(_) => {}
+                None => {
+                    let mut emergency = Emergency {
+                        icao24: aircraft.icao24.clone(),
+                        callsign: aircraft.callsign.clone(),
+                        kind,
+                        started_at: now,
+                        ended_at: None,
+                        inbound_to: None,
+                        expected_runway: None,
+                        closes_runway: None,
+                        workflow: Vec::new(),
+                    };
+                    emergency.set_kind(kind);
+                    println!("{}: emergency {:?} declared by {}", airport.icao, kind, emergency.callsign());
+                    self.active.push(emergency);
+                }
+            }
+        }
+
+        let (active, ended): (Vec<Emergency>, Vec<Emergency>) = std::mem::take(&mut self.active).into_iter().partition(|e| {
+            aircraft_map.get(&e.icao24).is_some_and(|a| a.squawk.as_deref().is_none_or(|s| EmergencyKind::from_squawk(s).is_some()))
+        });
+        self.active = active;
+        for mut emergency in ended {
+            println!("{}: emergency {:?} for {} ended", airport.icao, emergency.kind, emergency.callsign());
+            emergency.ended_at = Some(now);
+            emergency.inbound_to = None;
+            emergency.closes_runway = None;
+            self.ended.push_back(emergency);
+        }
+        while self.ended.len() > ENDED_HISTORY {
+            self.ended.pop_front();
+        }
+
+        for emergency in &mut self.active {
+            let Some(aircraft) = aircraft_map.get(&emergency.icao24) else {
+                continue;
+            };
+            let assigned = control.get(&emergency.icao24)
+                .and_then(|i| i.runway.as_deref())
+                .and_then(|r| airport.runway_end(r));
+            let expected = assigned.or(arrival_end);
+            let inbound = inbound_end(aircraft, airport, expected);
+            let (lat, lon) = (aircraft.latitude.unwrap_or(0.0), aircraft.longitude.unwrap_or(0.0));
+            let on_runway = aircraft.on_ground.then(|| airport.runway_at(lat, lon)).flatten();
+
+            emergency.inbound_to = inbound.map(|e| e.name.clone());
+            emergency.expected_runway = expected.map(|e| e.name.clone());
+            emergency.closes_runway = inbound.and_then(|e| airport.runway_of(&e.name)).or(on_runway).map(|r| r.name.clone());
+        }
+    }
+
+    /// Aircraft squawking 7700, or 7500 as part of the security workflow, which land
+    /// ahead of everyone else.
+    pub fn priority(&self) -> HashSet<String> {
+        self.active.iter()
+            .filter(|e| e.kind != EmergencyKind::LostComms)
+            .map(|e| e.icao24.clone())
+            .collect()
+    }
+
+    /// Closed runways by name, with the emergency aircraft and the reason.
+    pub fn closures(&self, aircraft_map: &HashMap<String, Aircraft>) -> HashMap<String, (String, String)> {
+        self.active.iter()
+            .filter_map(|e| {
+                let runway = e.closes_runway.clone()?;
+                let on_ground = aircraft_map.get(&e.icao24).is_some_and(|a| a.on_ground);
+                let reason = if on_ground { "Emergency On Runway" } else { "Emergency Inbound" };
+                Some((runway, (e.icao24.clone(), reason.to_string())))
+            })
+            .collect()
+    }
+
+    /// Alerts for every active emergency and the runways they close.
+    pub fn conditions(&self, aircraft_map: &HashMap<String, Aircraft>) -> Vec<AlertCondition> {
+        let mut conditions = Vec::new();
+        for emergency in &self.active {
+            let Some(aircraft) = aircraft_map.get(&emergency.icao24) else {
+                continue;
+            };
+            conditions.push(emergency.condition(aircraft));
+            if let Some(runway) = &emergency.closes_runway {
+                let state = if aircraft.on_ground { "ON RUNWAY" } else { "INBOUND" };
+                let message = format!("RUNWAY {} CLOSED - EMERGENCY {} {}", runway, emergency.callsign(), state);
+                conditions.push(AlertCondition::new(AlertKind::RunwayClosed, aircraft, message));
+            }
+        }
+        conditions
+    }
+
+    /// Marks a security workflow step as done.
+    pub fn complete_step(&mut self, icao24: &str, step: usize, by: &str, now: i64) -> Result<Emergency, String> {
+        let emergency = self.active.iter_mut()
+            .find(|e| e.icao24.eq_ignore_ascii_case(icao24))
+            .ok_or_else(|| format!("No active emergency for {}", icao24))?;
+        let error = format!("{} has no workflow step {} ({} steps)", emergency.callsign(), step, emergency.workflow.len());
+        let entry = step.checked_sub(1).and_then(|i| emergency.workflow.get_mut(i)).ok_or(error)?;
+        if entry.done_at.is_none() {
+            entry.done_by = Some(by.to_string());
+            entry.done_at = Some(now);
+        }
+        Ok(emergency.clone())
+    }
+}
+
+/// Runway end an airborne arrival is inbound to: the final approach it is established
+/// on, else its expected runway once within range.
+fn inbound_end<'a>(aircraft: &Aircraft, airport: &'a Airport, expected: Option<&'a RunwayEnd>) -> Option<&'a RunwayEnd> {
+    if aircraft.on_ground || !matches!(aircraft.phase, Phase::Approach | Phase::Final | Phase::Landing) {
+        return None;
+    }
+    let (lat, lon) = (aircraft.latitude?, aircraft.longitude?);
+    final_approach_end(aircraft, airport).or(expected.filter(|end| end.distance_nm(lat, lon) < INBOUND_RANGE_NM))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::euroscope::{RunwayLine, SectorData};
+    use crate::logic::airport::Coordinate;
+    use crate::logic::control::AircraftInputs;
+
+    const THRESHOLD_04: (f64, f64) = (51.877039, 0.222861);
+    const THRESHOLD_22: (f64, f64) = (51.895158, 0.250044);
+
+    /// Stansted's single runway, 04/22.
+    fn airport() -> Airport {
+        let at = |(lat, lon): (f64, f64)| Coordinate { lat, lon };
+        Airport::from_sector(&SectorData {
+            icao: "EGSS".to_string(),
+            runways: vec![RunwayLine {
+                designators: ["04".to_string(), "22".to_string()],
+                thresholds: [at(THRESHOLD_04), at(THRESHOLD_22)],
+                airport: Some("EGSS".to_string()),
+            }],
+            ..Default::default()
+        })
+    }
+
+    /// A point `nm` out from the runway 22 threshold on the extended centreline.
+    fn on_final_22(nm: f64) -> (f64, f64) {
+        let bearing = 42.0_f64.to_radians();
+        (THRESHOLD_22.0 + nm * bearing.cos() / 60.0, THRESHOLD_22.1 + nm * bearing.sin() / (60.0 * THRESHOLD_22.0.to_radians().cos()))
+    }
+
+    fn squawking(icao24: &str, squawk: &str, phase: Phase, (lat, lon): (f64, f64), altitude_ft: f64) -> Aircraft {
+        Aircraft {
+            icao24: icao24.to_string(),
+            callsign: Some(format!("TST{}", &icao24[..3])),
+            squawk: Some(squawk.to_string()),
+            phase,
+            latitude: Some(lat),
+            longitude: Some(lon),
+            baro_altitude: Some(altitude_ft),
+            true_track: Some(222.0),
+            on_ground: phase == Phase::Landing && altitude_ft == 0.0,
+            ..Aircraft::default()
+        }
+    }
+
+    fn map(aircraft: impl IntoIterator<Item = Aircraft>) -> HashMap<String, Aircraft> {
+        aircraft.into_iter().map(|a| (a.icao24.clone(), a)).collect()
+    }
+
+    #[test]
+    fn general_emergencies_and_hijacks_get_priority() {
+        let airport = airport();
+        let aircraft = map([
+            squawking("400001", "7700", Phase::Cruise, (52.5, 0.5), 35_000.0),
+            squawking("400002", "7600", Phase::Cruise, (52.5, 0.6), 35_000.0),
+            squawking("400003", "7500", Phase::Cruise, (52.5, 0.7), 35_000.0),
+            squawking("400004", "1234", Phase::Cruise, (52.5, 0.8), 35_000.0),
+        ]);
+        let mut tracker = EmergencyTracker::default();
+        tracker.update(&aircraft, &airport, &ControllerInputs::default(), None, 1000);
+
+        assert_eq!(tracker.active.len(), 3);
+        assert_eq!(tracker.priority(), HashSet::from(["400001".to_string(), "400003".to_string()]));
+    }
+
+    #[test]
+    fn lost_comms_expects_the_assigned_runway_then_the_arrival_runway_then_the_last_clearance() {
+        let airport = airport();
+        let mut lost = squawking("400002", "7600", Phase::Descent, (52.5, 0.6), 12_000.0);
+        let message = |tracker: &EmergencyTracker, aircraft: &HashMap<String, Aircraft>| tracker.conditions(aircraft)[0].message.clone();
+
+        let mut control = ControllerInputs::default();
+        control.aircraft.insert("400002".to_string(), AircraftInputs { runway: Some("04".to_string()), ..Default::default() });
+        let mut tracker = EmergencyTracker::default();
+        let aircraft = map([lost.clone()]);
+        tracker.update(&aircraft, &airport, &control, airport.runway_end("22"), 1000);
+        assert_eq!(message(&tracker, &aircraft), "LOST COMMS TST400 - EXPECT APPROACH RUNWAY 04");
+
+        tracker.update(&aircraft, &airport, &ControllerInputs::default(), airport.runway_end("22"), 1002);
+        assert_eq!(message(&tracker, &aircraft), "LOST COMMS TST400 - EXPECT APPROACH RUNWAY 22");
+
+        lost.eta = Some(1_700_000_000);
+        let aircraft = map([lost]);
+        tracker.update(&aircraft, &airport, &ControllerInputs::default(), airport.runway_end("22"), 1004);
+        assert_eq!(message(&tracker, &aircraft), "LOST COMMS TST400 - EXPECT APPROACH RUNWAY 22, ETA 2213Z");
+
+        tracker.update(&aircraft, &airport, &ControllerInputs::default(), None, 1006);
+        assert_eq!(message(&tracker, &aircraft), "LOST COMMS TST400 - EXPECT LAST CLEARANCE TO BE FOLLOWED");
+    }
+
+    #[test]
+    fn security_workflow_steps() {
+        let airport = airport();
+        let aircraft = map([squawking("400003", "7500", Phase::Cruise, (52.5, 0.7), 35_000.0)]);
+        let mut tracker = EmergencyTracker::default();
+        tracker.update(&aircraft, &airport, &ControllerInputs::default(), None, 1000);
+        assert_eq!(tracker.active[0].workflow.len(), SECURITY_WORKFLOW.len());
+        assert_eq!(
+            tracker.conditions(&aircraft)[0].message,
+            "UNLAWFUL INTERFERENCE TST400 - SECURITY 0/5: CONFIRM THE CODE DISCREETLY; DO NOT REFER TO IT UNLESS THE CREW DOES"
+        );
+
+        assert_eq!(tracker.complete_step("400003", 0, "tower", 1001).unwrap_err(), "TST400 has no workflow step 0 (5 steps)");
+        assert_eq!(tracker.complete_step("400003", 6, "tower", 1001).unwrap_err(), "TST400 has no workflow step 6 (5 steps)");
+        assert_eq!(tracker.complete_step("400099", 1, "tower", 1001).unwrap_err(), "No active emergency for 400099");
+
+        let emergency = tracker.complete_step("400003", 1, "tower", 1001).unwrap();
+        assert_eq!(emergency.workflow[0].done_by.as_deref(), Some("tower"));
+        // Completing a step again keeps the first record
+        let emergency = tracker.complete_step("400003", 1, "ground", 1005).unwrap();
+        assert_eq!((emergency.workflow[0].done_by.as_deref(), emergency.workflow[0].done_at), (Some("tower"), Some(1001)));
+        assert!(tracker.conditions(&aircraft)[0].message.contains("SECURITY 1/5: NOTIFY AIRPORT SECURITY"));
+
+        for step in 2..=5 {
+            tracker.complete_step("400003", step, "tower", 1010).unwrap();
+        }
+        assert_eq!(tracker.conditions(&aircraft)[0].message, "UNLAWFUL INTERFERENCE TST400 - SECURITY WORKFLOW COMPLETE");
+
+        // A general emergency has no workflow
+        let aircraft = map([squawking("400001", "7700", Phase::Cruise, (52.5, 0.5), 35_000.0)]);
+        tracker.update(&aircraft, &airport, &ControllerInputs::default(), None, 1020);
+        assert_eq!(tracker.complete_step("400001", 1, "tower", 1021).unwrap_err(), "TST400 has no workflow step 1 (0 steps)");
+    }
+
+    #[test]
+    fn only_an_inbound_or_landed_emergency_closes_the_runway() {
+        let airport = airport();
+        let arrival = airport.runway_end("22");
+        let midfield = ((THRESHOLD_04.0 + THRESHOLD_22.0) / 2.0, (THRESHOLD_04.1 + THRESHOLD_22.1) / 2.0);
+        let cases = [
+            ("inbound at 10 nm", squawking("400001", "7700", Phase::Approach, on_final_22(10.0), 3500.0), Some("Emergency Inbound")),
+            ("on the runway", squawking("400001", "7700", Phase::Landing, midfield, 0.0), Some("Emergency On Runway")),
+            ("overflying the airport", squawking("400001", "7700", Phase::Cruise, midfield, 35_000.0), None),
+            ("descending overhead", squawking("400001", "7700", Phase::Descent, midfield, 15_000.0), None),
+            ("approaching beyond range", squawking("400001", "7700", Phase::Approach, on_final_22(INBOUND_RANGE_NM + 5.0), 7000.0), None),
+        ];
+        for (case, aircraft, reason) in cases {
+            let aircraft = map([aircraft]);
+            let mut tracker = EmergencyTracker::default();
+            tracker.update(&aircraft, &airport, &ControllerInputs::default(), arrival, 1000);
+
+            let closures = tracker.closures(&aircraft);
+            assert_eq!(closures.get("04/22").map(|(_, r)| r.as_str()), reason, "{}", case);
+            let closed = tracker.conditions(&aircraft).iter().any(|c| c.kind == AlertKind::RunwayClosed);
+            assert_eq!(closed, reason.is_some(), "{}", case);
+        }
+    }
+
+    #[test]
+    fn changing_squawk_ends_the_emergency_and_reopens_the_runway() {
+        let airport = airport();
+        let mut inbound = squawking("400001", "7700", Phase::Approach, on_final_22(8.0), 2800.0);
+        let mut tracker = EmergencyTracker::default();
+        tracker.update(&map([inbound.clone()]), &airport, &ControllerInputs::default(), airport.runway_end("22"), 1000);
+        assert_eq!(tracker.active[0].closes_runway.as_deref(), Some("04/22"));
+
+        // A missing squawk does not end it
+        inbound.squawk = None;
+        tracker.update(&map([inbound.clone()]), &airport, &ControllerInputs::default(), airport.runway_end("22"), 1002);
+        assert_eq!(tracker.active.len(), 1);
+
+        inbound.squawk = Some("2201".to_string());
+        let aircraft = map([inbound]);
+        tracker.update(&aircraft, &airport, &ControllerInputs::default(), airport.runway_end("22"), 1004);
+        assert!(tracker.active.is_empty());
+        assert_eq!(tracker.ended[0].ended_at, Some(1004));
+        assert!(tracker.closures(&aircraft).is_empty());
+    }
+}
@@ -0,0 +1,172 @@
+use serde::Serialize;
+
+use crate::logic::airport::{Airport, RunwayConfig};
+use crate::models::{Aircraft, AircraftState, Phase};
+
+/// Phase history older than this does not explain a new transition.
+const EVENT_WINDOW_S: i64 = 180;
+/// A climb-out started within this distance of the landing threshold is a missed
+/// approach at minima; one started further out is a go-around.
+const MISSED_APPROACH_NM: f64 = 1.0;
+/// Climb rate at which an aircraft back in `Approach` straight after `Final` has gone around.
+const GO_AROUND_CLIMB_FPM: f64 = 300.0;
+/// Window for the climb trend from the track history.
+const TREND_WINDOW_S: i64 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum MovementEventKind {
+    GoAround,
+    MissedApproach,
+    TouchAndGo,
+    RejectedTakeOff,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MovementEvent {
+    pub kind: MovementEventKind,
+    pub icao24: String,
+    pub callsign: Option<String>,
+    pub runway: Option<String>,
+    pub at: i64,
+}
+
+/// Detects a go-around, missed approach, touch-and-go or rejected take-off from the
+/// transition into `phase`, looking back through the recent phase history so a brief
+/// `Unknown` between, say, `Final` and `Climb` does not hide the event.
+pub fn detect_event(
+    prev: Option<&AircraftState>,
+    aircraft: &Aircraft,
+    phase: Phase,
+    airport: Option<&Airport>,
+    runways: Option<&RunwayConfig>,
+    now: i64,
+) -> Option<MovementEvent> {
+    let prev = prev.filter(|p| p.phase != phase)?;
+    let recent: Vec<Phase> = prev.transitions.iter()
+        .filter(|(p, at)| *p != Phase::Unknown && now - at <= EVENT_WINDOW_S)
+        .map(|(p, _)| *p)
+        .collect();
+    let last = *recent.last()?;
+
+    // Climbing away, whether classified as a climb or still held as an approach
+    let climbing_away = phase == Phase::Climb || (phase == Phase::Approach && last == Phase::Final && {
+        let rate = prev.vertical_trend_fpm(aircraft, now, TREND_WINDOW_S).or(aircraft.vertical_rate);
+        rate.is_some_and(|r| r > GO_AROUND_CLIMB_FPM)
+    });
+
+    let kind = if climbing_away && !aircraft.on_ground {
+        match last {
+            Phase::Landing => MovementEventKind::TouchAndGo,
+            Phase::Final => {
+                let arrival_end = airport.zip(runways).and_then(|(a, r)| r.arrival_end(a));
+                let distance_nm = match (arrival_end, aircraft.latitude, aircraft.longitude) {
+                    (Some(end), Some(lat), Some(lon)) => Some(end.approach_offset_m(lat, lon).0 / 1852.0),
+                    _ => aircraft.distance,
+                };
+                if distance_nm.is_some_and(|d| d < MISSED_APPROACH_NM) {
+                    MovementEventKind::MissedApproach
+                } else {
+                    MovementEventKind::GoAround
+                }
+            }
+            _ => return None,
+        }
+    } else if aircraft.on_ground && last == Phase::TakeOff && phase != Phase::TakeOff {
+        // Only a roll that started from the holding point or line-up, not a landing roll seen without history
+        let lined_up = recent.iter().rev().skip(1).any(|p| matches!(p, Phase::LineUp | Phase::TaxiOut));
+        if !lined_up {
+            return None;
+        }
+        MovementEventKind::RejectedTakeOff
+    } else {
+        return None;
+    };
+
+    let runway = match kind {
+        MovementEventKind::RejectedTakeOff => runways.map(|r| r.departure.clone()),
+        _ => runways.map(|r| r.arrival.clone()),
+    };
+
+    Some(MovementEvent {
+        kind,
+        icao24: aircraft.icao24.clone(),
+        callsign: aircraft.callsign.clone(),
+        runway,
+        at: now,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn aircraft(on_ground: bool, altitude_ft: f64, vertical_rate: f64, distance_nm: f64) -> Aircraft {
+        Aircraft {
+            icao24: "4ca123".to_string(),
+            callsign: Some("EIN123".to_string()),
+            on_ground,
+            baro_altitude: Some(altitude_ft),
+            vertical_rate: Some(vertical_rate),
+            distance: Some(distance_nm),
+            ..Aircraft::default()
+        }
+    }
+
+    /// Phase history with each phase entered at the given time; the track only holds
+    /// the altitude at the last one.
+    fn history(phases: &[(Phase, i64)], last: &Aircraft) -> AircraftState {
+        let (first, at) = phases[0];
+        let mut state = AircraftState::new(last, first, at);
+        for &(phase, at) in &phases[1..] {
+            state.record(last, phase, at);
+        }
+        state
+    }
+
+    #[test]
+    fn transitions() {
+        use MovementEventKind::*;
+        use Phase::*;
+
+        let runways = RunwayConfig { arrival: "22".to_string(), departure: "04".to_string() };
+        let descending = aircraft(false, 1500.0, -700.0, 4.0);
+        let climbing = aircraft(false, 1500.0, 1200.0, 4.0);
+        let climbing_at_minima = aircraft(false, 600.0, 1200.0, 0.4);
+        let levelling = aircraft(false, 1500.0, 0.0, 4.0);
+        let rolling = aircraft(true, 0.0, 0.0, 0.0);
+
+        // (case, phases entered, new phase, aircraft now, event)
+        type Case<'a> = (&'a str, &'a [(Phase, i64)], Phase, &'a Aircraft, Option<MovementEventKind>);
+        let cases: [Case; 12] = [
+            ("climb off final", &[(Approach, 0), (Final, 60)], Climb, &climbing, Some(GoAround)),
+            ("climb back into approach off final", &[(Approach, 0), (Final, 60)], Approach, &climbing, Some(GoAround)),
+            ("level approach off final", &[(Approach, 0), (Final, 60)], Approach, &levelling, None),
+            ("descending approach off final", &[(Approach, 0), (Final, 60)], Approach, &descending, None),
+            ("climb off final at minima", &[(Final, 60)], Climb, &climbing_at_minima, Some(MissedApproach)),
+            ("climb after an unknown blip", &[(Final, 60), (Unknown, 100)], Climb, &climbing, Some(GoAround)),
+            ("airborne again after touchdown", &[(Final, 30), (Landing, 60)], Climb, &climbing, Some(TouchAndGo)),
+            ("stopped after lining up", &[(TaxiOut, 0), (LineUp, 30), (TakeOff, 60)], TaxiIn, &rolling, Some(RejectedTakeOff)),
+            ("back to the holding point", &[(LineUp, 30), (TakeOff, 60)], TaxiOut, &rolling, Some(RejectedTakeOff)),
+            ("landing roll mistaken for a take-off", &[(TakeOff, 60)], TaxiIn, &rolling, None),
+            ("normal departure", &[(LineUp, 30), (TakeOff, 60)], Climb, &climbing, None),
+            ("final too long ago", &[(Final, 0), (Approach, 10)], Climb, &climbing, None),
+        ];
+        for (case, phases, phase, aircraft, expected) in cases {
+            let state = history(phases, aircraft);
+            let event = detect_event(Some(&state), aircraft, phase, None, Some(&runways), 200);
+            assert_eq!(event.as_ref().map(|e| e.kind), expected, "{}", case);
+            if let Some(event) = event {
+                let runway = if event.kind == RejectedTakeOff { "04" } else { "22" };
+                assert_eq!(event.runway.as_deref(), Some(runway), "{}", case);
+            }
+        }
+    }
+
+    #[test]
+    fn no_event_without_a_phase_change() {
+        let climbing = aircraft(false, 1500.0, 1200.0, 4.0);
+        let state = history(&[(Phase::Final, 0), (Phase::Climb, 100)], &climbing);
+        assert!(detect_event(Some(&state), &climbing, Phase::Climb, None, None, 200).is_none());
+        assert!(detect_event(None, &climbing, Phase::Climb, None, None, 200).is_none());
+    }
+}
@@ -1,78 +1,206 @@
-use geo::{Polygon, Point, Contains};
-use geo::coord;
+use geo::{Contains, LineString, MultiPolygon, Point, Polygon};
+use serde::{Deserialize, Serialize};
 
+use crate::euroscope::{Region, ZoneTags};
+use crate::logic::airport::{Runway, Taxiway};
+use crate::logic::routing::taxiway_designator;
+
+/// Margin either side of the pavement still treated as on the runway. Holding points
+/// sit 90 m or more from the centreline, so traffic waiting at them stays outside.
+const RUNWAY_SIDE_MARGIN_M: f64 = 15.0;
+/// Extension of the strip beyond each threshold.
+const RUNWAY_END_MARGIN_M: f64 = 60.0;
+/// Half-width of the polygon buffered around a taxiway centreline.
+const TAXIWAY_HALF_WIDTH_M: f64 = 15.0;
+/// `[REGIONS]` colours that fill paved apron in SMR maps.
+const APRON_COLOURS: [&str; 2] = ["smrgrey", "smrapron"];
+
+/// Kinds of ground zone, in the order they take precedence where zones overlap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum ZoneKind {
+    RunwayStrip,
+    Taxiway,
+    Stand,
+    Apron,
+}
+
+impl ZoneKind {
+    fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_uppercase().as_str() {
+            "RUNWAY" | "RUNWAYSTRIP" => Some(ZoneKind::RunwayStrip),
+            "TAXIWAY" => Some(ZoneKind::Taxiway),
+            "STAND" => Some(ZoneKind::Stand),
+            "APRON" => Some(ZoneKind::Apron),
+            _ => None,
+        }
+    }
+}
+
+/// Operational meaning attached to a zone in the airport's `Zones.txt`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ZoneTag {
+    ArrivalOnly,
+    DepartureOnly,
+    DepartureQueue,
+}
+
+impl ZoneTag {
+    fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_uppercase().replace([' ', '_'], "-").as_str() {
+            "ARRIVAL-ONLY" => Some(ZoneTag::ArrivalOnly),
+            "DEPARTURE-ONLY" => Some(ZoneTag::DepartureOnly),
+            "DEPARTURE-QUEUE" => Some(ZoneTag::DepartureQueue),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Zone {
+    pub kind: ZoneKind,
+    pub name: String,
+    pub tags: Vec<ZoneTag>,
+    /// Polygons in (x = lon, y = lat).
+    pub area: MultiPolygon<f64>,
+}
+
+impl Zone {
+    pub fn has_tag(&self, tag: ZoneTag) -> bool {
+        self.tags.contains(&tag)
+    }
+}
+
+/// Ground zones for one airport, built from its sector data.
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct AirportZones {
-    pub runway: Polygon,
-    pub taxiway_juliet: Polygon,
-    pub taxiway_hotel: Polygon,
+    pub zones: Vec<Zone>,
 }
 
 impl AirportZones {
-    pub fn new() -> Self {
-        // Approximate polygons for EGSS
-        // Runway 04/22: approx 3km long, SW to NE.
-        // SW End (04): 51.875, 0.22
-        // NE End (22): 51.895, 0.25
-        
-        // Creating a simple bounding box/polygon around the runway
-        let runway = Polygon::new(
-            vec![
-                coord! { x: 0.219, y: 51.874 },
-                coord! { x: 0.221, y: 51.876 },
-                coord! { x: 0.251, y: 51.896 },
-                coord! { x: 0.249, y: 51.894 },
-                coord! { x: 0.219, y: 51.874 },
-            ].into(),
-            vec![],
-        );
-
-        // Taxiway Juliet (Inner/East) - Placeholder
-        let taxiway_juliet = Polygon::new(
-             vec![
-                coord! { x: 0.225, y: 51.875 },
-                coord! { x: 0.255, y: 51.895 },
-                coord! { x: 0.256, y: 51.894 }, // Width
-                coord! { x: 0.226, y: 51.874 },
-                coord! { x: 0.225, y: 51.875 },
-            ].into(),
-            vec![],
-        );
-        
-        // Taxiway Hotel (Outer/East) - Placeholder
-        let taxiway_hotel = Polygon::new(
-             vec![
-                 coord! { x: 0.228, y: 51.876 },
-                 coord! { x: 0.258, y: 51.896 },
-                 coord! { x: 0.259, y: 51.895 },
-                 coord! { x: 0.229, y: 51.875 },
-                 coord! { x: 0.228, y: 51.876 },
-            ].into(),
-            vec![],
-        );
-
-        AirportZones {
-            runway,
-            taxiway_juliet,
-            taxiway_hotel,
+    /// Runway strips come from the threshold coordinates and width, taxiway polygons are buffered
+    /// from the centrelines (one zone per designator), and stand and apron areas are the
+    /// `[REGIONS]` polygons named as stands or drawn in an apron colour, with grass
+    /// regions inside them cut out. Tags from `Zones.txt` are applied last.
+    pub fn build(frame: LocalFrame, runways: &[Runway], taxiways: &[Taxiway], regions: &[Region], tags: &[ZoneTags]) -> Self {
+        let mut zones = Vec::new();
+
+        for runway in runways {
+            let [a, b] = [runway.ends[0].threshold, runway.ends[1].threshold];
+            let area = strip(frame, (a[0], a[1]), (b[0], b[1]), runway.width_m / 2.0 + RUNWAY_SIDE_MARGIN_M, RUNWAY_END_MARGIN_M);
+            zones.push(Zone {
+                kind: ZoneKind::RunwayStrip,
+                name: runway.name.clone(),
+                tags: Vec::new(),
+                area: MultiPolygon::new(vec![area]),
+            });
         }
-    }
 
-    pub fn check_zone(&self, lat: f64, lon: f64) -> Option<String> {
-        let p = Point::new(lon, lat); // Geo uses (x=lon, y=lat)
-        
-        if self.runway.contains(&p) {
-            return Some("Runway".to_string());
+        let mut taxiway_zones: Vec<Zone> = Vec::new();
+        for taxiway in taxiways.iter().filter(|t| t.type_ == "Taxiway" || t.type_ == "OneWay") {
+            let name = taxiway_designator(&taxiway.name);
+            let polygons = taxiway.coordinates.iter()
+                .filter(|segment| segment.len() == 2)
+                .map(|segment| strip(frame, (segment[0][0], segment[0][1]), (segment[1][0], segment[1][1]), TAXIWAY_HALF_WIDTH_M, TAXIWAY_HALF_WIDTH_M));
+
+            match taxiway_zones.iter_mut().find(|z| z.name == name) {
+                Some(zone) => zone.area.0.extend(polygons),
+                None => taxiway_zones.push(Zone {
+                    kind: ZoneKind::Taxiway,
+                    name,
+                    tags: Vec::new(),
+                    area: MultiPolygon::new(polygons.collect()),
+                }),
+            }
         }
-        if self.taxiway_juliet.contains(&p) {
-            return Some("TaxiwayJ".to_string());
+        zones.extend(taxiway_zones);
+
+        let ring = |region: &Region| -> LineString<f64> {
+            region.points.iter().map(|p| (p.lon, p.lat)).collect::<Vec<_>>().into()
+        };
+        let grass: Vec<LineString<f64>> = regions.iter()
+            .filter(|r| r.colour.to_ascii_lowercase().contains("grass") || r.name.to_ascii_lowercase().contains("grass"))
+            .map(ring)
+            .collect();
+
+        for region in regions {
+            let name = region.name.to_ascii_lowercase();
+            let kind = if name.contains("stand") {
+                ZoneKind::Stand
+            } else if name.contains("apron") || APRON_COLOURS.contains(&region.colour.to_ascii_lowercase().as_str()) {
+                ZoneKind::Apron
+            } else {
+                continue;
+            };
+
+            let outline = Polygon::new(ring(region), Vec::new());
+            let holes: Vec<LineString<f64>> = grass.iter()
+                .filter(|g| g.0.first().is_some_and(|c| outline.contains(&Point::from(*c))))
+                .cloned()
+                .collect();
+
+            zones.push(Zone {
+                kind,
+                name: region.name.clone(),
+                tags: Vec::new(),
+                area: MultiPolygon::new(vec![Polygon::new(ring(region), holes)]),
+            });
         }
-        if self.taxiway_hotel.contains(&p) {
-            return Some("TaxiwayH".to_string());
+
+        for entry in tags {
+            let Some(kind) = ZoneKind::parse(&entry.kind) else {
+                println!("Zones.txt: unknown zone kind '{}'", entry.kind);
+                continue;
+            };
+            let name = if kind == ZoneKind::Taxiway { taxiway_designator(&entry.name) } else { entry.name.clone() };
+            let Some(zone) = zones.iter_mut().find(|z| z.kind == kind && z.name.eq_ignore_ascii_case(&name)) else {
+                println!("Zones.txt: no {:?} zone named '{}'", kind, entry.name);
+                continue;
+            };
+            for tag in &entry.tags {
+                match ZoneTag::parse(tag) {
+                    Some(tag) if !zone.tags.contains(&tag) => zone.tags.push(tag),
+                    Some(_) => {}
+                    None => println!("Zones.txt: unknown tag '{}' on {}", tag, entry.name),
+                }
+            }
         }
-        None
+
+        zones.sort_by_key(|z| z.kind);
+        AirportZones { zones }
+    }
+
+    /// The highest-precedence zone containing the position.
+    pub fn check_zone(&self, lat: f64, lon: f64) -> Option<&Zone> {
+        let p = Point::new(lon, lat); // Geo uses (x=lon, y=lat)
+        self.zones.iter().find(|z| z.area.contains(&p))
     }
 }
 
+/// Rectangle of the given half-width around a->b, extended past both ends.
+fn strip(frame: LocalFrame, a: (f64, f64), b: (f64, f64), half_width: f64, extension: f64) -> Polygon<f64> {
+    let (ax, ay) = frame.to_xy(a.0, a.1);
+    let (bx, by) = frame.to_xy(b.0, b.1);
+    let length = ((bx - ax).powi(2) + (by - ay).powi(2)).sqrt().max(f64::EPSILON);
+    let (ux, uy) = ((bx - ax) / length, (by - ay) / length);
+    let (nx, ny) = (-uy * half_width, ux * half_width);
+    let (ex, ey) = (ux * extension, uy * extension);
+
+    let corners = [
+        (ax - ex + nx, ay - ey + ny),
+        (bx + ex + nx, by + ey + ny),
+        (bx + ex - nx, by + ey - ny),
+        (ax - ex - nx, ay - ey - ny),
+    ];
+    let ring: Vec<(f64, f64)> = corners.iter()
+        .map(|&(x, y)| {
+            let (lat, lon) = frame.to_lat_lon(x, y);
+            (lon, lat)
+        })
+        .collect();
+    Polygon::new(ring.into(), Vec::new())
+}
+
 pub fn haversine_distance(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
     let r = 6371.0; // Earth radius in km
     let to_rad = |d: f64| d * std::f64::consts::PI / 180.0;
@@ -88,3 +216,51 @@ pub fn haversine_distance(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
     
     r * c
 }
+
+/// Initial great-circle bearing from the first point to the second, in degrees true.
+pub fn initial_bearing(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let (phi1, phi2) = (lat1.to_radians(), lat2.to_radians());
+    let d_lon = (lon2 - lon1).to_radians();
+
+    let y = d_lon.sin() * phi2.cos();
+    let x = phi1.cos() * phi2.sin() - phi1.sin() * phi2.cos() * d_lon.cos();
+
+    (y.atan2(x).to_degrees() + 360.0) % 360.0
+}
+
+/// Flat-earth frame in metres around a reference point; accurate to well under a
+/// metre across an aerodrome, which is all the ground geometry needs.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LocalFrame {
+    lat0: f64,
+    lon0: f64,
+    cos_lat0: f64,
+}
+
+impl LocalFrame {
+    const METRES_PER_DEGREE: f64 = 6_371_000.0 * std::f64::consts::PI / 180.0;
+
+    pub fn new(lat0: f64, lon0: f64) -> Self {
+        LocalFrame { lat0, lon0, cos_lat0: lat0.to_radians().cos() }
+    }
+
+    /// (east, north) in metres.
+    pub fn to_xy(self, lat: f64, lon: f64) -> (f64, f64) {
+        (
+            (lon - self.lon0) * self.cos_lat0 * Self::METRES_PER_DEGREE,
+            (lat - self.lat0) * Self::METRES_PER_DEGREE,
+        )
+    }
+
+    pub fn to_lat_lon(self, x: f64, y: f64) -> (f64, f64) {
+        (
+            self.lat0 + y / Self::METRES_PER_DEGREE,
+            self.lon0 + x / (self.cos_lat0 * Self::METRES_PER_DEGREE),
+        )
+    }
+}
+
+/// Smallest angle between two bearings, in degrees (0-180).
+pub fn angle_difference(a: f64, b: f64) -> f64 {
+    ((a - b + 540.0) % 360.0 - 180.0).abs()
+}
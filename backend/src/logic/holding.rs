@@ -0,0 +1,352 @@
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::logic::airport::{Airport, Node};
+use crate::logic::geofence::haversine_distance;
+use crate::models::{Aircraft, AircraftState, Phase};
+
+/// Track kept for holding detection; one racetrack circuit takes about four minutes.
+const TRACK_WINDOW_S: i64 = 360;
+/// Turn accumulated over the window that marks a racetrack rather than a heading change.
+const HOLDING_TURN_DEG: f64 = 300.0;
+/// A holding pattern stays within this distance of its fix; leaving it ends the hold.
+const HOLD_AREA_NM: f64 = 12.0;
+/// No part of a racetrack moves away from the fix for longer than this: the turn at the
+/// fix and the outbound leg take about two minutes together.
+const LEAVING_WINDOW_S: i64 = 180;
+/// Holding aircraft are below this; above it they are still en route.
+const MAX_HOLDING_ALTITUDE_FT: f64 = 25_000.0;
+/// Stack levels are 1000 ft apart.
+const STACK_LEVEL_SPACING_FT: f64 = 1000.0;
+/// Time between successive aircraft leaving the bottom of a stack for the approach.
+const STACK_RELEASE_INTERVAL_S: i64 = 120;
+
+#[derive(Debug, Clone)]
+struct HoldEntry {
+    fix: String,
+    entered_at: i64,
+}
+
+/// One aircraft in a stack, lowest first.
+#[derive(Debug, Clone, Serialize)]
+pub struct StackedAircraft {
+    pub icao24: String,
+    pub callsign: Option<String>,
+    pub altitude_ft: Option<f64>,
+    /// Altitude rounded to the stack level the aircraft is holding at.
+    pub level_ft: Option<f64>,
+    /// 1 is the bottom of the stack and the next to leave.
+    pub position: usize,
+    pub entered_at: i64,
+    pub time_in_hold_s: i64,
+    /// Expected approach time.
+    pub eat: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Stack {
+    pub fix: String,
+    pub lat: f64,
+    pub lon: f64,
+    pub aircraft: Vec<StackedAircraft>,
+}
+
+/// Detects racetrack holding at the airport's stack fixes from recent track history.
+#[derive(Debug, Clone, Default)]
+pub struct HoldingMonitor {
+    holding: HashMap<String, HoldEntry>,
+}
+
+impl HoldingMonitor {
+    /// Checks the track history up to the latest position and returns the stack fix
+    /// the aircraft is holding at.
+    pub fn observe(&mut self, aircraft: &Aircraft, history: Option<&AircraftState>, airport: &Airport, now: i64) -> Option<&str> {
+        let (Some(lat), Some(lon), Some(track)) = (aircraft.latitude, aircraft.longitude, aircraft.true_track) else {
+            return None;
+        };
+        if aircraft.on_ground || aircraft.baro_altitude.unwrap_or(0.0) > MAX_HOLDING_ALTITUDE_FT {
+            self.holding.remove(&aircraft.icao24);
+            return None;
+        }
+
+        // Leaving the stack for the approach
+        if matches!(aircraft.phase, Phase::Final | Phase::Landing) {
+            self.holding.remove(&aircraft.icao24);
+            return None;
+        }
+
+        let Some(fix) = nearest_stack(airport, lat, lon) else {
+            self.holding.remove(&aircraft.icao24);
+            return None;
+        };
+
+        let mut points: Vec<(i64, f64, f64, f64)> = history.into_iter()
+            .flat_map(|h| h.recent(now, TRACK_WINDOW_S))
+            .filter(|s| s.time < now)
+            .filter_map(|s| Some((s.time, s.latitude?, s.longitude?, s.track?)))
+            .collect();
+        points.push((now, lat, lon, track));
+        let tracks: Vec<f64> = points.iter().map(|p| p.3).collect();
+        let turn = accumulated_turn(&tracks);
+
+        match self.holding.get(&aircraft.icao24) {
+            Some(entry) => {
+                // Vectored off the stack: the racetrack turns have aged out of the window,
+                // or the aircraft has kept moving away from the fix for longer than a hold allows
+                let held_fix = airport.stacks.iter().find(|s| s.name == entry.fix).unwrap_or(fix);
+                if turn < HOLDING_TURN_DEG || tracking_away(&points, held_fix, now) {
+                    println!("{} left the hold at {}", aircraft.callsign.as_deref().unwrap_or(&aircraft.icao24), entry.fix);
+                    self.holding.remove(&aircraft.icao24);
+                    return None;
+                }
+            }
+            None => {
+                // The track must span most of the window, not just a single turn
+                let spans_window = points.first().is_some_and(|p| now - p.0 >= TRACK_WINDOW_S / 2);
+                let in_area = points.iter().all(|p| haversine_distance(p.1, p.2, fix.lat, fix.lon) * 0.539957 < HOLD_AREA_NM);
+                if !spans_window || !in_area || turn < HOLDING_TURN_DEG {
+                    return None;
+                }
+                let entered_at = points.first().map(|p| p.0).unwrap_or(now);
+                println!("{} holding at {}", aircraft.callsign.as_deref().unwrap_or(&aircraft.icao24), fix.name);
+                self.holding.insert(aircraft.icao24.clone(), HoldEntry { fix: fix.name.clone(), entered_at });
+            }
+        }
+
+        self.holding.get(&aircraft.icao24).map(|h| h.fix.as_str())
+    }
+
+    /// Drops aircraft that are no longer tracked.
+    pub fn retain(&mut self, aircraft_map: &HashMap<String, Aircraft>) {
+        self.holding.retain(|k, _| aircraft_map.contains_key(k));
+    }
+
+    /// Every stack at the airport with its holding aircraft, lowest first. Aircraft
+    /// leave the bottom of the stack one release interval apart, which gives each its
+    /// expected approach time.
+    pub fn stacks(&self, airport: &Airport, aircraft_map: &HashMap<String, Aircraft>, now: i64) -> Vec<Stack> {
+        airport.stacks.iter()
+            .map(|fix| {
+                let mut held: Vec<(&Aircraft, &HoldEntry)> = self.holding.iter()
+                    .filter(|(_, h)| h.fix == fix.name)
+                    .filter_map(|(icao24, h)| Some((aircraft_map.get(icao24)?, h)))
+                    .collect();
+                held.sort_by(|a, b| {
+                    let alt = |ac: &Aircraft| ac.baro_altitude.unwrap_or(f64::MAX);
+                    alt(a.0).partial_cmp(&alt(b.0)).unwrap_or(std::cmp::Ordering::Equal)
+                });
+
+                let aircraft = held.into_iter()
+                    .enumerate()
+                    .map(|(i, (ac, h))| StackedAircraft {
+                        icao24: ac.icao24.clone(),
+                        callsign: ac.callsign.clone(),
+                        altitude_ft: ac.baro_altitude,
+                        level_ft: ac.baro_altitude.map(|a| (a / STACK_LEVEL_SPACING_FT).round() * STACK_LEVEL_SPACING_FT),
+                        position: i + 1,
+                        entered_at: h.entered_at,
+                        time_in_hold_s: now - h.entered_at,
+                        eat: now + i as i64 * STACK_RELEASE_INTERVAL_S,
+                    })
+                    .collect();
+
+                Stack { fix: fix.name.clone(), lat: fix.lat, lon: fix.lon, aircraft }
+            })
+            .collect()
+    }
+}
+
+fn nearest_stack(airport: &Airport, lat: f64, lon: f64) -> Option<&Node> {
+    airport.stacks.iter()
+        .map(|s| (s, haversine_distance(lat, lon, s.lat, s.lon) * 0.539957))
+        .filter(|(_, d)| *d < HOLD_AREA_NM)
+        .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
+        .map(|(s, _)| s)
+}
+
+/// Whether every sample over the leaving window is further from `fix` than the one before.
+fn tracking_away(points: &[(i64, f64, f64, f64)], fix: &Node, now: i64) -> bool {
+    if points.first().is_none_or(|p| now - p.0 < LEAVING_WINDOW_S) {
+        return false;
+    }
+    let distances: Vec<f64> = points.iter()
+        .filter(|p| now - p.0 <= LEAVING_WINDOW_S)
+        .map(|p| haversine_distance(p.1, p.2, fix.lat, fix.lon))
+        .collect();
+    distances.len() >= 2 && distances.windows(2).all(|w| w[1] > w[0])
+}
+
+/// Net turn through the track history in degrees; a full racetrack circuit is 360.
+fn accumulated_turn(tracks: &[f64]) -> f64 {
+    tracks.windows(2)
+        .map(|w| (w[1] - w[0] + 540.0) % 360.0 - 180.0)
+        .sum::<f64>()
+        .abs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::euroscope::{AerodromeInfo, Fix, SectorData};
+    use crate::logic::airport::Coordinate;
+
+    const ABBOT: (f64, f64) = (52.01611, 0.59958);
+    const LOREL: (f64, f64) = (52.01394, -0.05264);
+    const STEP_S: i64 = 4;
+    /// 180 kt in nm per second.
+    const SPEED_NM_S: f64 = 0.05;
+    /// Rate one turn in degrees per second.
+    const RATE_ONE: f64 = 3.0;
+
+    fn airport() -> Airport {
+        let fix = |name: &str, (lat, lon): (f64, f64)| Fix { name: name.to_string(), position: Coordinate { lat, lon } };
+        Airport::from_sector(&SectorData {
+            icao: "EGSS".to_string(),
+            fixes: vec![fix("ABBOT", ABBOT), fix("LOREL", LOREL)],
+            aerodrome: AerodromeInfo { stacks: vec!["ABBOT".to_string(), "LOREL".to_string()], ..Default::default() },
+            ..Default::default()
+        })
+    }
+
+    /// An arrival flown step by step, with its track history kept as the monitor does.
+    struct Flight {
+        aircraft: Aircraft,
+        history: Option<AircraftState>,
+        now: i64,
+    }
+
+    impl Flight {
+        fn new((lat, lon): (f64, f64), track: f64) -> Self {
+            let aircraft = Aircraft {
+                icao24: "406abc".to_string(),
+                callsign: Some("RYR1AB".to_string()),
+                latitude: Some(lat),
+                longitude: Some(lon),
+                true_track: Some(track),
+                baro_altitude: Some(9000.0),
+                velocity: Some(180.0),
+                phase: Phase::Approach,
+                ..Aircraft::default()
+            };
+            Flight { aircraft, history: None, now: 1_700_000_000 }
+        }
+
+        /// Flies for `seconds` turning at `turn_rate` degrees per second, returning the
+        /// stack fix reported at each step.
+        fn fly(&mut self, monitor: &mut HoldingMonitor, airport: &Airport, seconds: i64, turn_rate: f64) -> Vec<Option<String>> {
+            let mut fixes = Vec::new();
+            for _ in 0..seconds / STEP_S {
+                let ac = &mut self.aircraft;
+                let track = (ac.true_track.unwrap() + turn_rate * STEP_S as f64).rem_euclid(360.0);
+                let (lat, lon) = (ac.latitude.unwrap(), ac.longitude.unwrap());
+                let distance = SPEED_NM_S * STEP_S as f64;
+                ac.true_track = Some(track);
+                ac.latitude = Some(lat + distance * track.to_radians().cos() / 60.0);
+                ac.longitude = Some(lon + distance * track.to_radians().sin() / (60.0 * lat.to_radians().cos()));
+                self.now += STEP_S;
+
+                fixes.push(monitor.observe(&self.aircraft, self.history.as_ref(), airport, self.now).map(str::to_string));
+                match &mut self.history {
+                    Some(history) => history.record(&self.aircraft, Phase::Approach, self.now),
+                    None => self.history = Some(AircraftState::new(&self.aircraft, Phase::Approach, self.now)),
+                }
+            }
+            fixes
+        }
+
+        /// One racetrack circuit with one-minute legs, starting at the fix.
+        fn circuit(&mut self, monitor: &mut HoldingMonitor, airport: &Airport) -> Vec<Option<String>> {
+            let mut fixes = self.fly(monitor, airport, 60, RATE_ONE);
+            fixes.extend(self.fly(monitor, airport, 60, 0.0));
+            fixes.extend(self.fly(monitor, airport, 60, RATE_ONE));
+            fixes.extend(self.fly(monitor, airport, 60, 0.0));
+            fixes
+        }
+
+        fn distance_nm(&self, (lat, lon): (f64, f64)) -> f64 {
+            haversine_distance(self.aircraft.latitude.unwrap(), self.aircraft.longitude.unwrap(), lat, lon) * 0.539957
+        }
+    }
+
+    #[test]
+    fn a_racetrack_at_a_stack_fix_is_holding() {
+        let airport = airport();
+        let mut monitor = HoldingMonitor::default();
+        let mut flight = Flight::new(ABBOT, 270.0);
+
+        // One turn is a heading change, not a hold
+        assert!(flight.fly(&mut monitor, &airport, 60, RATE_ONE).iter().all(Option::is_none));
+        flight.fly(&mut monitor, &airport, 60, 0.0);
+        flight.fly(&mut monitor, &airport, 60, RATE_ONE);
+        flight.fly(&mut monitor, &airport, 60, 0.0);
+
+        let fixes = flight.circuit(&mut monitor, &airport);
+        assert_eq!(fixes.last().unwrap().as_deref(), Some("ABBOT"));
+
+        // Still holding on every step of further circuits
+        for _ in 0..3 {
+            let fixes = flight.circuit(&mut monitor, &airport);
+            assert!(fixes.iter().all(|f| f.as_deref() == Some("ABBOT")), "{:?}", fixes);
+        }
+        let stacks = monitor.stacks(&airport, &HashMap::from([(flight.aircraft.icao24.clone(), flight.aircraft.clone())]), flight.now);
+        assert_eq!(stacks[0].aircraft.len(), 1);
+        assert!(stacks[1].aircraft.is_empty());
+    }
+
+    #[test]
+    fn leaving_the_stack_on_vectors_ends_the_hold() {
+        let airport = airport();
+        let mut monitor = HoldingMonitor::default();
+        let mut flight = Flight::new(ABBOT, 270.0);
+        for _ in 0..3 {
+            flight.circuit(&mut monitor, &airport);
+        }
+        assert_eq!(monitor.holding.get("406abc").map(|h| h.fix.as_str()), Some("ABBOT"));
+
+        // Vectored south-west towards the airport, still in Approach and inside the area
+        flight.aircraft.true_track = Some(240.0);
+        let fixes = flight.fly(&mut monitor, &airport, 200, 0.0);
+        assert!(flight.distance_nm(ABBOT) < HOLD_AREA_NM);
+        assert_eq!(fixes.last().unwrap(), &None);
+        assert!(!monitor.holding.contains_key("406abc"));
+    }
+
+    #[test]
+    fn a_dog_leg_off_the_stack_ends_the_hold() {
+        let airport = airport();
+        let mut monitor = HoldingMonitor::default();
+        let mut flight = Flight::new(LOREL, 90.0);
+        for _ in 0..3 {
+            flight.circuit(&mut monitor, &airport);
+        }
+        assert!(monitor.holding.contains_key("406abc"));
+
+        // South for a minute, then a left turn onto east
+        flight.aircraft.true_track = Some(180.0);
+        flight.fly(&mut monitor, &airport, 60, 0.0);
+        flight.fly(&mut monitor, &airport, 30, -RATE_ONE);
+        while monitor.holding.contains_key("406abc") {
+            flight.fly(&mut monitor, &airport, STEP_S, 0.0);
+            assert!(flight.distance_nm(LOREL) < HOLD_AREA_NM, "still holding at the edge of the area");
+        }
+    }
+
+    #[test]
+    fn traffic_passing_a_stack_fix_is_not_holding() {
+        let airport = airport();
+
+        // Straight over ABBOT from ten miles north-east
+        let mut monitor = HoldingMonitor::default();
+        let start = (ABBOT.0 + 7.0 / 60.0, ABBOT.1 + 7.0 / (60.0 * ABBOT.0.to_radians().cos()));
+        let mut flight = Flight::new(start, 225.0);
+        assert!(flight.fly(&mut monitor, &airport, 400, 0.0).iter().all(Option::is_none));
+
+        // Past LOREL with a single turn onto the approach
+        let mut monitor = HoldingMonitor::default();
+        let mut flight = Flight::new((LOREL.0 + 8.0 / 60.0, LOREL.1), 180.0);
+        let mut fixes = flight.fly(&mut monitor, &airport, 160, 0.0);
+        fixes.extend(flight.fly(&mut monitor, &airport, 30, RATE_ONE));
+        fixes.extend(flight.fly(&mut monitor, &airport, 300, 0.0));
+        assert!(fixes.iter().all(Option::is_none));
+    }
+}
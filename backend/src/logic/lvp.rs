@@ -0,0 +1,145 @@
+use serde::Serialize;
+
+use crate::logic::separation::{SeparationScheme, SpacingLadder};
+use crate::weather::Metar;
+
+/// Procedures start when RVR (or visibility when no RVR is reported) falls below this,
+/// or the ceiling is at or below `LVP_CEILING_FT`.
+const LVP_RVR_M: u32 = 600;
+const LVP_CEILING_FT: u32 = 200;
+/// Procedures are cancelled only once conditions have recovered past these, so a
+/// fluctuating RVR does not toggle them every report.
+const CANCEL_RVR_M: u32 = 800;
+const CANCEL_CEILING_FT: u32 = 300;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum LvpTrigger {
+    Manual,
+    Weather,
+}
+
+/// Low visibility procedures state for one airport.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LvpState {
+    pub active: bool,
+    pub trigger: Option<LvpTrigger>,
+    /// Controller override; `None` follows the weather.
+    pub manual: Option<bool>,
+    pub since: Option<i64>,
+    pub reason: Option<String>,
+}
+
+impl LvpState {
+    /// Sets or clears the controller override. Once cleared, the next weather update
+    /// decides whether procedures stay in force.
+    pub fn set_manual(&mut self, airport: &str, active: Option<bool>, now: i64) {
+        self.manual = active;
+        if let Some(active) = active {
+            let reason = if active { "Declared by controller" } else { "Cancelled by controller" };
+            self.set(airport, active, LvpTrigger::Manual, reason.to_string(), now);
+        }
+    }
+
+    /// Re-evaluates the weather trigger from the latest METAR. Has no effect while a
+    /// manual override is set. Just after an override is cleared the state is taken
+    /// from the weather alone, which without a METAR means no procedures.
+    pub fn update_from_weather(&mut self, airport: &str, metar: Option<&Metar>, now: i64) {
+        if self.manual.is_some() {
+            return;
+        }
+        let override_cleared = self.trigger == Some(LvpTrigger::Manual);
+        let Some(metar) = metar else {
+            if override_cleared {
+                self.set(airport, false, LvpTrigger::Weather, "No METAR".to_string(), now);
+            }
+            return;
+        };
+
+        let range = metar.min_rvr_m().or(metar.conditions.visibility_m);
+        let ceiling = metar.ceiling_ft();
+        let describe = || {
+            let range = range.map(|r| format!("RVR/VIS {}m", r)).unwrap_or_else(|| "RVR/VIS not reported".to_string());
+            match ceiling {
+                Some(c) => format!("{}, ceiling {}ft", range, c),
+                None => range,
+            }
+        };
+
+        let below = range.is_some_and(|r| r < LVP_RVR_M) || ceiling.is_some_and(|c| c <= LVP_CEILING_FT);
+        let recovered = range.is_none_or(|r| r >= CANCEL_RVR_M) && ceiling.is_none_or(|c| c >= CANCEL_CEILING_FT);
+
+        if override_cleared {
+            self.set(airport, below, LvpTrigger::Weather, describe(), now);
+        } else if !self.active && below {
+            self.set(airport, true, LvpTrigger::Weather, describe(), now);
+        } else if self.active && recovered {
+            self.set(airport, false, LvpTrigger::Weather, describe(), now);
+        }
+    }
+
+    fn set(&mut self, airport: &str, active: bool, trigger: LvpTrigger, reason: String, now: i64) {
+        if self.active != active {
+            println!("{}: LVP {} ({})", airport, if active { "in force" } else { "cancelled" }, reason);
+            self.since = Some(now);
+        }
+        self.active = active;
+        self.trigger = Some(trigger);
+        self.reason = Some(reason);
+    }
+}
+
+/// Thresholds used by ground sequencing and approach spacing.
+#[derive(Debug, Clone, Copy)]
+pub struct Procedures {
+    pub lvp: bool,
+    pub ladder: SpacingLadder,
+    /// No departure is released while an arrival is inside this distance from the threshold.
+    pub final_protection_nm: f64,
+    /// Minimum time between successive take-offs.
+    pub departure_interval_s: i64,
+}
+
+impl Procedures {
+    pub const NORMAL: Procedures = Procedures {
+        lvp: false,
+        ladder: SpacingLadder::NORMAL,
+        final_protection_nm: 4.0,
+        departure_interval_s: 120,
+    };
+
+    /// Departures wait until the arrival has landed clear of the ILS sensitive area.
+    pub const LVP: Procedures = Procedures {
+        lvp: true,
+        ladder: SpacingLadder::LVP,
+        final_protection_nm: 6.0,
+        departure_interval_s: 180,
+    };
+
+    /// Procedures in force; outside LVP the ladder follows the airport's separation scheme.
+    pub fn for_state(lvp: &LvpState, separation: SeparationScheme) -> Self {
+        if lvp.active {
+            Procedures::LVP
+        } else {
+            Procedures { ladder: separation.ladder(), ..Procedures::NORMAL }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clearing_a_declaration_without_a_metar_cancels_lvp() {
+        let mut lvp = LvpState::default();
+        lvp.set_manual("EGSS", Some(true), 100);
+        lvp.update_from_weather("EGSS", None, 100);
+        assert!(lvp.active);
+
+        lvp.set_manual("EGSS", None, 200);
+        lvp.update_from_weather("EGSS", None, 200);
+        assert!(!lvp.active);
+        assert_eq!(lvp.trigger, Some(LvpTrigger::Weather));
+        assert_eq!(lvp.since, Some(200));
+    }
+}
@@ -3,3 +3,14 @@ pub mod phases;
 pub mod separation;
 pub mod sequencing;
 pub mod airport;
+pub mod routing;
+pub mod conformance;
+pub mod spatial;
+pub mod wind;
+pub mod lvp;
+pub mod events;
+pub mod aman;
+pub mod holding;
+pub mod control;
+pub mod alerts;
+pub mod emergency;
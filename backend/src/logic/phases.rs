@@ -1,10 +1,55 @@
 use crate::models::{Aircraft, AircraftState, Phase};
-use crate::logic::geofence::AirportZones;
+use crate::logic::airport::{Airport, RunwayEnd};
+use crate::logic::geofence::{angle_difference, ZoneKind, ZoneTag};
+
+/// Height above the nominal glidepath still accepted as established on final.
+const GLIDEPATH_TOLERANCE_FT: f64 = 1000.0;
+/// Final approach starts no further out than this from the threshold.
+const FINAL_RANGE_NM: f64 = 10.0;
+/// Lateral final approach cone: half-width at the threshold, widening by the half angle.
+const FINAL_CONE_BASE_M: f64 = 150.0;
+const FINAL_CONE_HALF_ANGLE_DEG: f64 = 7.5;
+/// Track difference from the runway heading still accepted as aligned.
+const FINAL_TRACK_TOLERANCE_DEG: f64 = 15.0;
+/// Window for climb/descent and acceleration trends from the track history.
+const TREND_WINDOW_S: i64 = 10;
+/// Ground speed change that tells a take-off roll from a landing roll.
+const ROLL_ACCELERATION_KT_S: f64 = 0.5;
+/// Airborne thresholds, as height above field elevation.
+const APPROACH_CEILING_FT: f64 = 5000.0;
+const CRUISE_FLOOR_FT: f64 = 2000.0;
+
+/// Runway end whose final approach cone contains the aircraft: inside the lateral cone
+/// on the extended centreline, tracking along it and not above the glidepath plus
+/// tolerance. When several runways qualify, the one closest to its centreline wins.
+pub fn final_approach_end<'a>(aircraft: &Aircraft, airport: &'a Airport) -> Option<&'a RunwayEnd> {
+    let (Some(lat), Some(lon)) = (aircraft.latitude, aircraft.longitude) else {
+        return None;
+    };
+    let alt = aircraft.baro_altitude.unwrap_or(0.0);
+    let track = aircraft.true_track.unwrap_or(0.0);
+    let range_m = FINAL_RANGE_NM * 1852.0;
+
+    airport.runways.iter()
+        .flat_map(|r| r.ends.iter())
+        .filter_map(|end| {
+            let (along, lateral) = end.approach_offset_m(lat, lon);
+            let in_cone = along > 0.0 && along < range_m
+                && lateral < FINAL_CONE_BASE_M + along * FINAL_CONE_HALF_ANGLE_DEG.to_radians().tan();
+            let aligned = angle_difference(track, end.heading) < FINAL_TRACK_TOLERANCE_DEG;
+            let height = alt - end.elevation_ft;
+            let below_ceiling = height < end.glidepath_height_ft(along / 1852.0) + GLIDEPATH_TOLERANCE_FT;
+            (in_cone && aligned && below_ceiling).then_some((end, lateral))
+        })
+        .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
+        .map(|(end, _)| end)
+}
 
 pub fn determine_phase(
     aircraft: &Aircraft, 
     prev_state: Option<&AircraftState>, 
-    zones: &AirportZones
+    airport: Option<&Airport>,
+    now: i64,
 ) -> Phase {
     if let Some(cat) = &aircraft.category {
         if cat.starts_with('C') {
@@ -13,34 +58,24 @@ pub fn determine_phase(
     }
 
     if !aircraft.on_ground {
-        // Airborne Logic
-        let alt = aircraft.baro_altitude.unwrap_or(0.0);
-        let vertical_rate = aircraft.vertical_rate.unwrap_or(0.0);
-        let track = aircraft.true_track.unwrap_or(0.0);
-        let lat = aircraft.latitude.unwrap_or(0.0);
-        let lon = aircraft.longitude.unwrap_or(0.0);
-
-        // Stansted (EGSS) Centroid Approx: 51.885, 0.235
-        // Runway 04 Heading: ~44 deg | Runway 22 Heading: ~224 deg
-        
-        // Check alignment for Final
-        // Logic: Low altitude + Descent + Aligned Heading + Correct Quadrant
-        
-        let is_aligned_22 = (track - 224.0).abs() < 15.0; // Runway 22 Approach
-        let is_aligned_04 = (track - 44.0).abs() < 15.0;  // Runway 04 Approach
-
-        let is_ne_quadrant = lat > 51.885 && lon > 0.235; // For Ry 22
-        let is_sw_quadrant = lat < 51.885 && lon < 0.235; // For Ry 04
-
-        if vertical_rate < -300.0 && alt < 5000.0 {
-            if alt < 2500.0 {
-                if (is_aligned_22 && is_ne_quadrant) || (is_aligned_04 && is_sw_quadrant) {
-                    return Phase::Final;
-                }
+        // Airborne Logic, heights relative to the field
+        let height = aircraft.baro_altitude.unwrap_or(0.0) - airport.map(|a| a.elevation_ft).unwrap_or(0.0);
+        // Altitude trend over the recent track, falling back to the reported rate
+        let vertical_rate = prev_state
+            .and_then(|p| p.vertical_trend_fpm(aircraft, now, TREND_WINDOW_S))
+            .or(aircraft.vertical_rate)
+            .unwrap_or(0.0);
+
+        // Final: Descent + inside the approach cone of any runway end
+        let on_final = airport.is_some_and(|a| final_approach_end(aircraft, a).is_some());
+
+        if vertical_rate < -300.0 && height < APPROACH_CEILING_FT {
+            if on_final {
+                return Phase::Final;
             }
             return Phase::Approach; // Descent but not aligned/low enough
         }
-        if alt > 2000.0 && vertical_rate.abs() < 500.0 {
+        if height > CRUISE_FLOOR_FT && vertical_rate.abs() < 500.0 {
             return Phase::Cruise; 
         }
         if vertical_rate > 300.0 {
@@ -53,7 +88,7 @@ pub fn determine_phase(
     let speed = aircraft.velocity.unwrap_or(0.0);
     let lat = aircraft.latitude.unwrap_or(0.0);
     let lon = aircraft.longitude.unwrap_or(0.0);
-    let zone = zones.check_zone(lat, lon);
+    let zone = airport.and_then(|a| a.zones.check_zone(lat, lon));
     
     // High speed on ground -> TakeOff or Landing
     if speed > 60.0 {
@@ -64,24 +99,25 @@ pub fn determine_phase(
                  _ => {} // Ambiguous: infer from zone
              }
         }
-        // Fallback if no history
-        if let Some(z) = zone.as_deref() {
-            if z == "Runway" {
-                // Heuristic: Accelerating means Takeoff? Not available in single snapshot easily without acc.
-                // We'll rely on history mostly. Default to TakeOff if unknown?
-                // Or maybe check vertical rate just before? 
-                return Phase::TakeOff; 
+        // Fallback if no phase history: accelerating is a take-off roll, decelerating a landing roll
+        if let Some(z) = zone {
+            if z.kind == ZoneKind::RunwayStrip {
+                let acceleration = prev_state.and_then(|p| p.acceleration(aircraft, now, TREND_WINDOW_S));
+                return match acceleration {
+                    Some(a) if a < -ROLL_ACCELERATION_KT_S => Phase::Landing,
+                    _ => Phase::TakeOff, // Default when the trend is unknown or flat
+                };
             }
         }
         return Phase::Unknown;
     }
 
     // Low speed logic
-    if let Some(z) = zone.as_deref() {
-        match z {
-             "TaxiwayJ" => return Phase::TaxiIn, // Per EGSS rules
-             "TaxiwayH" => return Phase::TaxiOut,
-             "Runway" => return Phase::LineUp, // Or clearing runway
+    if let Some(z) = zone {
+        match z.kind {
+             ZoneKind::RunwayStrip => return Phase::LineUp, // Or clearing runway
+             _ if z.has_tag(ZoneTag::ArrivalOnly) => return Phase::TaxiIn, // Per airport zone tags
+             _ if z.has_tag(ZoneTag::DepartureOnly) || z.has_tag(ZoneTag::DepartureQueue) => return Phase::TaxiOut,
              _ => {}
         }
     }
@@ -94,3 +130,192 @@ pub fn determine_phase(
 
     Phase::OnBlock
 }
+
+/// A new classification must persist this long before it replaces the current phase.
+const CONFIRM_S: i64 = 4;
+/// Dropping to `Unknown` needs a much longer run of unclassifiable samples.
+const UNKNOWN_CONFIRM_S: i64 = 20;
+/// Dwell after which a phase is fully trusted.
+const FULL_CONFIDENCE_DWELL_S: i64 = 60;
+
+/// Phase reported after hysteresis, with the classification still waiting to be confirmed.
+#[derive(Debug, Clone, Copy)]
+pub struct PhaseDecision {
+    pub phase: Phase,
+    /// 0.5 for a new classification, rising with dwell time; lower while contradicted.
+    pub confidence: f64,
+    pub pending: Option<(Phase, i64)>,
+}
+
+/// Minimum time in a phase before it may be left.
+fn min_dwell_s(phase: Phase) -> i64 {
+    match phase {
+        Phase::Unknown => 0,
+        Phase::TakeOff | Phase::Landing | Phase::Final | Phase::LineUp => 6,
+        Phase::OnBlock | Phase::Pushback | Phase::TaxiOut | Phase::TaxiIn => 10,
+        Phase::Climb | Phase::Cruise | Phase::Descent | Phase::Holding | Phase::Approach => 20,
+    }
+}
+
+fn is_airborne(phase: Phase) -> bool {
+    matches!(phase, Phase::Climb | Phase::Cruise | Phase::Descent | Phase::Holding | Phase::Approach | Phase::Final)
+}
+
+/// Transitions an aircraft can physically make. Airborne and ground phases only meet
+/// at touchdown and lift-off.
+pub fn transition_allowed(from: Phase, to: Phase) -> bool {
+    use Phase::*;
+    match (from, to) {
+        (a, b) if a == b => true,
+        (Unknown, _) | (_, Unknown) => true,
+        (Final | Approach | Descent, Landing) => true,
+        (TakeOff | Landing, Climb) => true,
+        (a, b) if is_airborne(a) && is_airborne(b) => true,
+        (a, _) if is_airborne(a) => false,
+        (_, b) if is_airborne(b) => false,
+        (OnBlock, Pushback | TaxiOut) => true,
+        (Pushback, OnBlock | TaxiOut) => true,
+        (TaxiOut, OnBlock | LineUp | TakeOff) => true,
+        (LineUp, TaxiOut | TaxiIn | TakeOff) => true,
+        // Rejected take-off
+        (TakeOff, LineUp | TaxiOut | TaxiIn) => true,
+        (Landing, LineUp | TaxiIn) => true,
+        // Runway crossings and tows
+        (TaxiIn, OnBlock | LineUp | TaxiOut) => true,
+        _ => false,
+    }
+}
+
+/// Applies hysteresis to a raw classification: a different phase is only adopted once
+/// the current one has been held for its minimum dwell, the transition is allowed and
+/// the new phase has persisted for the confirmation time.
+pub fn stabilise_phase(prev: Option<&AircraftState>, raw: Phase, now: i64) -> PhaseDecision {
+    let Some(prev) = prev else {
+        return PhaseDecision { phase: raw, confidence: 0.5, pending: None };
+    };
+
+    let since = prev.transitions.last().map(|(_, t)| *t).unwrap_or(now);
+    let dwell = now - since;
+    let settled = |dwell: i64| (0.5 + 0.5 * dwell as f64 / FULL_CONFIDENCE_DWELL_S as f64).min(1.0);
+    let round = |c: f64| (c * 100.0).round() / 100.0;
+
+    if raw == prev.phase {
+        return PhaseDecision { phase: prev.phase, confidence: round(settled(dwell)), pending: None };
+    }
+    if !transition_allowed(prev.phase, raw) {
+        return PhaseDecision { phase: prev.phase, confidence: round(settled(dwell) * 0.8), pending: None };
+    }
+
+    let pending_since = match prev.pending {
+        Some((phase, t)) if phase == raw => t,
+        _ => now,
+    };
+    let confirm = if raw == Phase::Unknown { UNKNOWN_CONFIRM_S } else { CONFIRM_S };
+
+    if dwell >= min_dwell_s(prev.phase) && now - pending_since >= confirm {
+        PhaseDecision { phase: raw, confidence: 0.5, pending: None }
+    } else {
+        PhaseDecision { phase: prev.phase, confidence: round(settled(dwell) * 0.6), pending: Some((raw, pending_since)) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn airborne(altitude_ft: f64, vertical_rate: f64) -> Aircraft {
+        Aircraft {
+            icao24: "aaa001".to_string(),
+            on_ground: false,
+            baro_altitude: Some(altitude_ft),
+            vertical_rate: Some(vertical_rate),
+            ..Aircraft::default()
+        }
+    }
+
+    /// One poll as the monitor runs it: stabilise the raw phase, then record the result.
+    fn poll(state: &mut AircraftState, aircraft: &Aircraft, raw: Phase, now: i64) -> PhaseDecision {
+        let decision = stabilise_phase(Some(state), raw, now);
+        state.record(aircraft, decision.phase, now);
+        state.pending = decision.pending;
+        decision
+    }
+
+    #[test]
+    fn a_new_phase_waits_for_the_minimum_dwell_and_confirmation() {
+        let aircraft = airborne(1500.0, -700.0);
+
+        // Final may be left after 6 s, once the new phase has held for 4 s
+        let mut state = AircraftState::new(&aircraft, Phase::Final, 0);
+        let phases: Vec<Phase> = [2, 4, 6].into_iter().map(|t| poll(&mut state, &aircraft, Phase::Landing, t).phase).collect();
+        assert_eq!(phases, [Phase::Final, Phase::Final, Phase::Landing]);
+
+        // Climb must be held for 20 s, however long the new phase has been confirmed
+        let mut state = AircraftState::new(&aircraft, Phase::Climb, 0);
+        for t in (2..20).step_by(2) {
+            let decision = poll(&mut state, &aircraft, Phase::Cruise, t);
+            assert_eq!((decision.phase, decision.pending), (Phase::Climb, Some((Phase::Cruise, 2))), "t={}", t);
+        }
+        assert_eq!(poll(&mut state, &aircraft, Phase::Cruise, 20).phase, Phase::Cruise);
+        assert_eq!(state.transitions, [(Phase::Climb, 0), (Phase::Cruise, 20)]);
+    }
+
+    #[test]
+    fn impossible_transitions_are_rejected() {
+        use Phase::*;
+        for (from, to, allowed) in [
+            (Final, TaxiOut, false),
+            (Cruise, OnBlock, false),
+            (OnBlock, Climb, false),
+            (TaxiOut, Landing, false),
+            (Final, Landing, true),
+            (TakeOff, Climb, true),
+            (TakeOff, TaxiIn, true),
+            (Approach, Final, true),
+            (Cruise, Unknown, true),
+        ] {
+            assert_eq!(transition_allowed(from, to), allowed, "{:?} -> {:?}", from, to);
+        }
+
+        let aircraft = airborne(1500.0, -700.0);
+        let mut state = AircraftState::new(&aircraft, Phase::Final, 0);
+        let decision = poll(&mut state, &aircraft, Phase::TaxiOut, 30);
+        assert_eq!((decision.phase, decision.pending, decision.confidence), (Phase::Final, None, 0.6));
+    }
+
+    #[test]
+    fn confidence_ramps_with_dwell_time() {
+        let aircraft = airborne(3000.0, 0.0);
+        assert_eq!(stabilise_phase(None, Phase::Cruise, 0).confidence, 0.5);
+
+        let mut state = AircraftState::new(&aircraft, Phase::Cruise, 0);
+        let confidence: Vec<f64> = [0, 30, 60, 120].into_iter()
+            .map(|t| poll(&mut state, &aircraft, Phase::Cruise, t).confidence)
+            .collect();
+        assert_eq!(confidence, [0.5, 0.75, 1.0, 1.0]);
+
+        // Contradicted while a new classification is pending, and reset once it is adopted
+        assert_eq!(poll(&mut state, &aircraft, Phase::Approach, 122).confidence, 0.6);
+        assert_eq!(poll(&mut state, &aircraft, Phase::Approach, 126).confidence, 0.5);
+    }
+
+    #[test]
+    fn an_oscillating_vertical_rate_does_not_flicker_the_phase() {
+        // Level at 3000 ft, reporting alternately 400 ft/min up and down: the raw rate
+        // alone would swap between Cruise and Approach on every poll
+        let mut state = AircraftState::new(&airborne(3000.0, 0.0), Phase::Cruise, 0);
+        for t in (2..=120).step_by(2) {
+            let aircraft = airborne(3000.0, if t % 4 == 0 { 400.0 } else { -400.0 });
+            let raw = determine_phase(&aircraft, Some(&state), None, t);
+            assert_eq!(raw, Phase::Cruise, "t={}", t);
+            poll(&mut state, &aircraft, raw, t);
+        }
+
+        // Even an alternating raw classification never survives the confirmation time
+        for t in (122..=240).step_by(2) {
+            let raw = if t % 4 == 0 { Phase::Approach } else { Phase::Cruise };
+            assert_eq!(poll(&mut state, &airborne(3000.0, 0.0), raw, t).phase, Phase::Cruise, "t={}", t);
+        }
+        assert_eq!(state.transitions, [(Phase::Cruise, 0)]);
+    }
+}
@@ -0,0 +1,673 @@
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+
+use crate::logic::airport::{Node, Taxiway};
+use crate::logic::geofence::{haversine_distance, LocalFrame};
+use crate::logic::spatial::{PointIndex, SegmentIndex};
+
+/// Vertices closer than this are the same junction.
+const MERGE_TOLERANCE_M: f64 = 2.0;
+/// A centreline ending this close to another centreline joins it (T-junction).
+const JOIN_TOLERANCE_M: f64 = 5.0;
+/// Dangling centreline ends this close to another node are bridged with a gap edge.
+const GAP_BRIDGE_M: f64 = 20.0;
+const MAX_HOLD_OFFSET_M: f64 = 60.0;
+const MAX_STAND_OFFSET_M: f64 = 250.0;
+/// Extra cost for switching taxiway, so routes prefer fewer instructions.
+const TAXIWAY_CHANGE_PENALTY_M: f64 = 150.0;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TaxiNodeKind {
+    Junction,
+    Hold(String),
+    Stand(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct TaxiNode {
+    pub lat: f64,
+    pub lon: f64,
+    pub kind: TaxiNodeKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdgeKind {
+    Taxiway,
+    /// Connector bridging two centrelines that do not quite meet; never named in instructions.
+    Gap,
+    /// Stand lead-in line or the link from a stand to its taxilane.
+    LeadIn,
+}
+
+/// One direction of travel along a piece of centreline. Two-way taxiways have a twin arc.
+#[derive(Debug, Clone)]
+pub struct TaxiArc {
+    pub from: usize,
+    pub to: usize,
+    pub length_m: f64,
+    pub taxiway: String,
+    pub kind: EdgeKind,
+}
+
+/// Routable taxiway network with nodes at intersections, holding points and stands.
+#[derive(Debug, Clone, Default)]
+pub struct TaxiGraph {
+    pub nodes: Vec<TaxiNode>,
+    pub arcs: Vec<TaxiArc>,
+    frame: LocalFrame,
+    node_index: PointIndex,
+    arc_index: SegmentIndex,
+    outgoing: Vec<Vec<usize>>,
+    holds: HashMap<String, usize>,
+    stands: HashMap<String, usize>,
+}
+
+/// A planned taxi route, from `origin` to the clearance limit `destination`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaxiRoute {
+    pub origin: String,
+    pub destination: String,
+    pub taxiways: Vec<String>,
+    pub length_m: f64,
+    /// (lat, lon) of each node along the route, for display.
+    pub path: Vec<[f64; 2]>,
+    /// Arc indices into the `TaxiGraph` the route was planned on.
+    #[serde(skip)]
+    pub arcs: Vec<usize>,
+}
+
+impl TaxiRoute {
+    /// e.g. "Taxi via J, H to H1"
+    pub fn instruction(&self) -> String {
+        if self.taxiways.is_empty() {
+            format!("Taxi to {}", self.destination)
+        } else {
+            format!("Taxi via {} to {}", self.taxiways.join(", "), self.destination)
+        }
+    }
+}
+
+/// Reduces a centreline feature name to the designator a controller would say:
+/// "A_West_1" -> "A", "Taxiway_Echo_East" -> "E", "NR_PR" -> "NR".
+pub fn taxiway_designator(name: &str) -> String {
+    const PHONETIC: [&str; 26] = [
+        "ALPHA", "BRAVO", "CHARLIE", "DELTA", "ECHO", "FOXTROT", "GOLF", "HOTEL", "INDIA", "JULIET", "KILO", "LIMA", "MIKE",
+        "NOVEMBER", "OSCAR", "PAPA", "QUEBEC", "ROMEO", "SIERRA", "TANGO", "UNIFORM", "VICTOR", "WHISKEY", "XRAY", "YANKEE", "ZULU",
+    ];
+
+    let mut tokens = name.split(['_', ' ']).filter(|t| !t.is_empty());
+    let mut first = tokens.next().unwrap_or(name).to_ascii_uppercase();
+    if first == "TAXIWAY" {
+        first = tokens.next().unwrap_or(name).to_ascii_uppercase();
+    }
+
+    match PHONETIC.iter().position(|p| *p == first) {
+        Some(i) => ((b'A' + i as u8) as char).to_string(),
+        None => first,
+    }
+}
+
+struct Segment {
+    a: (f64, f64),
+    b: (f64, f64),
+    taxiway: String,
+    kind: EdgeKind,
+    one_way: bool,
+    /// Parameters along a->b where the segment must be split, with an optional label.
+    splits: Vec<(f64, Option<TaxiNodeKind>)>,
+}
+
+impl Segment {
+    fn point_at(&self, t: f64) -> (f64, f64) {
+        (self.a.0 + (self.b.0 - self.a.0) * t, self.a.1 + (self.b.1 - self.a.1) * t)
+    }
+
+    /// Closest parameter in [0, 1] and the distance to it.
+    fn project(&self, p: (f64, f64)) -> (f64, f64) {
+        let (dx, dy) = (self.b.0 - self.a.0, self.b.1 - self.a.1);
+        let len2 = dx * dx + dy * dy;
+        let t = if len2 == 0.0 { 0.0 } else { (((p.0 - self.a.0) * dx + (p.1 - self.a.1) * dy) / len2).clamp(0.0, 1.0) };
+        let q = self.point_at(t);
+        (t, distance(p, q))
+    }
+}
+
+fn distance(a: (f64, f64), b: (f64, f64)) -> f64 {
+    ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt()
+}
+
+/// Parameters (t, u) where segments p1-p2 and q1-q2 properly cross.
+fn crossing(p1: (f64, f64), p2: (f64, f64), q1: (f64, f64), q2: (f64, f64)) -> Option<(f64, f64)> {
+    let r = (p2.0 - p1.0, p2.1 - p1.1);
+    let s = (q2.0 - q1.0, q2.1 - q1.1);
+    let denom = r.0 * s.1 - r.1 * s.0;
+    if denom.abs() < 1e-9 {
+        return None;
+    }
+    let qp = (q1.0 - p1.0, q1.1 - p1.1);
+    let t = (qp.0 * s.1 - qp.1 * s.0) / denom;
+    let u = (qp.0 * r.1 - qp.1 * r.0) / denom;
+    let eps = 1e-6;
+    if t > eps && t < 1.0 - eps && u > eps && u < 1.0 - eps {
+        Some((t, u))
+    } else {
+        None
+    }
+}
+
+impl TaxiGraph {
+    pub fn build(taxiways: &[Taxiway], holds: &[Node], stands: &[Node]) -> Self {
+        let points = taxiways.iter().flat_map(|t| t.coordinates.iter().flatten());
+        let (count, lat_sum, lon_sum) = points.fold((0usize, 0.0, 0.0), |(n, la, lo), p| (n + 1, la + p[0], lo + p[1]));
+        if count == 0 {
+            return TaxiGraph::default();
+        }
+        let frame = LocalFrame::new(lat_sum / count as f64, lon_sum / count as f64);
+
+        let mut segments: Vec<Segment> = Vec::new();
+        for taxiway in taxiways {
+            let kind = if taxiway.type_ == "LeadIn" { EdgeKind::LeadIn } else { EdgeKind::Taxiway };
+            for line in &taxiway.coordinates {
+                for pair in line.windows(2) {
+                    segments.push(Segment {
+                        a: frame.to_xy(pair[0][0], pair[0][1]),
+                        b: frame.to_xy(pair[1][0], pair[1][1]),
+                        taxiway: taxiway_designator(&taxiway.name),
+                        kind,
+                        one_way: taxiway.type_ == "OneWay",
+                        splits: vec![(0.0, None), (1.0, None)],
+                    });
+                }
+            }
+        }
+
+        // Crossings and T-junctions
+        for i in 0..segments.len() {
+            for j in (i + 1)..segments.len() {
+                let (a1, a2, b1, b2) = (segments[i].a, segments[i].b, segments[j].a, segments[j].b);
+                if let Some((t, u)) = crossing(a1, a2, b1, b2) {
+                    segments[i].splits.push((t, None));
+                    segments[j].splits.push((u, None));
+                    continue;
+                }
+                for end in [b1, b2] {
+                    let (t, d) = segments[i].project(end);
+                    if d < JOIN_TOLERANCE_M && t > 0.0 && t < 1.0 {
+                        segments[i].splits.push((t, None));
+                    }
+                }
+                for end in [a1, a2] {
+                    let (u, d) = segments[j].project(end);
+                    if d < JOIN_TOLERANCE_M && u > 0.0 && u < 1.0 {
+                        segments[j].splits.push((u, None));
+                    }
+                }
+            }
+        }
+
+        // Holding points sit on the centreline they protect
+        for hold in holds {
+            let p = frame.to_xy(hold.lat, hold.lon);
+            if let Some((i, t, d)) = nearest_segment(&segments, p, false) {
+                if d <= MAX_HOLD_OFFSET_M {
+                    segments[i].splits.push((t, Some(TaxiNodeKind::Hold(hold.name.clone()))));
+                }
+            }
+        }
+
+        // Stands are joined to the nearest taxilane by a lead-in
+        let mut stand_links = Vec::new();
+        for stand in stands {
+            let p = frame.to_xy(stand.lat, stand.lon);
+            if let Some((i, t, d)) = nearest_segment(&segments, p, true) {
+                if d <= MAX_STAND_OFFSET_M {
+                    segments[i].splits.push((t, None));
+                    stand_links.push((stand, segments[i].point_at(t)));
+                }
+            }
+        }
+
+        let mut graph = TaxiGraph { frame, ..Default::default() };
+        let mut index = NodeIndex::default();
+
+        for seg in &mut segments {
+            seg.splits.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+            let ids: Vec<usize> = seg.splits.iter()
+                .map(|(t, label)| {
+                    let id = index.node_at(&mut graph, &frame, seg.point_at(*t));
+                    if let Some(label) = label {
+                        graph.label(id, label.clone());
+                    }
+                    id
+                })
+                .collect();
+
+            for pair in ids.windows(2) {
+                if pair[0] == pair[1] {
+                    continue;
+                }
+                let length = distance(index.xy(pair[0]), index.xy(pair[1]));
+                graph.connect(pair[0], pair[1], length, &seg.taxiway, seg.kind, !seg.one_way);
+            }
+        }
+
+        for (stand, attach) in stand_links {
+            let attach_id = index.node_at(&mut graph, &frame, attach);
+            let stand_xy = frame.to_xy(stand.lat, stand.lon);
+            let stand_id = index.push(&mut graph, &frame, stand_xy);
+            graph.label(stand_id, TaxiNodeKind::Stand(stand.name.clone()));
+            graph.connect(stand_id, attach_id, distance(stand_xy, attach), "", EdgeKind::LeadIn, true);
+        }
+
+        // Bridge centrelines that stop just short of each other
+        let dangling: Vec<usize> = (0..graph.nodes.len())
+            .filter(|&n| graph.nodes[n].kind == TaxiNodeKind::Junction && graph.degree(n) == 1)
+            .collect();
+        for n in dangling {
+            let p = index.xy(n);
+            let candidate = (0..graph.nodes.len())
+                .filter(|&m| m != n && !matches!(graph.nodes[m].kind, TaxiNodeKind::Stand(_)) && !graph.adjacent(n, m))
+                .map(|m| (m, distance(p, index.xy(m))))
+                .filter(|(_, d)| *d <= GAP_BRIDGE_M)
+                .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal));
+            if let Some((m, d)) = candidate {
+                graph.connect(n, m, d, "", EdgeKind::Gap, true);
+            }
+        }
+
+        graph.node_index = PointIndex::build(frame, graph.nodes.iter().map(|n| (n.lat, n.lon)));
+        graph.arc_index = SegmentIndex::build(frame, graph.arcs.iter().map(|a| {
+            let (from, to) = (&graph.nodes[a.from], &graph.nodes[a.to]);
+            ((from.lat, from.lon), (to.lat, to.lon))
+        }));
+        graph
+    }
+
+    fn label(&mut self, id: usize, kind: TaxiNodeKind) {
+        match &kind {
+            TaxiNodeKind::Hold(name) => { self.holds.insert(name.clone(), id); }
+            TaxiNodeKind::Stand(name) => { self.stands.insert(name.clone(), id); }
+            TaxiNodeKind::Junction => {}
+        }
+        self.nodes[id].kind = kind;
+    }
+
+    fn connect(&mut self, from: usize, to: usize, length_m: f64, taxiway: &str, kind: EdgeKind, both_ways: bool) {
+        let mut add = |from: usize, to: usize| {
+            self.arcs.push(TaxiArc { from, to, length_m, taxiway: taxiway.to_string(), kind });
+            self.outgoing[from].push(self.arcs.len() - 1);
+        };
+        add(from, to);
+        if both_ways {
+            add(to, from);
+        }
+    }
+
+    fn degree(&self, n: usize) -> usize {
+        let mut neighbours: Vec<usize> = self.outgoing[n].iter().map(|&a| self.arcs[a].to).collect();
+        neighbours.extend(self.arcs.iter().filter(|a| a.to == n).map(|a| a.from));
+        neighbours.sort_unstable();
+        neighbours.dedup();
+        neighbours.len()
+    }
+
+    fn adjacent(&self, a: usize, b: usize) -> bool {
+        self.outgoing[a].iter().any(|&i| self.arcs[i].to == b) || self.outgoing[b].iter().any(|&i| self.arcs[i].to == a)
+    }
+
+    pub fn hold_node(&self, name: &str) -> Option<usize> {
+        self.holds.get(name).copied()
+    }
+
+    pub fn stand_node(&self, name: &str) -> Option<usize> {
+        self.stands.get(name).copied()
+    }
+
+    /// Nearest node to a position, e.g. an aircraft vacating the runway.
+    pub fn nearest_node(&self, lat: f64, lon: f64) -> Option<usize> {
+        self.node_index.nearest(lat, lon).map(|(n, _)| n)
+    }
+
+    /// Display name of a node, as used for route origins and destinations.
+    pub fn node_name(&self, n: usize) -> String {
+        match &self.nodes[n].kind {
+            TaxiNodeKind::Hold(name) => name.clone(),
+            TaxiNodeKind::Stand(name) => format!("Stand {}", name),
+            TaxiNodeKind::Junction => "Position".to_string(),
+        }
+    }
+
+    /// Shortest route between two nodes. Arcs are the search states so that
+    /// taxiway changes can be penalised and U-turns on a centreline are excluded.
+    pub fn route(&self, origin: usize, destination: usize) -> Option<TaxiRoute> {
+        if origin >= self.nodes.len() || destination >= self.nodes.len() {
+            return None;
+        }
+        if origin == destination {
+            return Some(self.route_from_arcs(origin, destination, Vec::new()));
+        }
+
+        let mut cost = vec![f64::INFINITY; self.arcs.len()];
+        let mut previous: Vec<Option<usize>> = vec![None; self.arcs.len()];
+        let mut heap = BinaryHeap::new();
+
+        for &a in &self.outgoing[origin] {
+            cost[a] = self.arcs[a].length_m;
+            heap.push(SearchState { cost: cost[a], arc: a });
+        }
+
+        while let Some(SearchState { cost: c, arc }) = heap.pop() {
+            if c > cost[arc] {
+                continue;
+            }
+            let current = &self.arcs[arc];
+            if current.to == destination {
+                let mut arcs = vec![arc];
+                let mut cursor = arc;
+                while let Some(p) = previous[cursor] {
+                    arcs.push(p);
+                    cursor = p;
+                }
+                arcs.reverse();
+                return Some(self.route_from_arcs(origin, destination, arcs));
+            }
+
+            for &next in &self.outgoing[current.to] {
+                let candidate = &self.arcs[next];
+                if candidate.to == current.from {
+                    continue;
+                }
+                let changes_taxiway = candidate.kind == EdgeKind::Taxiway
+                    && current.kind == EdgeKind::Taxiway
+                    && candidate.taxiway != current.taxiway;
+                let step = candidate.length_m + if changes_taxiway { TAXIWAY_CHANGE_PENALTY_M } else { 0.0 };
+                if c + step < cost[next] {
+                    cost[next] = c + step;
+                    previous[next] = Some(arc);
+                    heap.push(SearchState { cost: c + step, arc: next });
+                }
+            }
+        }
+
+        None
+    }
+
+    fn route_from_arcs(&self, origin: usize, destination: usize, arcs: Vec<usize>) -> TaxiRoute {
+        let mut taxiways: Vec<String> = Vec::new();
+        for &a in &arcs {
+            let arc = &self.arcs[a];
+            if arc.kind == EdgeKind::Taxiway && taxiways.last() != Some(&arc.taxiway) {
+                taxiways.push(arc.taxiway.clone());
+            }
+        }
+
+        let mut path = vec![[self.nodes[origin].lat, self.nodes[origin].lon]];
+        path.extend(arcs.iter().map(|&a| {
+            let n = &self.nodes[self.arcs[a].to];
+            [n.lat, n.lon]
+        }));
+
+        TaxiRoute {
+            origin: self.node_name(origin),
+            destination: self.node_name(destination),
+            taxiways,
+            length_m: arcs.iter().map(|&a| self.arcs[a].length_m).sum(),
+            path,
+            arcs,
+        }
+    }
+
+    /// Matches a ground position to the arc it is most likely travelling along.
+    ///
+    /// Distance to the centreline dominates; when a track is known, arcs pointing the
+    /// other way are penalised so the direction of travel is picked out of each twin pair.
+    pub fn match_position(&self, lat: f64, lon: f64, track: Option<f64>, max_offset_m: f64) -> Option<usize> {
+        let p = self.frame.to_xy(lat, lon);
+
+        self.arc_index
+            .within(lat, lon, max_offset_m)
+            .into_iter()
+            .map(|(arc, offset_m)| {
+                let heading_penalty = match (track, self.project(p, arc).1) {
+                    (Some(track), Some(bearing)) => {
+                        let diff = ((bearing - track + 540.0) % 360.0 - 180.0).abs();
+                        diff / 180.0 * max_offset_m
+                    }
+                    _ => 0.0,
+                };
+                (arc, offset_m + heading_penalty)
+            })
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal))
+            .map(|(arc, _)| arc)
+    }
+
+    /// Distance in metres from a position to the centreline of an arc.
+    pub fn offset_from_arc(&self, lat: f64, lon: f64, arc: usize) -> f64 {
+        self.project(self.frame.to_xy(lat, lon), arc).0
+    }
+
+    /// How far in metres a position is beyond the end of an arc, measured along it;
+    /// negative while it has not reached the end.
+    pub fn beyond_arc_end_m(&self, lat: f64, lon: f64, arc: usize) -> f64 {
+        let p = self.frame.to_xy(lat, lon);
+        let arc = &self.arcs[arc];
+        let a = self.frame.to_xy(self.nodes[arc.from].lat, self.nodes[arc.from].lon);
+        let b = self.frame.to_xy(self.nodes[arc.to].lat, self.nodes[arc.to].lon);
+        let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+        let length = (dx * dx + dy * dy).sqrt();
+        if length == 0.0 {
+            return 0.0;
+        }
+        ((p.0 - b.0) * dx + (p.1 - b.1) * dy) / length
+    }
+
+    /// Offset from the arc and its true bearing (`None` if degenerate).
+    fn project(&self, p: (f64, f64), arc: usize) -> (f64, Option<f64>) {
+        let arc = &self.arcs[arc];
+        let a = self.frame.to_xy(self.nodes[arc.from].lat, self.nodes[arc.from].lon);
+        let b = self.frame.to_xy(self.nodes[arc.to].lat, self.nodes[arc.to].lon);
+        let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+        let len2 = dx * dx + dy * dy;
+        if len2 == 0.0 {
+            return (distance(p, a), None);
+        }
+        let t = (((p.0 - a.0) * dx + (p.1 - a.1) * dy) / len2).clamp(0.0, 1.0);
+        let bearing = (dx.atan2(dy).to_degrees() + 360.0) % 360.0;
+        (distance(p, (a.0 + dx * t, a.1 + dy * t)), Some(bearing))
+    }
+
+    /// Distance in metres between a position and a node.
+    pub fn distance_to_node(&self, lat: f64, lon: f64, node: usize) -> f64 {
+        let n = &self.nodes[node];
+        haversine_distance(lat, lon, n.lat, n.lon) * 1000.0
+    }
+
+    /// Resolves "Stand 10", a bare stand name or a holding point name to its node.
+    pub fn named_node(&self, name: &str) -> Option<usize> {
+        match name.strip_prefix("Stand ") {
+            Some(stand) => self.stand_node(stand.trim()),
+            None => self.hold_node(name).or_else(|| self.stand_node(name)),
+        }
+    }
+
+    /// Route between two named points, stand to hold or hold to stand.
+    pub fn route_between(&self, from: &str, to: &str) -> Option<TaxiRoute> {
+        self.route(self.named_node(from)?, self.named_node(to)?)
+    }
+
+    /// Re-plans a route made on another build of the graph, from the aircraft's position
+    /// to the same destination. Arc indices do not carry over between builds.
+    pub fn replan(&self, route: &TaxiRoute, lat: f64, lon: f64) -> Option<TaxiRoute> {
+        self.route(self.nearest_node(lat, lon)?, self.named_node(&route.destination)?)
+    }
+}
+
+#[derive(Default)]
+struct NodeIndex {
+    xy: Vec<(f64, f64)>,
+    grid: HashMap<(i64, i64), Vec<usize>>,
+}
+
+impl NodeIndex {
+    fn cell(p: (f64, f64)) -> (i64, i64) {
+        ((p.0 / MERGE_TOLERANCE_M).floor() as i64, (p.1 / MERGE_TOLERANCE_M).floor() as i64)
+    }
+
+    fn xy(&self, id: usize) -> (f64, f64) {
+        self.xy[id]
+    }
+
+    /// Returns the node within the merge tolerance of `p`, creating one if needed.
+    fn node_at(&mut self, graph: &mut TaxiGraph, frame: &LocalFrame, p: (f64, f64)) -> usize {
+        let (cx, cy) = Self::cell(p);
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                if let Some(ids) = self.grid.get(&(cx + dx, cy + dy)) {
+                    if let Some(&id) = ids.iter().find(|&&id| distance(self.xy[id], p) <= MERGE_TOLERANCE_M) {
+                        return id;
+                    }
+                }
+            }
+        }
+        self.push(graph, frame, p)
+    }
+
+    fn push(&mut self, graph: &mut TaxiGraph, frame: &LocalFrame, p: (f64, f64)) -> usize {
+        let (lat, lon) = frame.to_lat_lon(p.0, p.1);
+        graph.nodes.push(TaxiNode { lat, lon, kind: TaxiNodeKind::Junction });
+        graph.outgoing.push(Vec::new());
+        let id = graph.nodes.len() - 1;
+        self.xy.push(p);
+        self.grid.entry(Self::cell(p)).or_default().push(id);
+        id
+    }
+}
+
+fn nearest_segment(segments: &[Segment], p: (f64, f64), allow_lead_in: bool) -> Option<(usize, f64, f64)> {
+    segments.iter()
+        .enumerate()
+        .filter(|(_, s)| allow_lead_in || s.kind != EdgeKind::LeadIn)
+        .map(|(i, s)| {
+            let (t, d) = s.project(p);
+            (i, t, d)
+        })
+        .min_by(|a, b| a.2.partial_cmp(&b.2).unwrap_or(Ordering::Equal))
+}
+
+#[derive(PartialEq)]
+struct SearchState {
+    cost: f64,
+    arc: usize,
+}
+
+impl Eq for SearchState {}
+
+impl Ord for SearchState {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Min-heap on cost
+        other.cost.partial_cmp(&self.cost).unwrap_or(Ordering::Equal)
+    }
+}
+
+impl PartialOrd for SearchState {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 0.001 degrees of longitude at 51.88 N, about 69 m.
+    const LON_M: f64 = 68.7;
+
+    fn taxiway(name: &str, type_: &str, from: (f64, f64), to: (f64, f64)) -> Taxiway {
+        Taxiway {
+            name: name.to_string(),
+            type_: type_.to_string(),
+            coordinates: vec![vec![vec![from.0, from.1], vec![to.0, to.1]]],
+        }
+    }
+
+    fn node(name: &str, lat: f64, lon: f64) -> Node {
+        Node { name: name.to_string(), lat, lon }
+    }
+
+    /// Juliet runs east from stand 10 and meets Hotel in a T; Kilo crosses Juliet
+    /// halfway along. H1 is on Hotel north of the junction.
+    fn apron() -> TaxiGraph {
+        let taxiways = [
+            taxiway("Taxiway_Juliet", "Taxiway", (51.880, 0.230), (51.880, 0.236)),
+            taxiway("H", "Taxiway", (51.878, 0.236), (51.886, 0.236)),
+            taxiway("K", "Taxiway", (51.878, 0.233), (51.882, 0.233)),
+        ];
+        TaxiGraph::build(&taxiways, &[node("H1", 51.885, 0.236)], &[node("10", 51.8795, 0.2302)])
+    }
+
+    #[test]
+    fn crossings_and_t_junctions_split_the_centrelines() {
+        let graph = apron();
+        for (lat, lon, degree) in [(51.880, 0.233, 4), (51.880, 0.236, 3)] {
+            let n = graph.nearest_node(lat, lon).unwrap();
+            assert!(graph.distance_to_node(lat, lon, n) < 1.0);
+            assert_eq!(graph.nodes[n].kind, TaxiNodeKind::Junction);
+            assert_eq!(graph.degree(n), degree, "({}, {})", lat, lon);
+        }
+    }
+
+    #[test]
+    fn stand_to_hold_and_back() {
+        let graph = apron();
+        let out = graph.route_between("Stand 10", "H1").unwrap();
+        assert_eq!(out.instruction(), "Taxi via J, H to H1");
+        assert_eq!((out.origin.as_str(), out.destination.as_str()), ("Stand 10", "H1"));
+        // About 6 x 69 m along Juliet and 5 x 111 m up Hotel, plus the lead-in
+        assert!((900.0..1100.0).contains(&out.length_m), "{}", out.length_m);
+
+        let back = graph.route_between("H1", "10").unwrap();
+        assert_eq!(back.instruction(), "Taxi via H, J to Stand 10");
+        assert!((back.length_m - out.length_m).abs() < 1.0);
+
+        assert!(graph.route_between("Stand 10", "H9").is_none());
+        assert_eq!(graph.route_between("H1", "H1").unwrap().instruction(), "Taxi to H1");
+    }
+
+    #[test]
+    fn one_way_taxiways_are_not_routable_backwards() {
+        let taxiways = [taxiway("A_Northbound", "OneWay", (51.880, 0.230), (51.882, 0.230))];
+        let holds = [node("A1", 51.882, 0.230), node("A2", 51.880, 0.230)];
+        let graph = TaxiGraph::build(&taxiways, &holds, &[]);
+        assert_eq!(graph.route_between("A2", "A1").unwrap().instruction(), "Taxi via A to A1");
+        assert!(graph.route_between("A1", "A2").is_none());
+    }
+
+    #[test]
+    fn gaps_are_bridged_only_within_the_bridge_distance() {
+        let with_gap = |gap_m: f64| {
+            let start = 0.232 + gap_m / LON_M / 1000.0;
+            let taxiways = [
+                taxiway("B", "Taxiway", (51.880, 0.230), (51.880, 0.232)),
+                taxiway("C", "Taxiway", (51.880, start), (51.880, 0.234)),
+            ];
+            let holds = [node("B1", 51.880, 0.230), node("C1", 51.880, 0.234)];
+            TaxiGraph::build(&taxiways, &holds, &[])
+        };
+
+        let graph = with_gap(GAP_BRIDGE_M - 5.0);
+        let route = graph.route_between("B1", "C1").unwrap();
+        // The bridge is not named in the instruction
+        assert_eq!(route.instruction(), "Taxi via B, C to C1");
+        assert!(route.arcs.iter().any(|&a| graph.arcs[a].kind == EdgeKind::Gap));
+
+        assert!(with_gap(GAP_BRIDGE_M + 5.0).route_between("B1", "C1").is_none());
+    }
+
+    #[test]
+    fn designators() {
+        for (name, designator) in [("A_West_1", "A"), ("Taxiway_Echo_East", "E"), ("NR_PR", "NR"), ("juliet", "J")] {
+            assert_eq!(taxiway_designator(name), designator);
+        }
+    }
+}
@@ -1,6 +1,91 @@
-use crate::models::{Aircraft, WakeCategory};
+use serde::{Deserialize, Serialize};
 
-pub fn check_separation(_leader: &Aircraft, _follower: &Aircraft) -> bool {
-    // Placeholder logic
-    true
+use crate::models::Aircraft;
+
+/// Distance bands (nm between consecutive arrivals) behind the approach spacing advisories.
+#[derive(Debug, Clone, Copy)]
+pub struct SpacingLadder {
+    /// Below this the follower must go around.
+    pub minimum_nm: f64,
+    pub min_speed_nm: f64,
+    pub slow_nm: f64,
+    pub maintain_nm: f64,
+    /// Gaps inside this band are wasting runway capacity.
+    pub expedite_nm: (f64, f64),
+}
+
+impl SpacingLadder {
+    pub const NORMAL: SpacingLadder = SpacingLadder {
+        minimum_nm: 2.5,
+        min_speed_nm: 3.0,
+        slow_nm: 4.0,
+        maintain_nm: 5.0,
+        expedite_nm: (7.0, 9.0),
+    };
+
+    /// 3 nm radar minimum for airports without approval for reduced separation on final.
+    pub const STANDARD: SpacingLadder = SpacingLadder {
+        minimum_nm: 3.0,
+        min_speed_nm: 3.5,
+        slow_nm: 4.5,
+        maintain_nm: 5.5,
+        expedite_nm: (7.5, 9.5),
+    };
+
+    /// Low visibility spacing keeps the ILS sensitive area clear until the leader has vacated.
+    pub const LVP: SpacingLadder = SpacingLadder {
+        minimum_nm: 4.0,
+        min_speed_nm: 5.0,
+        slow_nm: 6.0,
+        maintain_nm: 7.0,
+        expedite_nm: (9.0, 11.0),
+    };
+
+    pub fn advise(&self, gap: f64) -> Option<&'static str> {
+        if gap < self.minimum_nm {
+            Some("GO AROUND")
+        } else if gap < self.min_speed_nm {
+            Some("MIN SPD")
+        } else if gap < self.slow_nm {
+            Some("SLOW 160")
+        } else if gap < self.maintain_nm {
+            Some("MAINTAIN")
+        } else if gap > self.expedite_nm.0 && gap < self.expedite_nm.1 {
+            Some("EXPEDITE")
+        } else {
+            None
+        }
+    }
+}
+
+/// Final approach separation an airport is approved for, chosen per airport in the config.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SeparationScheme {
+    /// 2.5 nm on final.
+    #[default]
+    Reduced,
+    /// 3 nm on final.
+    Standard,
+}
+
+impl SeparationScheme {
+    /// Ladder outside low visibility procedures; LVP spacing is wider than either.
+    pub fn ladder(self) -> SpacingLadder {
+        match self {
+            SeparationScheme::Reduced => SpacingLadder::NORMAL,
+            SeparationScheme::Standard => SpacingLadder::STANDARD,
+        }
+    }
+}
+
+/// Gap between consecutive arrivals, from their distances to the threshold.
+pub fn arrival_gap(leader: &Aircraft, follower: &Aircraft) -> Option<f64> {
+    Some(follower.distance? - leader.distance?)
+}
+
+/// Whether the follower keeps at least the ladder's minimum behind the leader.
+/// Unknown distances are not treated as a loss of separation.
+pub fn check_separation(leader: &Aircraft, follower: &Aircraft, ladder: &SpacingLadder) -> bool {
+    arrival_gap(leader, follower).is_none_or(|gap| gap >= ladder.minimum_nm)
 }
@@ -1,26 +1,90 @@
 use crate::models::{Aircraft, Phase};
-use crate::logic::airport::AirportData;
+use crate::logic::airport::{Airport, RunwayConfig, RunwayEnd};
+use crate::logic::alerts::{AlertBoard, AlertCondition, AlertKind};
+use crate::logic::aman::ArrivalSequence;
+use crate::logic::conformance::{check_conformance, ConformanceStatus};
+use crate::logic::control::{ClearanceKind, ControllerInputs};
+use crate::logic::emergency::EmergencyTracker;
+use crate::logic::events::{MovementEvent, MovementEventKind};
+use crate::logic::holding::HoldingMonitor;
+use crate::logic::lvp::{LvpState, Procedures};
+use crate::logic::separation::{arrival_gap, check_separation, SeparationScheme};
 use std::collections::HashMap;
 
+/// An aircraft first seen within this distance of a stand is parked on it.
+const ON_STAND_RADIUS_M: f64 = 80.0;
+/// A taxiing aircraft this close to a holding point is holding there.
+const HOLD_ENTRY_RADIUS_M: f64 = 150.0;
+/// A holding aircraft further than this from the holding point has moved onto the runway.
+const HOLD_EXIT_RADIUS_M: f64 = 250.0;
+/// Spacing advisories are only issued once an arrival's phase has settled this far.
+const MIN_ADVISORY_CONFIDENCE: f64 = 0.6;
+/// A runway change completes after this long even if the old runway never clears.
+const RUNWAY_CHANGE_TIMEOUT_S: i64 = 600;
+
+#[derive(Default)]
 pub struct RunwayContext {
     pub last_departure_time: i64,
+    /// Runways in use; taken from the airport's `.rwy` defaults on first use.
+    pub config: Option<RunwayConfig>,
+    pub change: Option<RunwayChange>,
+    pub lvp: LvpState,
+    pub sequence: ArrivalSequence,
+    pub holding: HoldingMonitor,
+    /// Final approach separation the airport is configured for.
+    pub separation: SeparationScheme,
+    /// Clearances and assignments from controllers, which the engine does not override.
+    pub control: ControllerInputs,
+    pub alerts: AlertBoard,
+    pub emergencies: EmergencyTracker,
+}
+
+/// A runway change in progress. Departures are held while the last arrivals and
+/// departures on the old configuration complete, then traffic is re-sequenced.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RunwayChange {
+    pub to: RunwayConfig,
+    pub requested_at: i64,
 }
 
-impl Default for RunwayContext {
-    fn default() -> Self {
-        Self {
-            last_departure_time: 0,
+impl RunwayContext {
+    /// Current runway configuration, initialised from the airport defaults.
+    pub fn runway_config(&mut self, airport: &Airport) -> Option<RunwayConfig> {
+        if self.config.is_none() {
+            self.config = airport.default_runway_config();
+        }
+        self.config.clone()
+    }
+
+    /// Starts a managed change to `to` at `now`, or applies it straight away if nothing is
+    /// set yet.
+    pub fn request_change(&mut self, airport: &Airport, to: RunwayConfig, now: i64) {
+        match self.runway_config(airport) {
+            Some(current) if current != to => {
+                self.change = Some(RunwayChange { to, requested_at: now });
+            }
+            Some(_) => self.change = None,
+            None => self.config = Some(to),
         }
     }
 }
 
-pub fn process_ground_traffic(aircraft_map: &mut HashMap<String, Aircraft>, airport_data: &AirportData, context: &mut RunwayContext) {
-    let now = chrono::Utc::now().timestamp();
+pub fn process_ground_traffic(aircraft_map: &mut HashMap<String, Aircraft>, airport: &Airport, context: &mut RunwayContext, now: i64) {
+    let procedures = Procedures::for_state(&context.lvp, context.separation);
+    advance_runway_change(aircraft_map, airport, context, &procedures, now);
+    let runways = context.runway_config(airport);
+    let arrival_end = runways.as_ref().and_then(|r| r.arrival_end(airport));
+    let departure_runway = runways.as_ref().map(|r| r.departure.as_str()).unwrap_or_default();
+    context.control.retain(aircraft_map);
+    // Every safety-relevant condition found this cycle; the alert board turns them into
+    // alerts that persist until the condition goes away
+    let mut conditions = Vec::new();
     
-    // 1. Emergency Detection
-    let emergency_active = aircraft_map.values().any(|a| {
-        matches!(a.squawk.as_deref(), Some("7500" | "7600" | "7700"))
-    });
+    // 1. Emergency Detection: only a runway an emergency is inbound to (or on) closes
+    context.emergencies.update(aircraft_map, airport, &context.control, arrival_end, now);
+    conditions.extend(context.emergencies.conditions(aircraft_map));
+    let closures = context.emergencies.closures(aircraft_map);
+    let closure = |end: &str| airport.runway_of(end).and_then(|r| closures.get(&r.name));
 
     // 2. Snapshot arrivals for safety check & Approach Spacing
     let mut arrivals: Vec<Aircraft> = aircraft_map.values()
@@ -31,43 +95,42 @@ pub fn process_ground_traffic(aircraft_map: &mut HashMap<String, Aircraft>, airp
     // Sort by distance (descending - furthest first? No, we need relative order)
     arrivals.sort_by(|a, b| (a.distance.unwrap_or(999.0)).partial_cmp(&b.distance.unwrap_or(999.0)).unwrap());
 
+    // Landing sequence; go-arounds are held out of it until they are back on approach
+    context.sequence.update(aircraft_map, &context.control.fixed_positions(), &context.emergencies.priority());
+    for entry in &context.sequence.entries {
+        if let Some(aircraft) = aircraft_map.get_mut(&entry.icao24) {
+            aircraft.sequence = Some(entry.position);
+            if entry.rejoining {
+                let message = format!("GO AROUND - REJOIN #{}", entry.position);
+                conditions.push(AlertCondition::new(AlertKind::GoAround, aircraft, message.clone()));
+                aircraft.advisory = Some(message);
+            }
+        }
+    }
+
     // Calculate Approach Spacing Advisories
     // We need to write back to the map.
     for i in 0..arrivals.len() {
         let mut advice = None;
-        let ac = &arrivals[i];
+        let settled = arrivals[i].phase_confidence >= MIN_ADVISORY_CONFIDENCE;
 
-        // 1. Go-Around Detection (Top Priority)
-        // Only check if close to runway (< 4nm) and climbing substantially (> 400 fpm)
-        if ac.distance.unwrap_or(99.0) < 4.0 && ac.vertical_rate.unwrap_or(0.0) > 400.0 {
-            advice = Some("ABORTED APPROACH".to_string());
-        }
-
-        // 2. Spacing Advice (Only if no critical alert)
-        if advice.is_none() && i > 0 {
+        // Spacing Advice
+        if i > 0 {
              let preceding = &arrivals[i-1];
              let current = &arrivals[i];
              
-             if let (Some(d1), Some(d2)) = (preceding.distance, current.distance) {
-                 let gap = d2 - d1; // d2 is further out
-                 
-                 // Logic
-                 if gap < 2.5 {
-                     advice = Some("GO AROUND".to_string());
-                 } else if gap < 3.0 {
-                     advice = Some("MIN SPD".to_string());
-                 } else if gap < 4.0 {
-                     advice = Some("SLOW 160".to_string());
-                 } else if gap < 5.0 {
-                     advice = Some("MAINTAIN".to_string());
-                 } else if gap > 7.0 && gap < 9.0 {
-                     advice = Some("EXPEDITE".to_string());
+             if !check_separation(preceding, current, &procedures.ladder) {
+                 advice = Some("GO AROUND".to_string());
+                 if settled {
+                     conditions.push(AlertCondition::new(AlertKind::LossOfSeparation, current, "GO AROUND").involving(&preceding.icao24));
                  }
+             } else if let Some(gap) = arrival_gap(preceding, current) {
+                 advice = procedures.ladder.advise(gap).map(str::to_string);
              }
         }
         
         // Write back
-        if let Some(msg) = advice {
+        if let Some(msg) = advice.filter(|_| settled) {
              if let Some(entry) = aircraft_map.get_mut(&arrivals[i].icao24) {
                  entry.advisory = Some(msg);
              }
@@ -75,21 +138,41 @@ pub fn process_ground_traffic(aircraft_map: &mut HashMap<String, Aircraft>, airp
     }
 
     // Refresh arrivals snapshot for ground safety (exclude those explicitly not on final)
-    let final_traffic: Vec<Aircraft> = arrivals.iter().filter(|a| a.distance.unwrap_or(99.0) < 5.0).cloned().collect();
+    let final_traffic: Vec<Aircraft> = arrivals.iter()
+        .filter(|a| a.distance.unwrap_or(99.0) < procedures.final_protection_nm + 1.0)
+        .cloned()
+        .collect();
+    let conflict = runway_conflict(&final_traffic, arrival_end, &procedures);
+    let runway_clear = conflict.is_none();
+    let (conflicting_arrival, gap_msg) = conflict.unwrap_or_default();
 
     // 3. Iterate ground traffic
     for aircraft in aircraft_map.values_mut() {
         if !aircraft.on_ground {
             continue;
         }
+        let inputs = context.control.get(&aircraft.icao24).cloned().unwrap_or_default();
+        // Landed, so a landing clearance has been used
+        if inputs.has(ClearanceKind::Land) {
+            if let Some(i) = context.control.get_mut(&aircraft.icao24) {
+                i.consume(&[ClearanceKind::Land]);
+            }
+        }
+        if let Some(stand) = &inputs.stand {
+            aircraft.stand = Some(stand.clone());
+        }
+        let departure_runway = inputs.runway.as_deref().unwrap_or(departure_runway);
+        let cleared = |kind| inputs.has(kind);
+        let withheld = |kind| inputs.withholds(kind) && !inputs.has(kind);
 
         // Initialize state if missing
         if aircraft.ground_state.is_none() {
              // Determine initial state based on location
-             if let Some((stand, dist)) = airport_data.egss.find_nearest_stand(aircraft.latitude.unwrap_or(0.0), aircraft.longitude.unwrap_or(0.0)) {
-                 if dist < 0.001 {
+             if let Some((stand, dist)) = airport.find_nearest_stand(aircraft.latitude.unwrap_or(0.0), aircraft.longitude.unwrap_or(0.0)) {
+                 if dist < ON_STAND_RADIUS_M {
                      aircraft.ground_state = Some("OnStand".to_string());
                      aircraft.atc_message = Some(format!("Stand {}", stand.name));
+                     aircraft.stand = Some(stand.name.clone());
                  } else {
                      aircraft.ground_state = Some("Taxiing".to_string());
                  }
@@ -100,36 +183,90 @@ pub fn process_ground_traffic(aircraft_map: &mut HashMap<String, Aircraft>, airp
         let lat = aircraft.latitude.unwrap_or(0.0);
         let lon = aircraft.longitude.unwrap_or(0.0);
 
-        // Emergency Override
-        if emergency_active {
-             let is_emergency_ac = matches!(aircraft.squawk.as_deref(), Some("7500" | "7600" | "7700"));
-             if !is_emergency_ac {
-                  aircraft.atc_message = Some("AIRPORT CLOSED - EMERGENCY IN PROGRESS".to_string());
-                  continue; 
-             }
+        // Unlawful interference on the ground: the aircraft stays put until a controller
+        // clears it to the isolated parking position
+        if aircraft.squawk.as_deref() == Some("7500") && !cleared(ClearanceKind::Taxi) {
+             aircraft.atc_message = Some("Hold Position - Await Security Instructions".to_string());
+             continue;
         }
+        let closed = closure(departure_runway).filter(|(icao24, _)| *icao24 != aircraft.icao24);
 
         // State Machine
         match aircraft.ground_state.as_deref() {
-            Some("OnStand") => {
-                if speed > 2.0 {
-                    aircraft.ground_state = Some("Pushback".to_string());
-                    aircraft.atc_message = Some("Pushback Approved".to_string());
-                }
+            Some("OnStand") if speed > 2.0 => {
+                aircraft.ground_state = Some("Pushback".to_string());
+                aircraft.atc_message = Some(if withheld(ClearanceKind::Pushback) {
+                    "Pushback Not Approved - Hold Position"
+                } else {
+                    "Pushback Approved"
+                }.to_string());
             },
-            Some("Pushback") => {
-                if speed > 5.0 {
-                    aircraft.ground_state = Some("Taxiing".to_string());
-                    aircraft.atc_message = Some("Taxi to Runway".to_string()); 
+            Some("Pushback") if speed > 5.0 => {
+                aircraft.ground_state = Some("Taxiing".to_string());
+                if let Some(i) = context.control.get_mut(&aircraft.icao24) {
+                    i.consume(&[ClearanceKind::Pushback]);
+                }
+                // A controller's taxi clearance keeps the route it was issued with
+                if withheld(ClearanceKind::Taxi) {
+                    aircraft.taxi_route = None;
+                    aircraft.atc_message = Some("Taxi Not Approved - Hold Position".to_string());
+                } else if !cleared(ClearanceKind::Taxi) {
+                    aircraft.taxi_route = airport.departure_route(departure_runway, procedures.lvp, aircraft.stand.as_deref(), lat, lon);
+                    aircraft.atc_message = Some(match &aircraft.taxi_route {
+                        Some(route) => route.instruction(),
+                        None => "Taxi to Runway".to_string(),
+                    });
                 }
             },
             Some("Taxiing") => {
+                // No route yet, e.g. after a runway assignment: plan one unless taxi is withheld
+                if aircraft.taxi_route.is_none() && inputs.runway.is_some() && !withheld(ClearanceKind::Taxi) {
+                    aircraft.taxi_route = airport.departure_route(departure_runway, procedures.lvp, None, lat, lon);
+                    if let Some(route) = &aircraft.taxi_route {
+                        aircraft.atc_message = Some(format!("Runway {} - {}", departure_runway, route.instruction()));
+                    }
+                }
+
+                // LVP declared or cancelled since the clearance: swap to the other holding point of the pair
+                let expected_hold = airport.departure_hold(departure_runway, procedures.lvp).filter(|_| !cleared(ClearanceKind::Taxi));
+                if let (Some(route), Some(hold)) = (&aircraft.taxi_route, expected_hold) {
+                    if airport.is_lvp_pair(&route.destination, &hold.name) {
+                        aircraft.taxi_route = airport.departure_route(departure_runway, procedures.lvp, None, lat, lon);
+                        aircraft.conformance = None;
+                        if let Some(route) = &aircraft.taxi_route {
+                            let status = if procedures.lvp { "LVP in force" } else { "LVP cancelled" };
+                            aircraft.atc_message = Some(format!("{} - {}", status, route.instruction()));
+                        }
+                    }
+                }
+
+                // Route conformance; alerts go out as advisories until the aircraft is back on route
+                if let Some(route) = &aircraft.taxi_route {
+                    aircraft.conformance = check_conformance(&airport.taxi_graph, route, aircraft,
+                        cleared(ClearanceKind::LineUp) || cleared(ClearanceKind::TakeOff));
+                    if let Some(c) = aircraft.conformance.as_ref().filter(|c| c.status != ConformanceStatus::Conforming) {
+                        let kind = match c.status {
+                            ConformanceStatus::UnclearedHold => AlertKind::UnclearedHold,
+                            ConformanceStatus::WrongTurn => AlertKind::WrongTurn,
+                            _ => AlertKind::RouteDeviation,
+                        };
+                        conditions.push(AlertCondition::new(kind, aircraft, c.message.clone().unwrap_or_default()));
+                        aircraft.advisory = c.message.clone();
+                    }
+                }
+
                 // Check if approaching a Hold
-                if let Some((hold, dist)) = airport_data.egss.find_nearest_hold(lat, lon) {
-                    if dist < 0.002 { 
+                // Only the clearance limit stops the aircraft; other holds on the route are cleared through
+                if let Some((hold, dist)) = airport.find_nearest_hold(lat, lon) {
+                    let is_clearance_limit = aircraft.taxi_route.as_ref().is_none_or(|r| r.destination == hold.name);
+                    if dist < HOLD_ENTRY_RADIUS_M && is_clearance_limit {
                          aircraft.ground_state = Some("Holding".to_string());
                          aircraft.atc_message = Some(format!("Hold Short {}", hold.name));
                          aircraft.hold_time = Some(now); // Start Timer
+                         aircraft.conformance = None;
+                         if let Some(i) = context.control.get_mut(&aircraft.icao24) {
+                             i.consume(&[ClearanceKind::Taxi]);
+                         }
                     }
                 }
             },
@@ -138,27 +275,49 @@ pub fn process_ground_traffic(aircraft_map: &mut HashMap<String, Aircraft>, airp
                  if let Some(t) = aircraft.hold_time {
                      if (now - t) > 180 { // 3 mins
                           aircraft.atc_message = Some("REMINDER: AWAITING TAKEOFF".to_string());
+                          conditions.push(AlertCondition::new(AlertKind::HoldTimeout, aircraft, format!("{} AWAITING TAKEOFF", callsign(aircraft))));
                           // We don't return here, we still check if we can clear them. 
                           // If we clear them, the message will be overwritten below, which is correct (problem solved).
                      }
                  }
 
-                 if let Some((_hold, dist)) = airport_data.egss.find_nearest_hold(lat, lon) {
-                    if dist > 0.003 {
+                 if let Some((_hold, dist)) = airport.find_nearest_hold(lat, lon) {
+                    if dist > HOLD_EXIT_RADIUS_M {
                          aircraft.ground_state = Some("LiningUp".to_string());
-                         aircraft.atc_message = Some("Line Up & Wait".to_string());
+                         if !cleared(ClearanceKind::TakeOff) {
+                             aircraft.atc_message = Some("Line Up & Wait".to_string());
+                         }
                          aircraft.hold_time = None; // Reset
                     } else {
                         // Still at Hold -> Check Checks
-                        let (runway_clear, gap_msg) = is_runway_clear(&final_traffic);
-                        if !runway_clear {
+                        // Controller clearances stand; the engine only points out what it would hold for
+                        if cleared(ClearanceKind::TakeOff) || cleared(ClearanceKind::LineUp) {
+                             aircraft.atc_message = Some(if cleared(ClearanceKind::TakeOff) { "Cleared for Takeoff" } else { "Line Up & Wait" }.to_string());
+                             if let Some((emergency, reason)) = closed {
+                                 let message = format!("CAUTION - Runway {} Closed - {}", departure_runway, reason);
+                                 conditions.push(AlertCondition::new(AlertKind::RunwayClosed, aircraft, message.clone()).involving(emergency));
+                                 aircraft.advisory = Some(message);
+                             } else if !runway_clear {
+                                 // The live distance stays in the instruction; in the alert it would make every cycle an update
+                                 let message = format!("CAUTION - Hold Short - Traffic Final {}", arrival_end.map(|e| e.name.as_str()).unwrap_or_default());
+                                 conditions.push(AlertCondition::new(AlertKind::TrafficOnFinal, aircraft, message.clone()).involving(&conflicting_arrival));
+                                 aircraft.advisory = Some(message);
+                             }
+                        } else if let Some((_, reason)) = closed {
+                             aircraft.atc_message = Some(format!("Hold Short - Runway {} Closed - {}", departure_runway, reason));
+                        } else if context.change.is_some() {
+                             aircraft.atc_message = Some("Hold Short - Runway Change in Progress".to_string());
+                        } else if !runway_clear {
                              // Only overwrite Stagnation warning if there is a valid reason to hold
-                             aircraft.atc_message = Some(gap_msg);
+                             aircraft.atc_message = Some(gap_msg.clone());
                         } else {
                             // Check Wake Turbulence Timer
                             let time_since_dep = now - context.last_departure_time;
-                            if time_since_dep < 120 { 
-                                 aircraft.atc_message = Some(format!("Hold Short - Wake Turbulence ({}s)", 120 - time_since_dep));
+                            let interval = procedures.departure_interval_s;
+                            if time_since_dep < interval {
+                                 aircraft.atc_message = Some(format!("Hold Short - Wake Turbulence ({}s)", interval - time_since_dep));
+                            } else if withheld(ClearanceKind::TakeOff) {
+                                 aircraft.atc_message = Some("Hold Short - Take-off Clearance Withheld".to_string());
                             } else {
                                  aircraft.atc_message = Some("Cleared for Takeoff".to_string());
                             }
@@ -178,31 +337,216 @@ pub fn process_ground_traffic(aircraft_map: &mut HashMap<String, Aircraft>, airp
                      }
                 }
 
+                // Runway closed behind a departure that has not started its roll
+                if let Some((_, reason)) = closed.filter(|_| speed <= 40.0 && !cleared(ClearanceKind::TakeOff)) {
+                    aircraft.atc_message = Some(format!("Line-up Cancelled - Vacate Runway {} - {}", departure_runway, reason));
+                }
+
                 if speed > 40.0 {
                     aircraft.ground_state = Some("Takeoff".to_string());
                     aircraft.atc_message = Some("Takeoff Roll".to_string());
                     // Update Timer
                     context.last_departure_time = now;
+                    if let Some(i) = context.control.get_mut(&aircraft.icao24) {
+                        i.consume(&[ClearanceKind::Pushback, ClearanceKind::Taxi, ClearanceKind::LineUp, ClearanceKind::TakeOff]);
+                    }
                 }
             },
             _ => {}
         }
+
+        // ILS sensitive area protection: under LVP nothing but the departure may be
+        // inside it while an arrival is on final
+        if procedures.lvp && !runway_clear && matches!(aircraft.ground_state.as_deref(), Some("Taxiing" | "Holding")) {
+            if let Some(end) = arrival_end.filter(|end| airport.in_ils_sensitive_area(end, lat, lon)) {
+                let message = format!("ILS SENSITIVE AREA - {} VACATE, TRAFFIC FINAL {}", aircraft.callsign.as_deref().unwrap_or(&aircraft.icao24), end.name);
+                conditions.push(AlertCondition::new(AlertKind::IlsSensitiveArea, aircraft, message.clone()).involving(&conflicting_arrival));
+                aircraft.advisory = Some(message);
+            }
+        }
+    }
+
+    // Advisories whose alert is acknowledged or inhibited stay off the display until it
+    // escalates or clears
+    context.alerts.update(conditions, aircraft_map, now);
+    for aircraft in aircraft_map.values_mut() {
+        let quiet = aircraft.advisory.as_deref()
+            .and_then(|advisory| context.alerts.for_message(&aircraft.icao24, advisory))
+            .is_some_and(|alert| alert.is_quiet());
+        if quiet {
+            aircraft.advisory = None;
+        }
+    }
+}
+
+fn callsign(aircraft: &Aircraft) -> &str {
+    aircraft.callsign.as_deref().unwrap_or(&aircraft.icao24).trim()
+}
+
+/// Applies a detected movement event: go-arounds leave the landing order and rejoin
+/// the sequence, a rejected take-off is told to vacate the runway.
+pub fn apply_movement_event(aircraft_map: &mut HashMap<String, Aircraft>, context: &mut RunwayContext, event: &MovementEvent, now: i64) {
+    let Some(aircraft) = aircraft_map.get_mut(&event.icao24) else {
+        return;
+    };
+
+    match event.kind {
+        MovementEventKind::GoAround | MovementEventKind::MissedApproach | MovementEventKind::TouchAndGo => {
+            context.sequence.rejoin(aircraft, now);
+            if let Some(inputs) = context.control.get_mut(&aircraft.icao24) {
+                inputs.consume(&[ClearanceKind::Land]);
+            }
+        }
+        MovementEventKind::RejectedTakeOff => {
+            let message = format!("REJECTED TAKE-OFF {} RUNWAY {}", callsign(aircraft), event.runway.as_deref().unwrap_or("-"));
+            context.alerts.latch(AlertCondition::new(AlertKind::RejectedTakeOff, aircraft, message), now);
+            if let Some(inputs) = context.control.get_mut(&aircraft.icao24) {
+                inputs.consume(&[ClearanceKind::Pushback, ClearanceKind::Taxi, ClearanceKind::LineUp, ClearanceKind::TakeOff]);
+            }
+            aircraft.ground_state = Some("Taxiing".to_string());
+            aircraft.taxi_route = None;
+            aircraft.conformance = None;
+            aircraft.atc_message = Some("Rejected Take-off - Vacate Runway When Able".to_string());
+        }
     }
 }
 
-fn is_runway_clear(arrivals: &[Aircraft]) -> (bool, String) {
-    let thresh_lat = 51.895;
-    let thresh_lon = 0.250;
+/// Completes a pending runway change once the old configuration is clear: no arrival
+/// inside the final protection area of the old arrival end and no departure lining up
+/// or rolling. Departures already taxiing or holding are then re-routed to the new
+/// departure runway.
+fn advance_runway_change(
+    aircraft_map: &mut HashMap<String, Aircraft>,
+    airport: &Airport,
+    context: &mut RunwayContext,
+    procedures: &Procedures,
+    now: i64,
+) {
+    let Some(change) = context.change.clone() else {
+        return;
+    };
+    let old_arrival = context.runway_config(airport).and_then(|r| r.arrival_end(airport).cloned());
 
+    let arrival_inbound = old_arrival.is_some_and(|end| {
+        aircraft_map.values().any(|a| {
+            matches!(a.phase, Phase::Final | Phase::Landing)
+                && end.distance_nm(a.latitude.unwrap_or(0.0), a.longitude.unwrap_or(0.0)) < procedures.final_protection_nm
+        })
+    });
+    let departure_rolling = aircraft_map.values()
+        .any(|a| a.on_ground && matches!(a.ground_state.as_deref(), Some("LiningUp" | "Takeoff")));
+
+    if (arrival_inbound || departure_rolling) && now - change.requested_at < RUNWAY_CHANGE_TIMEOUT_S {
+        return;
+    }
+
+    println!("{}: runway change to arrivals {} / departures {} complete", airport.icao, change.to.arrival, change.to.departure);
+    context.config = Some(change.to.clone());
+    context.change = None;
+
+    // Re-sequence departures onto the new runway, except those a controller has routed
+    for aircraft in aircraft_map.values_mut() {
+        if !aircraft.on_ground || !matches!(aircraft.ground_state.as_deref(), Some("Taxiing" | "Holding")) {
+            continue;
+        }
+        if context.control.get(&aircraft.icao24).is_some_and(|i| i.runway.is_some() || i.has(ClearanceKind::Taxi)) {
+            continue;
+        }
+        let (lat, lon) = (aircraft.latitude.unwrap_or(0.0), aircraft.longitude.unwrap_or(0.0));
+        aircraft.taxi_route = airport.departure_route(&change.to.departure, procedures.lvp, None, lat, lon);
+        aircraft.ground_state = Some("Taxiing".to_string());
+        aircraft.hold_time = None;
+        aircraft.conformance = None;
+        aircraft.atc_message = Some(match &aircraft.taxi_route {
+            Some(route) => format!("Runway {} in use - {}", change.to.departure, route.instruction()),
+            None => format!("Runway {} in use - Taxi to Runway", change.to.departure),
+        });
+    }
+}
+
+/// First arrival inside the final protection area, with the hold instruction it calls for.
+fn runway_conflict(arrivals: &[Aircraft], arrival_end: Option<&RunwayEnd>, procedures: &Procedures) -> Option<(String, String)> {
+    let end = arrival_end?;
     for arr in arrivals {
         let lat = arr.latitude.unwrap_or(0.0);
         let lon = arr.longitude.unwrap_or(0.0);
-        let dist = ((lat - thresh_lat).powi(2) + (lon - thresh_lon).powi(2)).sqrt();
+        let dist = end.distance_nm(lat, lon);
+        let height = arr.baro_altitude.unwrap_or(0.0) - end.elevation_ft;
         
-        // 0.08 deg ~ 4-5nm. 0.05 ~ 3nm.
-        if dist < 0.08 && arr.baro_altitude.unwrap_or(0.0) < 2000.0 {
-            return (false, format!("Hold Short - Traffic Final ({:.1}nm)", dist * 60.0)); // Approx conversion for display
+        if dist < procedures.final_protection_nm && height < 2000.0 {
+            return Some((arr.icao24.clone(), format!("Hold Short - Traffic Final {} ({:.1}nm)", end.name, dist)));
         }
     }
-    (true, String::new())
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::euroscope::{RunwayLine, SectorData};
+    use crate::logic::airport::Coordinate;
+
+    fn airport() -> Airport {
+        Airport::from_sector(&SectorData {
+            icao: "EGSS".to_string(),
+            runways: vec![RunwayLine {
+                designators: ["04".to_string(), "22".to_string()],
+                thresholds: [Coordinate { lat: 51.877039, lon: 0.222861 }, Coordinate { lat: 51.895158, lon: 0.250044 }],
+                airport: Some("EGSS".to_string()),
+            }],
+            ..Default::default()
+        })
+    }
+
+    fn runways(end: &str) -> RunwayConfig {
+        RunwayConfig { arrival: end.to_string(), departure: end.to_string() }
+    }
+
+    /// About 2 nm out on final for runway 04.
+    fn on_final_04() -> HashMap<String, Aircraft> {
+        let arrival = Aircraft {
+            icao24: "aaa001".to_string(),
+            phase: Phase::Final,
+            latitude: Some(51.853),
+            longitude: Some(0.187),
+            baro_altitude: Some(700.0),
+            true_track: Some(43.0),
+            ..Aircraft::default()
+        };
+        HashMap::from([(arrival.icao24.clone(), arrival)])
+    }
+
+    #[test]
+    fn a_runway_change_waits_for_the_old_runway_to_drain() {
+        let airport = airport();
+        let mut context = RunwayContext::default();
+        context.request_change(&airport, runways("04"), 1000);
+        assert!(context.change.is_none(), "requesting the runways in use is not a change");
+
+        context.request_change(&airport, runways("22"), 1000);
+        let mut aircraft = on_final_04();
+        process_ground_traffic(&mut aircraft, &airport, &mut context, 1010);
+        assert_eq!(context.config, Some(runways("04")));
+        assert_eq!(context.change.as_ref().map(|c| c.requested_at), Some(1000));
+
+        // The last arrival on 04 has landed and gone
+        aircraft.clear();
+        process_ground_traffic(&mut aircraft, &airport, &mut context, 1020);
+        assert_eq!(context.config, Some(runways("22")));
+        assert!(context.change.is_none());
+    }
+
+    #[test]
+    fn a_runway_change_completes_after_the_timeout() {
+        let airport = airport();
+        let mut context = RunwayContext::default();
+        context.request_change(&airport, runways("22"), 1000);
+
+        let mut aircraft = on_final_04();
+        process_ground_traffic(&mut aircraft, &airport, &mut context, 1000 + RUNWAY_CHANGE_TIMEOUT_S - 1);
+        assert_eq!(context.config, Some(runways("04")));
+        process_ground_traffic(&mut aircraft, &airport, &mut context, 1000 + RUNWAY_CHANGE_TIMEOUT_S);
+        assert_eq!(context.config, Some(runways("22")));
+        assert!(context.change.is_none());
+    }
 }
@@ -0,0 +1,75 @@
+use rstar::primitives::{GeomWithData, Line};
+use rstar::RTree;
+
+use crate::logic::geofence::{haversine_distance, LocalFrame};
+
+type IndexedPoint = GeomWithData<[f64; 2], usize>;
+type IndexedSegment = GeomWithData<Line<[f64; 2]>, usize>;
+
+/// R-tree over point features such as stands or holding points.
+///
+/// Points are stored in a local metric frame so nearest-neighbour search is isotropic;
+/// reported distances are great-circle metres. Ids are the caller's indices.
+#[derive(Debug, Clone, Default)]
+pub struct PointIndex {
+    frame: LocalFrame,
+    tree: RTree<IndexedPoint>,
+}
+
+impl PointIndex {
+    pub fn build(frame: LocalFrame, points: impl IntoIterator<Item = (f64, f64)>) -> Self {
+        let items = points.into_iter()
+            .enumerate()
+            .map(|(id, (lat, lon))| {
+                let (x, y) = frame.to_xy(lat, lon);
+                IndexedPoint::new([x, y], id)
+            })
+            .collect();
+        PointIndex { frame, tree: RTree::bulk_load(items) }
+    }
+
+    /// Id of the nearest point and its distance in metres.
+    pub fn nearest(&self, lat: f64, lon: f64) -> Option<(usize, f64)> {
+        let (x, y) = self.frame.to_xy(lat, lon);
+        let item = self.tree.nearest_neighbor(&[x, y])?;
+        let (plat, plon) = self.frame.to_lat_lon(item.geom()[0], item.geom()[1]);
+        Some((item.data, haversine_distance(lat, lon, plat, plon) * 1000.0))
+    }
+}
+
+/// R-tree over line segments such as taxiway centrelines.
+#[derive(Debug, Clone, Default)]
+pub struct SegmentIndex {
+    frame: LocalFrame,
+    tree: RTree<IndexedSegment>,
+}
+
+impl SegmentIndex {
+    pub fn build(frame: LocalFrame, segments: impl IntoIterator<Item = ((f64, f64), (f64, f64))>) -> Self {
+        let items = segments.into_iter()
+            .enumerate()
+            .map(|(id, (a, b))| {
+                let (ax, ay) = frame.to_xy(a.0, a.1);
+                let (bx, by) = frame.to_xy(b.0, b.1);
+                IndexedSegment::new(Line::new([ax, ay], [bx, by]), id)
+            })
+            .collect();
+        SegmentIndex { frame, tree: RTree::bulk_load(items) }
+    }
+
+    /// Segments passing within `radius_m` of a position, with their distance in metres.
+    pub fn within(&self, lat: f64, lon: f64, radius_m: f64) -> Vec<(usize, f64)> {
+        let (x, y) = self.frame.to_xy(lat, lon);
+        self.tree
+            .locate_within_distance([x, y], radius_m * radius_m)
+            .map(|item| (item.data, self.distance_to(lat, lon, item)))
+            .collect()
+    }
+
+    fn distance_to(&self, lat: f64, lon: f64, item: &IndexedSegment) -> f64 {
+        let (x, y) = self.frame.to_xy(lat, lon);
+        let [fx, fy] = item.geom().nearest_point(&[x, y]);
+        let (flat, flon) = self.frame.to_lat_lon(fx, fy);
+        haversine_distance(lat, lon, flat, flon) * 1000.0
+    }
+}
@@ -0,0 +1,204 @@
+use serde::Serialize;
+
+use crate::logic::airport::{Airport, RunwayEnd};
+use crate::weather::Wind;
+
+/// Preferential runway limits, gusts included.
+const MAX_TAILWIND_KT: f64 = 5.0;
+const MAX_CROSSWIND_KT: f64 = 20.0;
+
+/// Wind resolved along and across one runway end. Headwind and tailwind are both
+/// reported as non-negative values; only one of them is non-zero.
+#[derive(Debug, Clone, Serialize)]
+pub struct WindComponents {
+    pub runway: String,
+    pub headwind_kt: f64,
+    pub tailwind_kt: f64,
+    pub crosswind_kt: f64,
+    /// Components using the gust speed, when gusts are reported.
+    pub gust_tailwind_kt: Option<f64>,
+    pub gust_crosswind_kt: Option<f64>,
+}
+
+impl WindComponents {
+    /// Worst tailwind and crosswind, gusts included.
+    fn worst(&self) -> (f64, f64) {
+        (
+            self.gust_tailwind_kt.unwrap_or(self.tailwind_kt),
+            self.gust_crosswind_kt.unwrap_or(self.crosswind_kt),
+        )
+    }
+
+    fn within_limits(&self) -> bool {
+        let (tail, cross) = self.worst();
+        tail <= MAX_TAILWIND_KT && cross <= MAX_CROSSWIND_KT
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RunwayRecommendation {
+    pub runway: String,
+    pub reason: String,
+}
+
+/// METAR winds are given in degrees true, as are runway headings. Variable wind is
+/// treated as the worst case: all of it across and all of it behind.
+pub fn wind_components(end: &RunwayEnd, wind: &Wind) -> WindComponents {
+    let resolve = |speed: f64| -> (f64, f64) {
+        match wind.direction {
+            Some(direction) => {
+                let angle = (direction as f64 - end.heading).to_radians();
+                (speed * angle.cos(), (speed * angle.sin()).abs())
+            }
+            None => (-speed, speed),
+        }
+    };
+    let round = |v: f64| (v * 10.0).round() / 10.0;
+
+    let (along, across) = resolve(wind.speed_kt as f64);
+    let gust = wind.gust_kt.map(|g| resolve(g as f64));
+
+    WindComponents {
+        runway: end.name.clone(),
+        headwind_kt: round(along.max(0.0)),
+        tailwind_kt: round((-along).max(0.0)),
+        crosswind_kt: round(across),
+        gust_tailwind_kt: gust.map(|(along, _)| round((-along).max(0.0))),
+        gust_crosswind_kt: gust.map(|(_, across)| round(across)),
+    }
+}
+
+pub fn all_components(airport: &Airport, wind: &Wind) -> Vec<WindComponents> {
+    airport.runways.iter()
+        .flat_map(|r| r.ends.iter())
+        .map(|end| wind_components(end, wind))
+        .collect()
+}
+
+/// Recommends the runway end for the reported wind. The current runway is kept while it
+/// stays within the tailwind and crosswind limits, so light winds do not flip the
+/// direction back and forth; otherwise the end with the most headwind is chosen.
+pub fn recommend_runway(airport: &Airport, wind: Option<&Wind>, current: Option<&str>) -> Option<RunwayRecommendation> {
+    let Some(wind) = wind else {
+        return current.map(|c| RunwayRecommendation { runway: c.to_string(), reason: "No wind reported".to_string() });
+    };
+
+    let components = all_components(airport, wind);
+    if let Some(c) = components.iter().find(|c| Some(c.runway.as_str()) == current && c.within_limits()) {
+        return Some(RunwayRecommendation {
+            runway: c.runway.clone(),
+            reason: "Current runway within wind limits".to_string(),
+        });
+    }
+
+    let most_headwind = |a: &&WindComponents, b: &&WindComponents| {
+        (a.headwind_kt - a.tailwind_kt).partial_cmp(&(b.headwind_kt - b.tailwind_kt)).unwrap_or(std::cmp::Ordering::Equal)
+    };
+
+    if let Some(best) = components.iter().filter(|c| c.within_limits()).max_by(most_headwind) {
+        return Some(RunwayRecommendation {
+            runway: best.runway.clone(),
+            reason: format!("Best headwind ({:.0}kt)", best.headwind_kt),
+        });
+    }
+
+    components.iter().max_by(most_headwind).map(|best| {
+        let (tail, cross) = best.worst();
+        RunwayRecommendation {
+            runway: best.runway.clone(),
+            reason: format!("No runway within limits (tailwind {:.0}kt, crosswind {:.0}kt)", tail, cross),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::euroscope::{RunwayLine, SectorData};
+    use crate::logic::airport::Coordinate;
+
+    /// Runway 36/18, laid out due north so the components come out exactly.
+    fn airport() -> Airport {
+        Airport::from_sector(&SectorData {
+            icao: "EGXX".to_string(),
+            runways: vec![RunwayLine {
+                designators: ["36".to_string(), "18".to_string()],
+                thresholds: [Coordinate { lat: 52.0, lon: 0.0 }, Coordinate { lat: 52.03, lon: 0.0 }],
+                airport: None,
+            }],
+            ..Default::default()
+        })
+    }
+
+    fn wind(direction: Option<u16>, speed_kt: u32, gust_kt: Option<u32>) -> Wind {
+        Wind { direction, speed_kt, gust_kt, variable_from: None, variable_to: None }
+    }
+
+    fn components(airport: &Airport, end: &str, wind: &Wind) -> (f64, f64, f64) {
+        let c = wind_components(airport.runway_end(end).unwrap(), wind);
+        (c.headwind_kt, c.tailwind_kt, c.crosswind_kt)
+    }
+
+    #[test]
+    fn resolves_the_wind_along_and_across_the_runway() {
+        let airport = airport();
+        for (end, wind, expected) in [
+            ("36", wind(Some(360), 20, None), (20.0, 0.0, 0.0)),
+            ("36", wind(Some(90), 15, None), (0.0, 0.0, 15.0)),
+            ("36", wind(Some(30), 20, None), (17.3, 0.0, 10.0)),
+            // A tailwind is reported as such, never as headwind
+            ("18", wind(Some(360), 20, None), (0.0, 20.0, 0.0)),
+            ("36", wind(Some(210), 10, None), (0.0, 8.7, 5.0)),
+            // Variable wind counts fully behind and fully across
+            ("36", wind(None, 5, None), (0.0, 5.0, 5.0)),
+            ("36", wind(Some(0), 0, None), (0.0, 0.0, 0.0)),
+        ] {
+            assert_eq!(components(&airport, end, &wind), expected, "{} {:?}", end, wind);
+        }
+
+        let gusting = wind_components(airport.runway_end("18").unwrap(), &wind(Some(300), 10, Some(20)));
+        assert_eq!((gusting.tailwind_kt, gusting.crosswind_kt), (5.0, 8.7));
+        assert_eq!((gusting.gust_tailwind_kt, gusting.gust_crosswind_kt), (Some(10.0), Some(17.3)));
+    }
+
+    #[test]
+    fn keeps_the_current_runway_while_within_limits() {
+        let airport = airport();
+        let recommend = |wind: Option<Wind>, current: Option<&str>| {
+            recommend_runway(&airport, wind.as_ref(), current).map(|r| (r.runway, r.reason))
+        };
+        let kept = |runway: &str| Some((runway.to_string(), "Current runway within wind limits".to_string()));
+
+        assert_eq!(recommend(None, Some("18")), Some(("18".to_string(), "No wind reported".to_string())));
+        assert_eq!(recommend(None, None), None);
+        // Calm, a straight crosswind that favours neither end, light variable wind and
+        // a tailwind within limits all leave the runway alone
+        for current in ["36", "18"] {
+            assert_eq!(recommend(Some(wind(Some(0), 0, None)), Some(current)), kept(current));
+            assert_eq!(recommend(Some(wind(Some(90), 15, None)), Some(current)), kept(current));
+            assert_eq!(recommend(Some(wind(None, 3, None)), Some(current)), kept(current));
+        }
+        assert_eq!(recommend(Some(wind(Some(360), 4, None)), Some("18")), kept("18"));
+    }
+
+    #[test]
+    fn changes_runway_when_the_current_one_is_out_of_limits() {
+        let airport = airport();
+        let recommend = |wind: Wind, current: Option<&str>| {
+            recommend_runway(&airport, Some(&wind), current).map(|r| (r.runway, r.reason)).unwrap()
+        };
+
+        assert_eq!(recommend(wind(Some(360), 10, None), Some("18")), ("36".to_string(), "Best headwind (10kt)".to_string()));
+        assert_eq!(recommend(wind(Some(180), 12, None), None), ("18".to_string(), "Best headwind (12kt)".to_string()));
+        // Gusts count against the limits
+        assert_eq!(recommend(wind(Some(360), 4, Some(12)), Some("18")).0, "36");
+        assert_eq!(
+            recommend(wind(Some(90), 30, None), Some("36")).1,
+            "No runway within limits (tailwind 0kt, crosswind 30kt)",
+        );
+        assert_eq!(
+            recommend(wind(None, 8, None), Some("36")).1,
+            "No runway within limits (tailwind 8kt, crosswind 8kt)",
+        );
+    }
+}
@@ -1,180 +1,266 @@
+mod analytics;
 mod config;
 mod models;
 mod adsblol; // Changed from opensky
+mod euroscope;
 mod logic;
+mod monitor;
+mod reload;
+mod store;
+mod stream;
+mod weather;
 
 use axum::{
-    extract::State,
-    routing::get,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{delete, get, post},
     Router,
     Json,
 };
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, RwLock};
 use std::collections::HashMap;
 use std::time::Duration;
+use tokio::sync::broadcast;
 use tokio::time;
 use tower_http::cors::CorsLayer;
 
+use crate::analytics::{AnalyticsQuery, Format};
 use crate::config::Config;
-use crate::models::{Aircraft, AircraftState};
-use crate::adsblol::AdsbLolClient;
-use crate::logic::geofence::{AirportZones, haversine_distance};
-use crate::logic::phases::determine_phase;
-use crate::logic::airport::{load_airport_data, AirportData};
-use crate::logic::sequencing::{process_ground_traffic, RunwayContext};
+use crate::models::{Aircraft, TrackSample};
+use crate::logic::airport::{load_airport_data, Airport, AirportData, RunwayConfig};
+use crate::logic::routing::TaxiRoute;
+use crate::logic::alerts::{Alert, AlertKind, Inhibition};
+use crate::logic::aman::ArrivalSequence;
+use crate::logic::control::{AircraftInputs, Command, ControllerInputs};
+use crate::logic::emergency::{Emergency, EmergencyTracker};
+use crate::logic::events::MovementEvent;
+use crate::logic::holding::Stack;
+use crate::logic::lvp::LvpState;
+use crate::logic::sequencing::RunwayChange;
+use crate::logic::wind::{all_components, recommend_runway, RunwayRecommendation, WindComponents};
+use crate::monitor::{run_poller, AirportMonitor};
+use crate::reload::{reload_in_background, spawn_watcher, ReloadReport, ReloadTrigger};
+use crate::store::{spawn_recorder, Movement, MovementDetail, MovementQuery, MovementStore};
+use crate::stream::{sse_handler, ws_handler, EventFeed, TrafficCycle, CYCLE_BUFFER};
+use crate::weather::{AirportWeather, Metar, Taf, WeatherClient, WeatherSource};
 
 #[derive(serde::Deserialize)]
-struct SetAirportRequest {
+struct SetLvpRequest {
+    active: Option<bool>,
+}
+
+#[derive(serde::Deserialize)]
+struct RouteQuery {
+    from: String,
+    to: String,
+}
+
+#[derive(serde::Deserialize)]
+struct AlertQuery {
+    airport: Option<String>,
+    /// Include recently cleared alerts.
+    #[serde(default)]
+    cleared: bool,
+}
+
+#[derive(serde::Deserialize)]
+struct InhibitRequest {
+    airport: String,
+    kind: AlertKind,
+    icao24: Option<String>,
+    /// Indefinite when omitted.
+    duration_s: Option<i64>,
+    reason: Option<String>,
+}
+
+/// An alert or inhibition tagged with the airport it belongs to.
+#[derive(serde::Serialize)]
+struct AtAirport<T> {
+    airport: String,
+    #[serde(flatten)]
+    item: T,
+}
+
+#[derive(serde::Serialize)]
+struct AlertList {
+    /// Highest severity first, then oldest first.
+    alerts: Vec<AtAirport<Alert>>,
+    inhibitions: Vec<AtAirport<Inhibition>>,
+}
+
+#[derive(serde::Serialize)]
+struct RunwayStatus {
+    config: Option<RunwayConfig>,
+    change: Option<RunwayChange>,
+    runway_ends: Vec<String>,
+}
+
+#[derive(serde::Serialize)]
+struct WeatherReport {
+    metar: Option<Metar>,
+    taf: Option<Taf>,
+    fetched_at: Option<i64>,
+    wind_components: Vec<WindComponents>,
+    recommended_runway: Option<RunwayRecommendation>,
+}
+
+/// A monitored airport and how much traffic it currently has.
+#[derive(serde::Serialize)]
+struct MonitoredAirport {
     code: String,
+    lat: f64,
+    lon: f64,
+    radius: u32,
+    has_ground_data: bool,
+    aircraft: usize,
+    last_update: Option<i64>,
 }
 
-struct AppState {
-    aircraft: Mutex<HashMap<String, Aircraft>>,
-    history: Mutex<HashMap<String, AircraftState>>,
+/// Settings and the ground data built from them. A reload swaps both in one step so a
+/// cycle never sees new settings with old sector data.
+struct LiveConfig {
     config: Config,
-    airport_data: Option<Arc<AirportData>>,
-    runway_context: Mutex<RunwayContext>,
-    active_airport: Mutex<Option<String>>,
+    airport_data: AirportData,
+}
+
+struct AppState {
+    live: RwLock<Arc<LiveConfig>>,
+    /// One monitor per configured airport, keyed by upper-case code.
+    monitors: RwLock<HashMap<String, Arc<AirportMonitor>>>,
+    weather: Mutex<HashMap<String, AirportWeather>>,
+    /// Per-cycle traffic from every airport for the push feeds.
+    updates: broadcast::Sender<TrafficCycle>,
+    /// Typed advisory and alert events for the SSE feed.
+    feed: Mutex<EventFeed>,
+    /// Outcome of the most recent reload.
+    last_reload: Mutex<Option<ReloadReport>>,
+    store: Arc<MovementStore>,
+}
+
+impl AppState {
+    fn live(&self) -> Arc<LiveConfig> {
+        self.live.read().unwrap().clone()
+    }
+
+    fn monitor(&self, code: &str) -> Option<Arc<AirportMonitor>> {
+        self.monitors.read().unwrap().get(&code.to_ascii_uppercase()).cloned()
+    }
 }
 
 #[tokio::main]
 async fn main() {
     // Load config
-    let config = Config::from_env();
-    
+    let config = Config::load().unwrap_or_else(|e| {
+        eprintln!("Configuration error: {}", e);
+        std::process::exit(1);
+    });
+
+    // `backend analytics ...` prints a report from the movement log instead of serving
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().is_some_and(|a| a == "analytics") {
+        match analytics::run_command(&args[1..], &config.database) {
+            Ok(output) => print!("{}", output),
+            Err(e) => {
+                eprintln!("Analytics error: {}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    match &config.path {
+        Some(path) => println!("Loaded configuration from {}", path.display()),
+        None => println!("No atc.toml found, using built-in airports"),
+    }
+
     // Load Airport Data
-    let airport_data = load_airport_data().map(Arc::new);
+    let airport_data = load_airport_data(config.airports.iter().map(|a| (a.code.as_str(), a.data_dir.as_path())));
+    if let Err(e) = config.check_runways(&airport_data) {
+        eprintln!("Configuration error: {}", e);
+        std::process::exit(1);
+    }
+
+    let store = MovementStore::open(&config.database).unwrap_or_else(|e| {
+        eprintln!("Movement log error: failed to open {}: {}", config.database.display(), e);
+        std::process::exit(1);
+    });
+    println!("Recording movements to {}", config.database.display());
 
     // Shared state
+    let monitors = config.airports.iter()
+        .map(|a| (a.code.clone(), Arc::new(AirportMonitor::new(a, airport_data.get(&a.code)))))
+        .collect();
     let state = Arc::new(AppState {
-        aircraft: Mutex::new(HashMap::new()),
-        history: Mutex::new(HashMap::new()),
-        config: config.clone(),
-        airport_data: airport_data.clone(),
-        runway_context: Mutex::new(RunwayContext::default()),
-        active_airport: Mutex::new(None),
+        last_reload: Mutex::new(Some(ReloadReport::startup(&config))),
+        live: RwLock::new(Arc::new(LiveConfig { config: config.clone(), airport_data })),
+        monitors: RwLock::new(monitors),
+        weather: Mutex::new(HashMap::new()),
+        updates: broadcast::channel(CYCLE_BUFFER).0,
+        feed: Mutex::new(EventFeed::new()),
+        store: Arc::new(store),
     });
+    spawn_recorder(state.clone(), state.store.clone());
 
-    // Start Poller
-    let poller_state = state.clone();
+    // Start Weather Poller
+    let weather_state = state.clone();
     tokio::spawn(async move {
-        let client = AdsbLolClient::new();
-        let zones = AirportZones::new(); 
-        
-        let mut interval = time::interval(Duration::from_secs(2)); 
+        let mut client: Option<(WeatherSource, WeatherClient)> = None;
+        let mut interval = time::interval(Duration::from_secs(60));
 
         loop {
             interval.tick().await;
-            
-            // 1. Determine Active Airport
-            let active_code = {
-                let lock = poller_state.active_airport.lock().unwrap();
-                lock.clone()
+            // Source and airports may change on reload
+            let live = weather_state.live();
+            if client.as_ref().is_none_or(|(source, _)| *source != live.config.weather_source) {
+                client = Some((live.config.weather_source.clone(), WeatherClient::new(live.config.weather_source.clone())));
+            }
+            let Some((_, client)) = &client else {
+                continue;
             };
-
-            if let Some(target_code) = active_code {
-                // Find config
-                if let Some(airport) = poller_state.config.airports.iter().find(|a| a.code == target_code) {
-                     match client.fetch_aircraft(airport.lat, airport.lon, airport.radius).await {
-                        Ok(planes) => {
-                             let planes_with_context: Vec<Aircraft> = planes.into_iter().filter_map(|mut p| {
-                                 if p.origin_country == "Unknown" {
-                                     p.origin_country = airport.code.clone();
-                                 }
-                                 
-                                 // Spurious Ground Filter
-                                 if p.on_ground {
-                                     if let (Some(lat), Some(lon)) = (p.latitude, p.longitude) {
-                                         let dist_km = haversine_distance(lat, lon, airport.lat, airport.lon);
-                                         if dist_km > 5.5 {
-                                             return None;
-                                         }
-                                     }
-                                 }
-                                 Some(p)
-                             }).collect();
-                            
-                            // UPDATE STATE
-                            let mut ac_lock = poller_state.aircraft.lock().unwrap();
-                            let mut hist_lock = poller_state.history.lock().unwrap();
-                            
-                            // Prune aircraft NOT in the new list (Full Sync) to handle switching cleanly
-                            // Or just standard update. 
-                            // Better: prune anything not updated in this cycle if we want strict sync, 
-                            // but standard logic prune stale (>60s) is defined below. 
-                            // However, if we switched airport, we want to clear old ones. 
-                            // The handler does the clear, so here we just update.
-                            
-                            let now_ts = chrono::Utc::now().timestamp();
-                            
-                            for mut plane in planes_with_context {
-                                // Get history
-                                let prev = hist_lock.get(&plane.icao24);
-                                
-                                // Determine Phase
-                                let phase = determine_phase(&plane, prev, &zones);
-                                plane.phase = phase; 
-                                
-                                // Maintain Ground State
-                                if let Some(existing) = ac_lock.get(&plane.icao24) {
-                                    plane.ground_state = existing.ground_state.clone();
-                                    plane.atc_message = existing.atc_message.clone();
-                                }
-
-                                // Calculate ETA / DME
-                                if phase == crate::models::Phase::Approach || phase == crate::models::Phase::Final {
-                                    if let (Some(lat), Some(lon), Some(spd)) = (plane.latitude, plane.longitude, plane.velocity) {
-                                        if spd > 10.0 {
-                                            let dist_km = haversine_distance(lat, lon, airport.lat, airport.lon);
-                                            let dist_nm = dist_km * 0.539957;
-                                            plane.distance = Some(dist_nm);
-                                            
-                                            let time_hours = dist_nm / spd;
-                                            let time_seconds = time_hours * 3600.0;
-                                            plane.eta = Some(now_ts + time_seconds as i64);
-                                        }
-                                    }
-                                }
-                                
-                                // Update History
-                                let state_entry = AircraftState {
-                                    icao24: plane.icao24.clone(),
-                                    phase,
-                                    last_update: now_ts,
-                                };
-                                hist_lock.insert(plane.icao24.clone(), state_entry);
-                                
-                                // Update Current View
-                                ac_lock.insert(plane.icao24.clone(), plane);
-                            }
-
-                            // Prune stale aircraft (> 60s)
-                            ac_lock.retain(|_, ac| {
-                                (now_ts - ac.last_contact) < 60
-                            });
-                            
-                            // Ground Logic
-                            if let Some(ad) = &poller_state.airport_data {
-                                let mut ctx_lock = poller_state.runway_context.lock().unwrap();
-                                process_ground_traffic(&mut ac_lock, ad, &mut ctx_lock);
-                            }
-
-                        },
-                        Err(e) => {
-                            eprintln!("Error fetching for {}: {}", target_code, e);
-                        }
+            for airport in &live.config.airports {
+                match client.fetch(&airport.code).await {
+                    Ok(report) => {
+                        weather_state.weather.lock().unwrap().insert(airport.code.clone(), report);
                     }
+                    Err(e) => eprintln!("Error fetching weather for {}: {}", airport.code, e),
                 }
-            } else {
-                // No airport selected, do nothing or sleep longer
             }
         }
     });
 
+    // One poller per monitored airport
+    for monitor in state.monitors.read().unwrap().values() {
+        tokio::spawn(run_poller(state.clone(), monitor.clone()));
+    }
+    spawn_watcher(state.clone());
+
     // Start Server
     let app = Router::new()
-        .route("/api/states", get(get_states))
-        .route("/api/airport", axum::routing::post(set_active_airport))
+        .route("/api/airports", get(get_airports))
+        .route("/api/airports/:code/states", get(get_states))
+        .route("/api/airports/:code/events", get(get_events))
+        .route("/api/airports/:code/aircraft/:icao24/track", get(get_track))
+        .route("/api/airports/:code/layout", get(get_airport_layout))
+        .route("/api/airports/:code/route", get(get_taxi_route))
+        .route("/api/airports/:code/runways", get(get_runways).post(set_runways))
+        .route("/api/airports/:code/weather", get(get_weather))
+        .route("/api/airports/:code/lvp", get(get_lvp).post(set_lvp))
+        .route("/api/airports/:code/sequence", get(get_sequence))
+        .route("/api/airports/:code/stacks", get(get_stacks))
+        .route("/api/airports/:code/commands", get(get_commands).post(post_command))
+        .route("/api/airports/:code/emergencies", get(get_emergencies))
+        .route("/api/airports/:code/emergencies/:icao24/workflow/:step", post(complete_workflow_step))
+        .route("/api/stream", get(ws_handler))
+        .route("/api/feed", get(sse_handler))
+        .route("/api/reload", get(get_reload).post(post_reload))
+        .route("/api/movements", get(get_movements))
+        .route("/api/movements/:id", get(get_movement))
+        .route("/api/analytics", get(get_analytics))
+        .route("/api/alerts", get(get_alerts))
+        .route("/api/alerts/:id/acknowledge", post(acknowledge_alert))
+        .route("/api/alerts/inhibitions", post(add_inhibition))
+        .route("/api/alerts/inhibitions/:id", delete(remove_inhibition))
         .layer(CorsLayer::permissive())
         .with_state(state.clone());
 
@@ -184,34 +270,462 @@ async fn main() {
     axum::serve(listener, app).await.unwrap();
 }
 
-async fn get_states(State(state): State<Arc<AppState>>) -> Json<Vec<Aircraft>> {
-    let lock = state.aircraft.lock().unwrap();
-    Json(lock.values().cloned().collect())
+/// Configured airports, each with its own poller.
+async fn get_airports(State(state): State<Arc<AppState>>) -> Json<Vec<MonitoredAirport>> {
+    let live = state.live();
+    Json(live.config.airports.iter().filter_map(|a| {
+        let monitor = state.monitor(&a.code)?;
+        let aircraft = monitor.aircraft.lock().unwrap().len();
+        let last_update = monitor.last_cycle.lock().unwrap().as_ref().map(|c| c.time);
+        Some(MonitoredAirport {
+            code: a.code.clone(),
+            lat: a.lat,
+            lon: a.lon,
+            radius: a.radius,
+            has_ground_data: live.airport_data.get(&a.code).is_some(),
+            aircraft,
+            last_update,
+        })
+    }).collect())
+}
+
+async fn get_states(
+    State(state): State<Arc<AppState>>,
+    Path(code): Path<String>,
+) -> Result<Json<Vec<Aircraft>>, StatusCode> {
+    let monitor = state.monitor(&code).ok_or(StatusCode::NOT_FOUND)?;
+    let lock = monitor.aircraft.lock().unwrap();
+    Ok(Json(lock.values().cloned().collect()))
+}
+
+/// Track history of one aircraft, oldest sample first.
+async fn get_track(
+    State(state): State<Arc<AppState>>,
+    Path((code, icao24)): Path<(String, String)>,
+) -> Result<Json<Vec<TrackSample>>, StatusCode> {
+    let monitor = state.monitor(&code).ok_or(StatusCode::NOT_FOUND)?;
+    let lock = monitor.history.lock().unwrap();
+    lock.get(&icao24.to_ascii_lowercase())
+        .map(|h| Json(h.track.iter().cloned().collect()))
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+async fn get_events(
+    State(state): State<Arc<AppState>>,
+    Path(code): Path<String>,
+) -> Result<Json<Vec<MovementEvent>>, StatusCode> {
+    let monitor = state.monitor(&code).ok_or(StatusCode::NOT_FOUND)?;
+    let lock = monitor.events.lock().unwrap();
+    Ok(Json(lock.iter().cloned().collect()))
+}
+
+async fn get_sequence(
+    State(state): State<Arc<AppState>>,
+    Path(code): Path<String>,
+) -> Result<Json<ArrivalSequence>, StatusCode> {
+    let monitor = state.monitor(&code).ok_or(StatusCode::NOT_FOUND)?;
+    let sequence = monitor.runway_context.lock().unwrap().sequence.clone();
+    Ok(Json(sequence))
+}
+
+/// Holding stacks with the aircraft in each, lowest first.
+async fn get_stacks(
+    State(state): State<Arc<AppState>>,
+    Path(code): Path<String>,
+) -> Result<Json<Vec<Stack>>, StatusCode> {
+    let live = state.live();
+    let airport = live.airport_data.get(&code).ok_or(StatusCode::NOT_FOUND)?;
+    let monitor = state.monitor(&code).ok_or(StatusCode::NOT_FOUND)?;
+    let now = chrono::Utc::now().timestamp();
+    let aircraft = monitor.aircraft.lock().unwrap().clone();
+    let stacks = monitor.runway_context.lock().unwrap().holding.stacks(airport, &aircraft, now);
+    Ok(Json(stacks))
+}
+
+async fn get_airport_layout(
+    State(state): State<Arc<AppState>>,
+    Path(code): Path<String>,
+) -> Result<Json<Airport>, StatusCode> {
+    state.live().airport_data.get(&code)
+        .map(|a| Json(a.clone()))
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+async fn get_taxi_route(
+    State(state): State<Arc<AppState>>,
+    Path(code): Path<String>,
+    Query(query): Query<RouteQuery>,
+) -> Result<Json<TaxiRoute>, StatusCode> {
+    let live = state.live();
+    let airport = live.airport_data.get(&code).ok_or(StatusCode::NOT_FOUND)?;
+    airport.taxi_graph.route_between(&query.from, &query.to)
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
 }
 
-async fn set_active_airport(
+async fn get_runways(
     State(state): State<Arc<AppState>>,
-    Json(payload): Json<SetAirportRequest>,
-) -> Json<String> {
-    println!("Switching active airport to: {}", payload.code);
-    
-    // 1. Set Active Code
+    Path(code): Path<String>,
+) -> Result<Json<RunwayStatus>, StatusCode> {
+    let live = state.live();
+    let airport = live.airport_data.get(&code).ok_or(StatusCode::NOT_FOUND)?;
+    let monitor = state.monitor(&code).ok_or(StatusCode::NOT_FOUND)?;
+
+    let (config, change) = {
+        let mut ctx_lock = monitor.runway_context.lock().unwrap();
+        (ctx_lock.runway_config(airport), ctx_lock.change.clone())
+    };
+
+    Ok(Json(RunwayStatus {
+        config,
+        change,
+        runway_ends: airport.runways.iter().flat_map(|r| r.ends.iter().map(|e| e.name.clone())).collect(),
+    }))
+}
+
+/// Starts a managed runway change.
+async fn set_runways(
+    State(state): State<Arc<AppState>>,
+    Path(code): Path<String>,
+    headers: HeaderMap,
+    Json(payload): Json<RunwayConfig>,
+) -> Result<Json<RunwayStatus>, (StatusCode, String)> {
+    let live = state.live();
+    let controller = authenticate(&live, &headers)?;
+    let airport = live.airport_data.get(&code).ok_or((StatusCode::NOT_FOUND, format!("Unknown airport {}", code)))?;
+    let monitor = state.monitor(&code).ok_or((StatusCode::NOT_FOUND, format!("{} is not monitored", code)))?;
+
+    let (Some(arrival), Some(departure)) = (airport.runway_end(&payload.arrival), airport.runway_end(&payload.departure)) else {
+        return Err((StatusCode::BAD_REQUEST, format!("Unknown runway end in {}/{}", payload.arrival, payload.departure)));
+    };
+    let requested = RunwayConfig { arrival: arrival.name.clone(), departure: departure.name.clone() };
+    println!("{}: {} requested runway change to arrivals {} / departures {}", airport.icao, controller, requested.arrival, requested.departure);
+
     {
-        let mut lock = state.active_airport.lock().unwrap();
-        *lock = Some(payload.code.clone());
+        let mut ctx_lock = monitor.runway_context.lock().unwrap();
+        ctx_lock.request_change(airport, requested, chrono::Utc::now().timestamp());
     }
 
-    // 2. Clear existing state to prevent ghost planes
-    {
-        let mut history_lock = state.history.lock().unwrap();
-        history_lock.clear();
-        
-        let mut ac_lock = state.aircraft.lock().unwrap();
-        ac_lock.clear();
-        
-        let mut ctx_lock = state.runway_context.lock().unwrap();
-        *ctx_lock = RunwayContext::default();
+    get_runways(State(state), Path(code)).await.map_err(|s| (s, String::new()))
+}
+
+async fn get_lvp(
+    State(state): State<Arc<AppState>>,
+    Path(code): Path<String>,
+) -> Result<Json<LvpState>, StatusCode> {
+    state.live().airport_data.get(&code).ok_or(StatusCode::NOT_FOUND)?;
+    let monitor = state.monitor(&code).ok_or(StatusCode::NOT_FOUND)?;
+    let lvp = monitor.runway_context.lock().unwrap().lvp.clone();
+    Ok(Json(lvp))
+}
+
+/// Declares or cancels LVP; `"active": null` returns to the weather trigger.
+async fn set_lvp(
+    State(state): State<Arc<AppState>>,
+    Path(code): Path<String>,
+    headers: HeaderMap,
+    Json(payload): Json<SetLvpRequest>,
+) -> Result<Json<LvpState>, (StatusCode, String)> {
+    let live = state.live();
+    let controller = authenticate(&live, &headers)?;
+    let airport = live.airport_data.get(&code).ok_or((StatusCode::NOT_FOUND, format!("Unknown airport {}", code)))?;
+    let monitor = state.monitor(&code).ok_or((StatusCode::NOT_FOUND, format!("{} is not monitored", code)))?;
+
+    let now = chrono::Utc::now().timestamp();
+    let metar = state.weather.lock().unwrap().get(&airport.icao).and_then(|w| w.metar.clone());
+    let mut ctx_lock = monitor.runway_context.lock().unwrap();
+    println!("{}: {} set LVP override {:?}", airport.icao, controller, payload.active);
+    ctx_lock.lvp.set_manual(&airport.icao, payload.active, now);
+    ctx_lock.lvp.update_from_weather(&airport.icao, metar.as_ref(), now);
+    Ok(Json(ctx_lock.lvp.clone()))
+}
+
+/// Name of the controller whose `Authorization: Bearer` token is on the request.
+fn authenticate(live: &LiveConfig, headers: &HeaderMap) -> Result<String, (StatusCode, String)> {
+    if live.config.controllers.is_empty() {
+        return Err((StatusCode::FORBIDDEN, "No controllers are configured".to_string()));
+    }
+    headers.get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .and_then(|token| live.config.controller(token.trim()))
+        .map(|c| c.name.clone())
+        .ok_or((StatusCode::UNAUTHORIZED, "Missing or unknown controller token".to_string()))
+}
+
+/// Controller inputs in force and the commands that set them.
+async fn get_commands(
+    State(state): State<Arc<AppState>>,
+    Path(code): Path<String>,
+) -> Result<Json<ControllerInputs>, StatusCode> {
+    let monitor = state.monitor(&code).ok_or(StatusCode::NOT_FOUND)?;
+    let control = monitor.runway_context.lock().unwrap().control.clone();
+    Ok(Json(control))
+}
+
+/// Applies a controller command; returns what is now in force for the aircraft.
+async fn post_command(
+    State(state): State<Arc<AppState>>,
+    Path(code): Path<String>,
+    headers: HeaderMap,
+    Json(command): Json<Command>,
+) -> Result<Json<AircraftInputs>, (StatusCode, String)> {
+    let live = state.live();
+    let controller = authenticate(&live, &headers)?;
+    let monitor = state.monitor(&code).ok_or((StatusCode::NOT_FOUND, format!("{} is not monitored", code)))?;
+
+    let now = chrono::Utc::now().timestamp();
+    let mut aircraft = monitor.aircraft.lock().unwrap();
+    if !aircraft.contains_key(&command.icao24().to_ascii_lowercase()) {
+        return Err((StatusCode::NOT_FOUND, format!("Unknown aircraft {}", command.icao24())));
+    }
+    let mut ctx_lock = monitor.runway_context.lock().unwrap();
+    let ctx = &mut *ctx_lock;
+    let summary = format!("{:?}", command);
+    let inputs = ctx.control.apply(&mut aircraft, live.airport_data.get(&monitor.code), &mut ctx.alerts, command, &controller, now)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    println!("{}: {} {}", monitor.code, controller, summary);
+    Ok(Json(inputs))
+}
+
+/// Runs a movement log query on the blocking pool rather than a runtime worker.
+async fn query_store<T, F>(state: &AppState, query: F) -> Result<T, (StatusCode, String)>
+where
+    T: Send + 'static,
+    F: FnOnce(&MovementStore) -> rusqlite::Result<T> + Send + 'static,
+{
+    let store = state.store.clone();
+    tokio::task::spawn_blocking(move || query(&store)).await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+/// Recorded movements matching the filters, most recent first.
+async fn get_movements(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<MovementQuery>,
+) -> Result<Json<Vec<Movement>>, (StatusCode, String)> {
+    query_store(&state, move |store| store.movements(&query)).await.map(Json)
+}
+
+/// A movement with its milestones, phase changes, instructions, advisories and alerts.
+async fn get_movement(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Result<Json<MovementDetail>, (StatusCode, String)> {
+    query_store(&state, move |store| store.movement(id)).await?
+        .map(Json)
+        .ok_or((StatusCode::NOT_FOUND, format!("Unknown movement {}", id)))
+}
+
+/// Taxi-time, holding, queue and arrival delay statistics per hour or day, as JSON or
+/// with `format=csv` as CSV.
+async fn get_analytics(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<AnalyticsQuery>,
+) -> Result<Response, (StatusCode, String)> {
+    let (since, until) = query.range(chrono::Utc::now().timestamp())
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    let format = query.format;
+    let report = query_store(&state, move |store| analytics::report(store, &query, since, until)).await?;
+    match format {
+        Format::Json => Ok(Json(report).into_response()),
+        Format::Csv => {
+            let csv = report.to_csv().map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+            Ok(([(header::CONTENT_TYPE, "text/csv")], csv).into_response())
+        }
+    }
+}
+
+/// Active emergencies and those that ended recently, with start and end times.
+async fn get_emergencies(
+    State(state): State<Arc<AppState>>,
+    Path(code): Path<String>,
+) -> Result<Json<EmergencyTracker>, StatusCode> {
+    let monitor = state.monitor(&code).ok_or(StatusCode::NOT_FOUND)?;
+    let emergencies = monitor.runway_context.lock().unwrap().emergencies.clone();
+    Ok(Json(emergencies))
+}
+
+/// Marks a step of the security workflow for a 7500 squawk as done.
+async fn complete_workflow_step(
+    State(state): State<Arc<AppState>>,
+    Path((code, icao24, step)): Path<(String, String, usize)>,
+    headers: HeaderMap,
+) -> Result<Json<Emergency>, (StatusCode, String)> {
+    let controller = authenticate(&state.live(), &headers)?;
+    let monitor = state.monitor(&code).ok_or((StatusCode::NOT_FOUND, format!("{} is not monitored", code)))?;
+    let now = chrono::Utc::now().timestamp();
+    let emergency = monitor.runway_context.lock().unwrap().emergencies.complete_step(&icao24, step, &controller, now)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    println!("{}: {} completed security step {} for {}", monitor.code, controller, step, icao24);
+    Ok(Json(emergency))
+}
+
+/// Alerts at every monitored airport, or at `?airport=`, with the inhibitions in force.
+async fn get_alerts(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<AlertQuery>,
+) -> Result<Json<AlertList>, StatusCode> {
+    let monitors: Vec<Arc<AirportMonitor>> = match &query.airport {
+        Some(code) => vec![state.monitor(code).ok_or(StatusCode::NOT_FOUND)?],
+        None => state.monitors.read().unwrap().values().cloned().collect(),
+    };
+
+    let mut list = AlertList { alerts: Vec::new(), inhibitions: Vec::new() };
+    for monitor in monitors {
+        let ctx_lock = monitor.runway_context.lock().unwrap();
+        let board = &ctx_lock.alerts;
+        let cleared = board.cleared.iter().filter(|_| query.cleared);
+        for alert in board.active.iter().chain(cleared) {
+            list.alerts.push(AtAirport { airport: monitor.code.clone(), item: alert.clone() });
+        }
+        for inhibition in &board.inhibitions {
+            list.inhibitions.push(AtAirport { airport: monitor.code.clone(), item: inhibition.clone() });
+        }
+    }
+    list.alerts.sort_by(|a, b| {
+        (a.item.is_active(), a.item.severity).cmp(&(b.item.is_active(), b.item.severity)).reverse()
+            .then(a.item.raised_at.cmp(&b.item.raised_at))
+    });
+    Ok(Json(list))
+}
+
+async fn acknowledge_alert(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<u64>,
+    headers: HeaderMap,
+) -> Result<Json<Alert>, (StatusCode, String)> {
+    let controller = authenticate(&state.live(), &headers)?;
+    let now = chrono::Utc::now().timestamp();
+    let monitors: Vec<Arc<AirportMonitor>> = state.monitors.read().unwrap().values().cloned().collect();
+    for monitor in monitors {
+        let mut ctx_lock = monitor.runway_context.lock().unwrap();
+        if ctx_lock.alerts.get(id).is_some() {
+            let alert = ctx_lock.alerts.acknowledge(id, &controller, now).map_err(|e| (StatusCode::CONFLICT, e))?;
+            println!("{}: {} acknowledged alert {}", monitor.code, controller, id);
+            return Ok(Json(alert));
+        }
+    }
+    Err((StatusCode::NOT_FOUND, format!("Unknown alert {}", id)))
+}
+
+/// Suppresses a kind of alert at one airport, for one aircraft or all of them.
+async fn add_inhibition(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<InhibitRequest>,
+) -> Result<Json<Inhibition>, (StatusCode, String)> {
+    let controller = authenticate(&state.live(), &headers)?;
+    let monitor = state.monitor(&request.airport)
+        .ok_or((StatusCode::NOT_FOUND, format!("{} is not monitored", request.airport)))?;
+    let now = chrono::Utc::now().timestamp();
+    let inhibition = Inhibition {
+        id: 0,
+        kind: request.kind,
+        icao24: request.icao24,
+        until: request.duration_s.map(|d| now + d),
+        by: controller,
+        reason: request.reason,
+    };
+    let inhibition = monitor.runway_context.lock().unwrap().alerts.inhibit(inhibition, now)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    println!("{}: {} inhibited {:?} alerts for {}", monitor.code, inhibition.by, inhibition.kind,
+        inhibition.icao24.as_deref().unwrap_or("all aircraft"));
+    Ok(Json(inhibition))
+}
+
+async fn remove_inhibition(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<u64>,
+    headers: HeaderMap,
+) -> Result<Json<Inhibition>, (StatusCode, String)> {
+    let controller = authenticate(&state.live(), &headers)?;
+    let monitors: Vec<Arc<AirportMonitor>> = state.monitors.read().unwrap().values().cloned().collect();
+    for monitor in monitors {
+        if let Some(inhibition) = monitor.runway_context.lock().unwrap().alerts.release(id) {
+            println!("{}: {} lifted inhibition {}", monitor.code, controller, id);
+            return Ok(Json(inhibition));
+        }
+    }
+    Err((StatusCode::NOT_FOUND, format!("Unknown inhibition {}", id)))
+}
+
+async fn get_weather(
+    State(state): State<Arc<AppState>>,
+    Path(code): Path<String>,
+) -> Result<Json<WeatherReport>, StatusCode> {
+    let live = state.live();
+    let airport = live.airport_data.get(&code).ok_or(StatusCode::NOT_FOUND)?;
+    let weather = state.weather.lock().unwrap().get(&airport.icao).cloned().unwrap_or_default();
+    let Json(runways) = get_runways(State(state.clone()), Path(code)).await?;
+
+    let wind = weather.metar.as_ref().and_then(|m| m.conditions.wind.as_ref());
+    let current = runways.config.as_ref().map(|c| c.arrival.as_str());
+
+    Ok(Json(WeatherReport {
+        wind_components: wind.map(|w| all_components(airport, w)).unwrap_or_default(),
+        recommended_runway: recommend_runway(airport, wind, current),
+        fetched_at: weather.metar.is_some().then_some(weather.fetched_at),
+        metar: weather.metar,
+        taf: weather.taf,
+    }))
+}
+
+/// Outcome of the most recent reload, or of startup if there has been none.
+async fn get_reload(State(state): State<Arc<AppState>>) -> Json<Option<ReloadReport>> {
+    Json(state.last_reload.lock().unwrap().clone())
+}
+
+/// Re-reads the config file and sector data and applies them without losing traffic state.
+async fn post_reload(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Json<ReloadReport>, Response> {
+    let controller = authenticate(&state.live(), &headers).map_err(IntoResponse::into_response)?;
+    println!("{} requested a configuration reload", controller);
+    let report = reload_in_background(state, ReloadTrigger::Request).await;
+    match report.error {
+        Some(_) => Err((StatusCode::UNPROCESSABLE_ENTITY, Json(report)).into_response()),
+        None => Ok(Json(report)),
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    const AIRPORT: &str = "[[airports]]\ncode = \"EGSS\"\nlat = 51.885\nlon = 0.235\n";
 
-    Json("OK".to_string())
+    fn live(controllers: &str) -> LiveConfig {
+        LiveConfig {
+            config: Config::parse(&format!("{}{}", AIRPORT, controllers), None).unwrap(),
+            airport_data: AirportData::default(),
+        }
+    }
+
+    fn headers(authorization: Option<&'static str>) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        if let Some(value) = authorization {
+            headers.insert(header::AUTHORIZATION, HeaderValue::from_static(value));
+        }
+        headers
+    }
+
+    #[test]
+    fn commands_need_a_configured_controller_token() {
+        let unconfigured = live("");
+        assert_eq!(
+            authenticate(&unconfigured, &headers(Some("Bearer tower-token-0123456789"))),
+            Err((StatusCode::FORBIDDEN, "No controllers are configured".to_string())),
+        );
+
+        let live = live("[[controllers]]\nname = \"tower\"\ntoken = \"tower-token-0123456789\"\n");
+        let rejected = Err((StatusCode::UNAUTHORIZED, "Missing or unknown controller token".to_string()));
+        for authorization in [None, Some("Bearer ground-token-0123456789"), Some("tower-token-0123456789"), Some("Basic tower-token-0123456789"), Some("Bearer ")] {
+            assert_eq!(authenticate(&live, &headers(authorization)), rejected, "{:?}", authorization);
+        }
+        assert_eq!(authenticate(&live, &headers(Some("Bearer tower-token-0123456789"))), Ok("tower".to_string()));
+    }
 }
@@ -1,4 +1,8 @@
 use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+use crate::logic::conformance::Conformance;
+use crate::logic::routing::TaxiRoute;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Aircraft {
@@ -28,6 +32,11 @@ pub struct Aircraft {
     pub distance: Option<f64>, // Distance to Touchdown (nm)
     pub advisory: Option<String>, // e.g., "SLOW 160", "EXPEDITE"
     pub hold_time: Option<i64>, // Timestamp when entered Holding state
+    pub stand: Option<String>, // Stand the aircraft was first seen on
+    pub taxi_route: Option<TaxiRoute>, // Current taxi clearance
+    pub conformance: Option<Conformance>, // Route conformance while taxiing
+    pub sequence: Option<usize>, // Position in the arrival sequence
+    pub phase_confidence: f64, // 0-1, how settled the phase classification is
 }
 
 impl Default for Aircraft {
@@ -58,6 +67,11 @@ impl Default for Aircraft {
             distance: None,
             advisory: None,
             hold_time: None,
+            stand: None,
+            taxi_route: None,
+            conformance: None,
+            sequence: None,
+            phase_confidence: 0.0,
         }
     }
 }
@@ -73,6 +87,7 @@ pub enum Phase {
     Climb,
     Cruise,
     Descent,
+    Holding,
     Approach,
     Final,
     Landing,
@@ -88,9 +103,127 @@ pub enum WakeCategory {
     Unknown,
 }
 
+/// Recent phase changes kept per aircraft.
+const PHASE_HISTORY_LEN: usize = 8;
+/// Age of the oldest track sample kept per aircraft, whatever the poll interval.
+const TRACK_HISTORY_S: i64 = 20 * 60;
+
+/// One observation in an aircraft's track history.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrackSample {
+    pub time: i64,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub altitude_ft: Option<f64>,
+    pub speed_kt: Option<f64>,
+    pub track: Option<f64>,
+    pub vertical_rate: Option<f64>,
+    pub on_ground: bool,
+    pub phase: Phase,
+}
+
+impl TrackSample {
+    fn from_aircraft(aircraft: &Aircraft, phase: Phase, time: i64) -> Self {
+        TrackSample {
+            time,
+            latitude: aircraft.latitude,
+            longitude: aircraft.longitude,
+            altitude_ft: aircraft.baro_altitude,
+            speed_kt: aircraft.velocity,
+            track: aircraft.true_track,
+            vertical_rate: aircraft.vertical_rate,
+            on_ground: aircraft.on_ground,
+            phase,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AircraftState {
    pub icao24: String,
    pub phase: Phase,
    pub last_update: i64,
+   /// Phases entered, oldest first, with the time each was entered.
+   pub transitions: Vec<(Phase, i64)>,
+   /// Track history of the last `TRACK_HISTORY_S` seconds, oldest first.
+   pub track: VecDeque<TrackSample>,
+   /// A different classification waiting out the hysteresis, and since when.
+   pub pending: Option<(Phase, i64)>,
+}
+
+impl AircraftState {
+    pub fn new(aircraft: &Aircraft, phase: Phase, now: i64) -> Self {
+        let mut state = AircraftState {
+            icao24: aircraft.icao24.clone(),
+            phase,
+            last_update: now,
+            transitions: Vec::new(),
+            track: VecDeque::new(),
+            pending: None,
+        };
+        state.record(aircraft, phase, now);
+        state
+    }
+
+    /// Appends the latest observation and the phase it was classified as.
+    pub fn record(&mut self, aircraft: &Aircraft, phase: Phase, now: i64) {
+        if self.transitions.last().is_none_or(|(last, _)| *last != phase) {
+            self.transitions.push((phase, now));
+            if self.transitions.len() > PHASE_HISTORY_LEN {
+                self.transitions.remove(0);
+            }
+        }
+        self.phase = phase;
+        self.last_update = now;
+
+        if self.track.back().is_some_and(|s| s.time == now) {
+            self.track.pop_back();
+        }
+        self.track.push_back(TrackSample::from_aircraft(aircraft, phase, now));
+        while self.track.front().is_some_and(|s| now - s.time > TRACK_HISTORY_S) {
+            self.track.pop_front();
+        }
+    }
+
+    /// Samples from the last `window_s` seconds before `now`, oldest first.
+    pub fn recent(&self, now: i64, window_s: i64) -> impl Iterator<Item = &TrackSample> {
+        self.track.iter().filter(move |s| now - s.time <= window_s)
+    }
+
+    /// Oldest sample within the window, used as the baseline for trends.
+    fn baseline(&self, now: i64, window_s: i64) -> Option<&TrackSample> {
+        self.recent(now, window_s).find(|s| s.time < now)
+    }
+
+    /// Change in ground speed from the window baseline to `aircraft`, in kt per second.
+    pub fn acceleration(&self, aircraft: &Aircraft, now: i64, window_s: i64) -> Option<f64> {
+        let base = self.baseline(now, window_s)?;
+        Some((aircraft.velocity? - base.speed_kt?) / (now - base.time) as f64)
+    }
+
+    /// Climb (positive) or descent rate in ft/min from the altitude trend over the
+    /// window, which is steadier than the reported vertical rate.
+    pub fn vertical_trend_fpm(&self, aircraft: &Aircraft, now: i64, window_s: i64) -> Option<f64> {
+        let base = self.baseline(now, window_s)?;
+        Some((aircraft.baro_altitude? - base.altitude_ft?) * 60.0 / (now - base.time) as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn track_history_covers_the_same_time_at_any_poll_interval() {
+        let aircraft = Aircraft { icao24: "aaa001".to_string(), ..Aircraft::default() };
+        for interval in [1, 2, 5] {
+            let mut state = AircraftState::new(&aircraft, Phase::Cruise, 0);
+            for t in (interval..=3600).step_by(interval as usize) {
+                state.record(&aircraft, Phase::Cruise, t);
+            }
+            let span = state.track.back().unwrap().time - state.track.front().unwrap().time;
+            assert_eq!(span, TRACK_HISTORY_S, "every {} s", interval);
+            assert_eq!(state.track.len() as i64, TRACK_HISTORY_S / interval + 1);
+        }
+    }
 }
@@ -0,0 +1,251 @@
+//! One `AirportMonitor` per configured airport, each driven by its own poller task.
+//!
+//! Monitors share the settings, sector data, weather and push feeds in `AppState` but
+//! keep their own aircraft, history and runway context, so viewing or changing one
+//! airport never disturbs another. Settings are read afresh every cycle, so a reload
+//! takes effect on the next poll.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time;
+
+use crate::adsblol::AdsbLolClient;
+use crate::config::{AirportConfig, SurveillanceKind};
+use crate::logic::airport::{Airport, RunwayConfig};
+use crate::logic::events::{detect_event, MovementEvent};
+use crate::logic::geofence::haversine_distance;
+use crate::logic::phases::{determine_phase, stabilise_phase};
+use crate::logic::sequencing::{apply_movement_event, process_ground_traffic, RunwayContext};
+use crate::models::{Aircraft, AircraftState, Phase};
+use crate::stream::TrafficCycle;
+use crate::AppState;
+
+/// Movement events kept per airport for `/api/airports/:code/events`.
+const MAX_EVENTS: usize = 200;
+
+/// Live traffic and runway state for one airport.
+pub struct AirportMonitor {
+    pub code: String,
+    pub aircraft: Mutex<HashMap<String, Aircraft>>,
+    pub history: Mutex<HashMap<String, AircraftState>>,
+    pub runway_context: Mutex<RunwayContext>,
+    /// Recent go-arounds, touch-and-gos and rejected take-offs, oldest first.
+    pub events: Mutex<VecDeque<MovementEvent>>,
+    pub last_cycle: Mutex<Option<TrafficCycle>>,
+}
+
+impl AirportMonitor {
+    /// Starts with the configured runways and separation scheme.
+    pub fn new(config: &AirportConfig, ground: Option<&Airport>) -> Self {
+        let context = RunwayContext {
+            config: configured_runways(config, ground),
+            separation: config.separation,
+            ..Default::default()
+        };
+        AirportMonitor {
+            code: config.code.clone(),
+            aircraft: Mutex::new(HashMap::new()),
+            history: Mutex::new(HashMap::new()),
+            runway_context: Mutex::new(context),
+            events: Mutex::new(VecDeque::new()),
+            last_cycle: Mutex::new(None),
+        }
+    }
+}
+
+/// Configured runways as the airport spells them; names were checked when the config loaded.
+pub fn configured_runways(config: &AirportConfig, ground: Option<&Airport>) -> Option<RunwayConfig> {
+    let (runways, ground) = config.runways.as_ref().zip(ground)?;
+    Some(RunwayConfig {
+        arrival: ground.runway_end(&runways.arrival)?.name.clone(),
+        departure: ground.runway_end(&runways.departure)?.name.clone(),
+    })
+}
+
+/// Polls the airport's surveillance source at its poll interval and runs the engine on it.
+/// Stops once a reload removes the airport.
+pub async fn run_poller(state: Arc<AppState>, monitor: Arc<AirportMonitor>) {
+    let mut interval_s = 0;
+    let mut interval = time::interval(Duration::from_secs(1));
+    let mut client: Option<(String, AdsbLolClient)> = None;
+
+    loop {
+        interval.tick().await;
+        let live = state.live();
+        let registered = state.monitor(&monitor.code).is_some_and(|m| Arc::ptr_eq(&m, &monitor));
+        let Some(airport) = live.config.airport(&monitor.code).filter(|_| registered) else {
+            println!("Stopped polling {}", monitor.code);
+            return;
+        };
+
+        if airport.poll_interval_s != interval_s {
+            interval_s = airport.poll_interval_s;
+            let period = Duration::from_secs(interval_s);
+            interval = time::interval_at(time::Instant::now() + period, period);
+        }
+        if client.as_ref().is_none_or(|(url, _)| *url != airport.surveillance.url) {
+            let source = match airport.surveillance.kind {
+                SurveillanceKind::AdsbLol => AdsbLolClient::new(&airport.surveillance.url),
+            };
+            client = Some((airport.surveillance.url.clone(), source));
+        }
+        let Some((_, source)) = &client else {
+            continue;
+        };
+
+        match source.fetch_aircraft(airport.lat, airport.lon, airport.radius).await {
+            Ok(planes) => update(&state, &monitor, planes),
+            Err(e) => eprintln!("Error fetching for {}: {}", monitor.code, e),
+        }
+    }
+}
+
+/// One engine cycle: classify, carry over ground state, prune, run the ground logic and
+/// publish the result.
+fn update(state: &AppState, monitor: &AirportMonitor, planes: Vec<Aircraft>) {
+    let mut ac_lock = monitor.aircraft.lock().unwrap();
+    let mut hist_lock = monitor.history.lock().unwrap();
+
+    // Settings are taken under the aircraft lock: a reload swaps them before it re-plans
+    // taxi routes under the same lock, so no route is planned on a replaced graph
+    let live = state.live();
+    let Some(airport) = live.config.airport(&monitor.code) else {
+        return;
+    };
+
+    let planes_with_context: Vec<Aircraft> = planes.into_iter().filter_map(|mut p| {
+        if p.origin_country == "Unknown" {
+            p.origin_country = airport.code.clone();
+        }
+
+        // Spurious Ground Filter
+        if p.on_ground {
+            if let (Some(lat), Some(lon)) = (p.latitude, p.longitude) {
+                if haversine_distance(lat, lon, airport.lat, airport.lon) > airport.spurious_ground_km {
+                    return None;
+                }
+            }
+        }
+        Some(p)
+    }).collect();
+
+    let now_ts = chrono::Utc::now().timestamp();
+    let ground = live.airport_data.get(&airport.code);
+    let runways = ground.and_then(|g| monitor.runway_context.lock().unwrap().runway_config(g));
+    let arrival_end = ground.zip(runways.as_ref()).and_then(|(g, r)| r.arrival_end(g));
+    let mut events = Vec::new();
+
+    for mut plane in planes_with_context {
+        // Get history
+        let prev = hist_lock.get(&plane.icao24);
+
+        // Determine Phase
+        let mut phase = determine_phase(&plane, prev, ground, now_ts);
+        plane.phase = phase;
+        if let Some(g) = ground {
+            if monitor.runway_context.lock().unwrap().holding.observe(&plane, prev, g, now_ts).is_some() {
+                phase = Phase::Holding;
+                plane.phase = phase;
+            }
+        }
+        // Hysteresis: only stable classifications reach sequencing
+        let decision = stabilise_phase(prev, phase, now_ts);
+        phase = decision.phase;
+        plane.phase_confidence = decision.confidence;
+        // Controller inputs: a phase override wins over the classification, and an
+        // assigned runway replaces the arrival runway for the ETA
+        let (phase_override, assigned_end) = {
+            let ctx = monitor.runway_context.lock().unwrap();
+            let inputs = ctx.control.get(&plane.icao24);
+            (
+                inputs.and_then(|i| i.phase),
+                inputs.and_then(|i| i.runway.as_deref()).and_then(|r| ground?.runway_end(r)),
+            )
+        };
+        if let Some(overridden) = phase_override {
+            phase = overridden;
+            plane.phase_confidence = 1.0;
+        }
+        plane.phase = phase;
+        events.extend(detect_event(prev, &plane, phase, ground, runways.as_ref(), now_ts));
+
+        // Maintain Ground State
+        if let Some(existing) = ac_lock.get(&plane.icao24) {
+            plane.ground_state = existing.ground_state.clone();
+            plane.atc_message = existing.atc_message.clone();
+            plane.hold_time = existing.hold_time;
+            plane.stand = existing.stand.clone();
+            plane.taxi_route = existing.taxi_route.clone();
+            plane.conformance = existing.conformance.clone();
+        }
+
+        // Calculate ETA / DME
+        if phase == Phase::Approach || phase == Phase::Final {
+            if let (Some(lat), Some(lon), Some(spd)) = (plane.latitude, plane.longitude, plane.velocity) {
+                if spd > 10.0 {
+                    // Distance to the landing threshold of the arrival runway, else the airport reference
+                    let dist_nm = match assigned_end.or(arrival_end) {
+                        Some(end) => end.distance_nm(lat, lon),
+                        None => haversine_distance(lat, lon, airport.lat, airport.lon) * 0.539957,
+                    };
+                    plane.distance = Some(dist_nm);
+
+                    let time_hours = dist_nm / spd;
+                    let time_seconds = time_hours * 3600.0;
+                    plane.eta = Some(now_ts + time_seconds as i64);
+                }
+            }
+        }
+
+        // Update History
+        let state_entry = hist_lock.entry(plane.icao24.clone())
+            .or_insert_with(|| AircraftState::new(&plane, phase, now_ts));
+        state_entry.record(&plane, phase, now_ts);
+        state_entry.pending = decision.pending;
+
+        // Update Current View
+        ac_lock.insert(plane.icao24.clone(), plane);
+    }
+
+    // Prune stale aircraft
+    ac_lock.retain(|_, ac| (now_ts - ac.last_contact) < airport.stale_after_s);
+    hist_lock.retain(|icao24, _| ac_lock.contains_key(icao24));
+
+    // Ground Logic
+    if let Some(ground) = ground {
+        let metar = state.weather.lock().unwrap().get(&ground.icao).and_then(|w| w.metar.clone());
+        let mut ctx_lock = monitor.runway_context.lock().unwrap();
+        ctx_lock.lvp.update_from_weather(&ground.icao, metar.as_ref(), now_ts);
+        ctx_lock.holding.retain(&ac_lock);
+        for event in &events {
+            apply_movement_event(&mut ac_lock, &mut ctx_lock, event, now_ts);
+        }
+        process_ground_traffic(&mut ac_lock, ground, &mut ctx_lock, now_ts);
+    }
+    let alerts = monitor.runway_context.lock().unwrap().alerts.drain_changes();
+
+    let cycle = TrafficCycle {
+        airport: airport.code.clone(),
+        time: now_ts,
+        aircraft: Arc::new(ac_lock.values().cloned().collect()),
+        events: events.clone(),
+        alerts,
+    };
+    let previous = monitor.last_cycle.lock().unwrap().replace(cycle.clone());
+    state.feed.lock().unwrap().record_cycle(previous.as_ref(), &cycle);
+    // No subscribers is not an error
+    let _ = state.updates.send(cycle);
+
+    if !events.is_empty() {
+        let mut events_lock = monitor.events.lock().unwrap();
+        for event in events {
+            println!("{}: {:?} {} runway {}", airport.code, event.kind,
+                event.callsign.as_deref().unwrap_or(&event.icao24), event.runway.as_deref().unwrap_or("-"));
+            events_lock.push_back(event);
+        }
+        while events_lock.len() > MAX_EVENTS {
+            events_lock.pop_front();
+        }
+    }
+}
@@ -0,0 +1,362 @@
+//! Runtime reload of the config file and sector data.
+//!
+//! A reload re-reads everything, validates it as at startup and only then swaps the
+//! settings and ground data in one step. Aircraft, history and runway state are kept;
+//! taxi routes planned on a replaced graph are re-planned, because their arc indices
+//! mean nothing in the new one. A watcher on the config file and the airport data
+//! directories triggers the same reload after edits settle.
+
+use notify::{RecursiveMode, Watcher};
+use serde::Serialize;
+use std::fmt::Debug;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+use crate::config::{AirportConfig, Config};
+use crate::logic::airport::{load_airport_data, Airport};
+use crate::monitor::{configured_runways, run_poller, AirportMonitor};
+use crate::{AppState, LiveConfig};
+
+/// Quiet period after the last file event before reloading, so a save that touches
+/// several files reloads once.
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReloadTrigger {
+    Startup,
+    Request,
+    FileChange,
+}
+
+/// What a reload changed. A failed reload leaves everything as it was and only fills `error`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReloadReport {
+    pub time: i64,
+    pub trigger: ReloadTrigger,
+    pub error: Option<String>,
+    /// Global settings applied, e.g. `weather_source: ... -> ...`.
+    pub changes: Vec<String>,
+    /// Settings that only take effect after a restart.
+    pub restart_required: Vec<String>,
+    pub airports_added: Vec<String>,
+    pub airports_removed: Vec<String>,
+    pub airports: Vec<AirportChange>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AirportChange {
+    pub code: String,
+    /// e.g. `stale_after_s: 60 -> 90`.
+    pub settings: Vec<String>,
+    pub ground: Option<GroundChange>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct GroundChange {
+    pub stands_added: Vec<String>,
+    pub stands_removed: Vec<String>,
+    pub holds_added: Vec<String>,
+    pub holds_removed: Vec<String>,
+    pub taxiways: (usize, usize),
+    pub runways: (Vec<String>, Vec<String>),
+    /// Aircraft whose taxi route was re-planned on the new graph.
+    pub routes_replanned: Vec<String>,
+    /// Aircraft whose route could not be re-planned and who were told to hold position.
+    pub routes_withdrawn: Vec<String>,
+}
+
+impl ReloadReport {
+    fn new(trigger: ReloadTrigger) -> Self {
+        ReloadReport {
+            time: chrono::Utc::now().timestamp(),
+            trigger,
+            error: None,
+            changes: Vec::new(),
+            restart_required: Vec::new(),
+            airports_added: Vec::new(),
+            airports_removed: Vec::new(),
+            airports: Vec::new(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty() && self.restart_required.is_empty() && self.airports_added.is_empty()
+            && self.airports_removed.is_empty() && self.airports.is_empty()
+    }
+
+    /// Report for the settings loaded at startup.
+    pub fn startup(config: &Config) -> Self {
+        let mut report = ReloadReport::new(ReloadTrigger::Startup);
+        report.airports_added = config.airports.iter().map(|a| a.code.clone()).collect();
+        report
+    }
+}
+
+/// Runs `reload` on the blocking pool, as reading sector data and building taxi graphs
+/// would otherwise hold up a runtime worker.
+pub async fn reload_in_background(state: Arc<AppState>, trigger: ReloadTrigger) -> ReloadReport {
+    tokio::task::spawn_blocking(move || reload(&state, trigger)).await.unwrap_or_else(|e| {
+        eprintln!("Reload failed: {}", e);
+        ReloadReport { error: Some(format!("Reload did not complete: {}", e)), ..ReloadReport::new(trigger) }
+    })
+}
+
+/// Reloads settings and sector data, keeping the current ones if anything fails to
+/// load or validate. The report is also kept for `GET /api/reload`.
+fn reload(state: &Arc<AppState>, trigger: ReloadTrigger) -> ReloadReport {
+    let report = apply(state, trigger).unwrap_or_else(|e| {
+        eprintln!("Reload failed, keeping current configuration: {}", e);
+        ReloadReport { error: Some(e), ..ReloadReport::new(trigger) }
+    });
+    if report.error.is_none() {
+        if report.is_empty() {
+            println!("Reloaded configuration: no changes");
+        } else {
+            println!(
+                "Reloaded configuration: {} setting(s), {} airport(s) changed, {} added, {} removed",
+                report.changes.len() + report.restart_required.len(),
+                report.airports.len(),
+                report.airports_added.len(),
+                report.airports_removed.len()
+            );
+        }
+    }
+    *state.last_reload.lock().unwrap() = Some(report.clone());
+    report
+}
+
+fn apply(state: &Arc<AppState>, trigger: ReloadTrigger) -> Result<ReloadReport, String> {
+    let old = state.live();
+    let config = old.config.reread().map_err(|e| e.to_string())?;
+    let airport_data = load_airport_data(config.airports.iter().map(|a| (a.code.as_str(), a.data_dir.as_path())));
+    config.check_runways(&airport_data).map_err(|e| e.to_string())?;
+
+    let mut report = ReloadReport::new(trigger);
+    if config.server_port != old.config.server_port {
+        report.restart_required.push(change("server_port", &old.config.server_port, &config.server_port));
+    }
+    if config.database != old.config.database {
+        report.restart_required.push(change("database", &old.config.database, &config.database));
+    }
+    if config.controllers != old.config.controllers {
+        let names = |c: &Config| c.controllers.iter().map(|c| c.name.clone()).collect::<Vec<_>>();
+        report.changes.push(change("controllers", &names(&old.config), &names(&config)));
+    }
+    if config.weather_source != old.config.weather_source {
+        report.changes.push(change("weather_source", &old.config.weather_source, &config.weather_source));
+    }
+    report.airports_removed = old.config.airports.iter()
+        .filter(|a| config.airport(&a.code).is_none())
+        .map(|a| a.code.clone())
+        .collect();
+
+    for airport in &config.airports {
+        let Some(previous) = old.config.airport(&airport.code) else {
+            report.airports_added.push(airport.code.clone());
+            continue;
+        };
+        let ground = ground_change(old.airport_data.get(&airport.code), airport_data.get(&airport.code));
+        let settings = settings_changes(previous, airport);
+        if !settings.is_empty() || ground.is_some() {
+            report.airports.push(AirportChange { code: airport.code.clone(), settings, ground });
+        }
+    }
+
+    // Swap settings and ground data together, then bring the monitors in line
+    let live = Arc::new(LiveConfig { config, airport_data });
+    *state.live.write().unwrap() = live.clone();
+
+    {
+        let mut monitors = state.monitors.write().unwrap();
+        for code in &report.airports_removed {
+            monitors.remove(code);
+            println!("Stopped monitoring {}", code);
+        }
+        for code in &report.airports_added {
+            let Some(airport) = live.config.airport(code) else {
+                continue;
+            };
+            let monitor = Arc::new(AirportMonitor::new(airport, live.airport_data.get(code)));
+            monitors.insert(code.clone(), monitor.clone());
+            tokio::spawn(run_poller(state.clone(), monitor));
+            println!("Started monitoring {}", code);
+        }
+    }
+
+    for change in &mut report.airports {
+        let (Some(monitor), Some(airport)) = (state.monitor(&change.code), live.config.airport(&change.code)) else {
+            continue;
+        };
+        let previous = old.config.airport(&change.code);
+        let ground = live.airport_data.get(&change.code);
+        let mut aircraft = monitor.aircraft.lock().unwrap();
+        let mut context = monitor.runway_context.lock().unwrap();
+        context.separation = airport.separation;
+
+        if let Some(ground) = ground {
+            // A changed runway setting is applied as a managed runway change
+            if previous.is_some_and(|p| p.runways != airport.runways) {
+                if let Some(runways) = configured_runways(airport, Some(ground)) {
+                    context.request_change(ground, runways, report.time);
+                }
+            }
+            // Runways that no longer exist fall back to the airport defaults
+            let missing = |name: &str| ground.runway_end(name).is_none();
+            if context.config.as_ref().is_some_and(|c| missing(&c.arrival) || missing(&c.departure)) {
+                context.config = None;
+                context.change = None;
+            }
+        }
+
+        let Some(ground_change) = change.ground.as_mut() else {
+            continue;
+        };
+        for ac in aircraft.values_mut() {
+            let Some(route) = &ac.taxi_route else {
+                continue;
+            };
+            let name = ac.callsign.clone().unwrap_or_else(|| ac.icao24.clone());
+            let replanned = ground.zip(ac.latitude.zip(ac.longitude))
+                .and_then(|(g, (lat, lon))| g.taxi_graph.replan(route, lat, lon));
+            ac.conformance = None;
+            match replanned {
+                Some(new_route) => {
+                    ac.taxi_route = Some(new_route);
+                    ground_change.routes_replanned.push(name);
+                }
+                None => {
+                    ac.atc_message = Some(format!("Hold Position - route to {} withdrawn", route.destination));
+                    ac.taxi_route = None;
+                    ground_change.routes_withdrawn.push(name);
+                }
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+fn change<T: Debug>(name: &str, from: &T, to: &T) -> String {
+    format!("{}: {:?} -> {:?}", name, from, to)
+}
+
+fn settings_changes(old: &AirportConfig, new: &AirportConfig) -> Vec<String> {
+    let mut changes = Vec::new();
+    let mut compare = |name: &str, from: &dyn Debug, to: &dyn Debug| {
+        let (from, to) = (format!("{:?}", from), format!("{:?}", to));
+        if from != to {
+            changes.push(format!("{}: {} -> {}", name, from, to));
+        }
+    };
+    compare("lat", &old.lat, &new.lat);
+    compare("lon", &old.lon, &new.lon);
+    compare("radius_nm", &old.radius, &new.radius);
+    compare("data_dir", &old.data_dir, &new.data_dir);
+    compare("runways", &old.runways, &new.runways);
+    compare("separation", &old.separation, &new.separation);
+    compare("spurious_ground_km", &old.spurious_ground_km, &new.spurious_ground_km);
+    compare("stale_after_s", &old.stale_after_s, &new.stale_after_s);
+    compare("surveillance", &old.surveillance.name, &new.surveillance.name);
+    compare("surveillance_url", &old.surveillance.url, &new.surveillance.url);
+    compare("poll_interval_s", &old.poll_interval_s, &new.poll_interval_s);
+    changes
+}
+
+/// Differences between two builds of an airport's ground model, or `None` if it is unchanged.
+fn ground_change(old: Option<&Airport>, new: Option<&Airport>) -> Option<GroundChange> {
+    let unchanged = match (old, new) {
+        (None, None) => true,
+        (Some(a), Some(b)) => serde_json::to_value(a).ok() == serde_json::to_value(b).ok() && a.zones == b.zones,
+        _ => false,
+    };
+    if unchanged {
+        return None;
+    }
+
+    let names = |airport: Option<&Airport>, pick: fn(&Airport) -> Vec<String>| airport.map(pick).unwrap_or_default();
+    let stands = |a: &Airport| a.stands.iter().map(|n| n.name.clone()).collect();
+    let holds = |a: &Airport| a.holds.iter().map(|n| n.name.clone()).collect();
+    let runways = |a: &Airport| a.runways.iter().map(|r| r.name.clone()).collect();
+    let difference = |a: &[String], b: &[String]| a.iter().filter(|n| !b.contains(n)).cloned().collect::<Vec<_>>();
+
+    let (old_stands, new_stands) = (names(old, stands), names(new, stands));
+    let (old_holds, new_holds) = (names(old, holds), names(new, holds));
+    Some(GroundChange {
+        stands_added: difference(&new_stands, &old_stands),
+        stands_removed: difference(&old_stands, &new_stands),
+        holds_added: difference(&new_holds, &old_holds),
+        holds_removed: difference(&old_holds, &new_holds),
+        taxiways: (old.map_or(0, |a| a.taxiways.len()), new.map_or(0, |a| a.taxiways.len())),
+        runways: (names(old, runways), names(new, runways)),
+        ..Default::default()
+    })
+}
+
+/// Config file and sector directories to watch, canonicalised so event paths compare.
+fn watched_paths(config: &Config) -> (Option<PathBuf>, Vec<PathBuf>) {
+    let file = config.path.as_ref().and_then(|p| p.canonicalize().ok());
+    let dirs = config.airports.iter().filter_map(|a| a.data_dir.canonicalize().ok()).collect();
+    (file, dirs)
+}
+
+/// Watches the config file and airport data directories and reloads after edits settle.
+/// The watch list follows the config, so new data directories are picked up.
+pub fn spawn_watcher(state: Arc<AppState>) {
+    let (tx, mut rx) = mpsc::unbounded_channel::<Vec<PathBuf>>();
+    let mut watcher = match notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        if let Ok(event) = event {
+            if !event.kind.is_access() {
+                let _ = tx.send(event.paths);
+            }
+        }
+    }) {
+        Ok(w) => w,
+        Err(e) => {
+            eprintln!("File watching unavailable, use POST /api/reload: {}", e);
+            return;
+        }
+    };
+
+    tokio::spawn(async move {
+        let mut watching: Vec<(PathBuf, RecursiveMode)> = Vec::new();
+        let mut targets = watched_paths(&state.live().config);
+
+        loop {
+            // Editors replace files by rename, so watch the config file's directory
+            let wanted: Vec<(PathBuf, RecursiveMode)> = targets.0.iter()
+                .filter_map(|f| f.parent().map(|p| (p.to_path_buf(), RecursiveMode::NonRecursive)))
+                .chain(targets.1.iter().map(|d| (d.clone(), RecursiveMode::Recursive)))
+                .collect();
+            if wanted != watching {
+                for (path, _) in &watching {
+                    let _ = watcher.unwatch(path);
+                }
+                for (path, mode) in &wanted {
+                    if let Err(e) = watcher.watch(path, *mode) {
+                        eprintln!("Cannot watch {}: {}", path.display(), e);
+                    }
+                }
+                watching = wanted;
+            }
+
+            // Wait for a relevant change, then for the burst of events to end
+            let relevant = |paths: &[PathBuf]| paths.iter().any(|p| {
+                targets.0.as_deref() == Some(p.as_path()) || targets.1.iter().any(|d| p.starts_with(d))
+            });
+            loop {
+                match rx.recv().await {
+                    Some(paths) if relevant(&paths) => break,
+                    Some(_) => continue,
+                    None => return,
+                }
+            }
+            while let Ok(Some(_)) = tokio::time::timeout(DEBOUNCE, rx.recv()).await {}
+
+            reload_in_background(state.clone(), ReloadTrigger::FileChange).await;
+            targets = watched_paths(&state.live().config);
+        }
+    });
+}
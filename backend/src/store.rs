@@ -0,0 +1,758 @@
+//! Persistent movement log in SQLite.
+//!
+//! A recorder thread follows the same per-cycle broadcast as the push feeds and writes
+//! every arrival and departure it sees: the movement itself, its milestones (off-block,
+//! holding, take-off roll, touchdown, ...), phase transitions, each instruction and
+//! advisory shown for it, and every alert change. The schema is created and upgraded by
+//! the numbered migrations below, tracked in SQLite's `user_version`.
+
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection, OpenFlags, OptionalExtension, Transaction};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+
+use crate::logic::airport::Airport;
+use crate::logic::alerts::Alert;
+use crate::logic::events::MovementEventKind;
+use crate::models::{Aircraft, Phase};
+use crate::stream::TrafficCycle;
+use crate::AppState;
+
+/// Schema changes in order; entry `n` upgrades a database at version `n` to `n + 1`.
+const MIGRATIONS: &[&str] = &[
+    "CREATE TABLE movements (
+        id INTEGER PRIMARY KEY,
+        airport TEXT NOT NULL,
+        icao24 TEXT NOT NULL,
+        callsign TEXT,
+        kind TEXT NOT NULL,
+        first_seen INTEGER NOT NULL,
+        last_seen INTEGER NOT NULL,
+        stand TEXT,
+        completed_at INTEGER
+    );
+    CREATE TABLE milestones (
+        movement_id INTEGER NOT NULL REFERENCES movements(id),
+        time INTEGER NOT NULL,
+        milestone TEXT NOT NULL,
+        detail TEXT
+    );
+    CREATE TABLE phase_changes (
+        movement_id INTEGER NOT NULL REFERENCES movements(id),
+        time INTEGER NOT NULL,
+        from_phase TEXT,
+        to_phase TEXT NOT NULL,
+        confidence REAL NOT NULL
+    );
+    CREATE TABLE messages (
+        movement_id INTEGER NOT NULL REFERENCES movements(id),
+        time INTEGER NOT NULL,
+        channel TEXT NOT NULL,
+        text TEXT NOT NULL
+    );
+    CREATE TABLE alerts (
+        alert_id INTEGER NOT NULL,
+        airport TEXT NOT NULL,
+        movement_id INTEGER REFERENCES movements(id),
+        time INTEGER NOT NULL,
+        state TEXT NOT NULL,
+        kind TEXT NOT NULL,
+        severity TEXT NOT NULL,
+        aircraft TEXT NOT NULL,
+        message TEXT NOT NULL,
+        acknowledged_by TEXT
+    );",
+    "CREATE INDEX movements_by_airport ON movements(airport, first_seen);
+    CREATE INDEX movements_by_icao24 ON movements(icao24, first_seen);
+    CREATE INDEX milestones_by_movement ON milestones(movement_id, time);
+    CREATE INDEX phase_changes_by_movement ON phase_changes(movement_id, time);
+    CREATE INDEX messages_by_movement ON messages(movement_id, time);
+    CREATE INDEX alerts_by_movement ON alerts(movement_id, time);",
+    "ALTER TABLE movements ADD COLUMN unconstrained_eta INTEGER;
+    ALTER TABLE movements ADD COLUMN taxi_out_m REAL;
+    ALTER TABLE movements ADD COLUMN taxi_in_m REAL;",
+];
+
+/// Rows returned by `GET /api/movements` when no limit is given, and at most.
+const DEFAULT_QUERY_LIMIT: u32 = 100;
+const MAX_QUERY_LIMIT: u32 = 1000;
+/// An arrival slower than this within this distance of a stand is in-block.
+const IN_BLOCK_SPEED_KT: f64 = 2.0;
+const IN_BLOCK_RADIUS_M: f64 = 80.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MovementKind {
+    Arrival,
+    Departure,
+}
+
+impl MovementKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            MovementKind::Arrival => "arrival",
+            MovementKind::Departure => "departure",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Movement {
+    pub id: i64,
+    pub airport: String,
+    pub icao24: String,
+    pub callsign: Option<String>,
+    pub kind: String,
+    pub first_seen: i64,
+    pub last_seen: i64,
+    pub stand: Option<String>,
+    /// Set once the aircraft is no longer tracked.
+    pub completed_at: Option<i64>,
+    /// First ETA computed for an arrival, before any sequencing.
+    pub unconstrained_eta: Option<i64>,
+    /// Length of the first taxi route from stand to holding point.
+    pub taxi_out_m: Option<f64>,
+    /// Length of the shortest route from where the arrival vacated to its stand.
+    pub taxi_in_m: Option<f64>,
+}
+
+/// A movement with its milestones, for the analytics reports.
+#[derive(Debug, Clone)]
+pub struct Timeline {
+    pub movement: Movement,
+    pub milestones: Vec<Milestone>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Milestone {
+    pub time: i64,
+    pub milestone: String,
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PhaseChange {
+    pub time: i64,
+    pub from: Option<String>,
+    pub to: String,
+    pub confidence: f64,
+}
+
+/// An instruction or advisory as shown for the flight.
+#[derive(Debug, Clone, Serialize)]
+pub struct Message {
+    pub time: i64,
+    pub channel: String,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AlertRecord {
+    pub alert_id: i64,
+    pub time: i64,
+    pub state: String,
+    pub kind: String,
+    pub severity: String,
+    pub aircraft: Vec<String>,
+    pub message: String,
+    pub acknowledged_by: Option<String>,
+}
+
+/// One movement with everything recorded for it, in time order.
+#[derive(Debug, Clone, Serialize)]
+pub struct MovementDetail {
+    #[serde(flatten)]
+    pub movement: Movement,
+    pub milestones: Vec<Milestone>,
+    pub phases: Vec<PhaseChange>,
+    pub messages: Vec<Message>,
+    pub alerts: Vec<AlertRecord>,
+}
+
+/// Filters for `GET /api/movements`, e.g. `?airport=EGSS&callsign=EZY&since=1700000000`.
+#[derive(Debug, Default, Deserialize)]
+pub struct MovementQuery {
+    pub airport: Option<String>,
+    pub icao24: Option<String>,
+    /// Prefix match, so `EZY` finds every easyJet flight.
+    pub callsign: Option<String>,
+    pub kind: Option<MovementKind>,
+    /// First seen at or after, Unix seconds.
+    pub since: Option<i64>,
+    /// First seen before, Unix seconds.
+    pub until: Option<i64>,
+    pub limit: Option<u32>,
+}
+
+pub struct MovementStore {
+    /// Written by the recorder.
+    conn: Mutex<Connection>,
+    /// Queries run on their own connection so they never wait for the recorder's
+    /// transactions, nor it for them.
+    reader: Mutex<Connection>,
+}
+
+impl MovementStore {
+    /// Opens or creates the log and brings its schema up to date. Movements left open
+    /// by a previous run are closed at their last sighting.
+    pub fn open(path: &Path) -> rusqlite::Result<Self> {
+        let mut conn = Connection::open(path)?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.pragma_update(None, "foreign_keys", true)?;
+        migrate(&mut conn)?;
+        let interrupted = conn.execute("UPDATE movements SET completed_at = last_seen WHERE completed_at IS NULL", [])?;
+        if interrupted > 0 {
+            println!("Closed {} movement(s) left open by the previous run", interrupted);
+        }
+        let reader = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        Ok(MovementStore { conn: Mutex::new(conn), reader: Mutex::new(reader) })
+    }
+
+    /// Opens an existing log for reading alongside a running server, without migrating
+    /// it or closing its open movements.
+    pub fn open_read_only(path: &Path) -> Result<Self, String> {
+        let open = || Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .map_err(|e| format!("failed to open {}: {}", path.display(), e));
+        let conn = open()?;
+        let version: usize = conn.pragma_query_value(None, "user_version", |r| r.get(0))
+            .map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+        if version < MIGRATIONS.len() {
+            return Err(format!("{} is at schema version {}, start the server once to migrate it to {}",
+                path.display(), version, MIGRATIONS.len()));
+        }
+        Ok(MovementStore { conn: Mutex::new(conn), reader: Mutex::new(open()?) })
+    }
+
+    /// Movements matching the query, most recent first.
+    pub fn movements(&self, query: &MovementQuery) -> rusqlite::Result<Vec<Movement>> {
+        let mut sql = format!("SELECT {} FROM movements WHERE 1 = 1", MOVEMENT_COLUMNS);
+        let mut values: Vec<Value> = Vec::new();
+        let mut filter = |clause: &str, value: Value| {
+            sql.push_str(clause);
+            values.push(value);
+        };
+        if let Some(airport) = &query.airport {
+            filter(" AND airport = ?", Value::Text(airport.to_ascii_uppercase()));
+        }
+        if let Some(icao24) = &query.icao24 {
+            filter(" AND icao24 = ?", Value::Text(icao24.to_ascii_lowercase()));
+        }
+        if let Some(callsign) = &query.callsign {
+            filter(" AND callsign LIKE ? ESCAPE '\\'", Value::Text(format!("{}%", escape_like(&callsign.to_ascii_uppercase()))));
+        }
+        if let Some(kind) = query.kind {
+            filter(" AND kind = ?", Value::Text(kind.as_str().to_string()));
+        }
+        if let Some(since) = query.since {
+            filter(" AND first_seen >= ?", Value::Integer(since));
+        }
+        if let Some(until) = query.until {
+            filter(" AND first_seen < ?", Value::Integer(until));
+        }
+        let limit = query.limit.unwrap_or(DEFAULT_QUERY_LIMIT).min(MAX_QUERY_LIMIT);
+        filter(" ORDER BY first_seen DESC, id DESC LIMIT ?", Value::Integer(limit.into()));
+
+        let conn = self.reader.lock().unwrap();
+        let mut statement = conn.prepare(&sql)?;
+        let rows = statement.query_map(params_from_iter(values.iter()), movement_from_row)?;
+        rows.collect()
+    }
+
+    pub fn movement(&self, id: i64) -> rusqlite::Result<Option<MovementDetail>> {
+        let conn = self.reader.lock().unwrap();
+        let sql = format!("SELECT {} FROM movements WHERE id = ?", MOVEMENT_COLUMNS);
+        let Some(movement) = conn.query_row(&sql, [id], movement_from_row).optional()? else {
+            return Ok(None);
+        };
+
+        let milestones = conn
+            .prepare("SELECT time, milestone, detail FROM milestones WHERE movement_id = ? ORDER BY time, rowid")?
+            .query_map([id], |r| Ok(Milestone { time: r.get(0)?, milestone: r.get(1)?, detail: r.get(2)? }))?
+            .collect::<rusqlite::Result<_>>()?;
+        let phases = conn
+            .prepare("SELECT time, from_phase, to_phase, confidence FROM phase_changes WHERE movement_id = ? ORDER BY time, rowid")?
+            .query_map([id], |r| Ok(PhaseChange { time: r.get(0)?, from: r.get(1)?, to: r.get(2)?, confidence: r.get(3)? }))?
+            .collect::<rusqlite::Result<_>>()?;
+        let messages = conn
+            .prepare("SELECT time, channel, text FROM messages WHERE movement_id = ? ORDER BY time, rowid")?
+            .query_map([id], |r| Ok(Message { time: r.get(0)?, channel: r.get(1)?, text: r.get(2)? }))?
+            .collect::<rusqlite::Result<_>>()?;
+        let alerts = conn
+            .prepare("SELECT alert_id, time, state, kind, severity, aircraft, message, acknowledged_by
+                      FROM alerts WHERE movement_id = ? ORDER BY time, rowid")?
+            .query_map([id], |r| {
+                let aircraft: String = r.get(5)?;
+                Ok(AlertRecord {
+                    alert_id: r.get(0)?,
+                    time: r.get(1)?,
+                    state: r.get(2)?,
+                    kind: r.get(3)?,
+                    severity: r.get(4)?,
+                    aircraft: serde_json::from_str(&aircraft).unwrap_or_default(),
+                    message: r.get(6)?,
+                    acknowledged_by: r.get(7)?,
+                })
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+
+        Ok(Some(MovementDetail { movement, milestones, phases, messages, alerts }))
+    }
+
+    /// Movements under way at any time in `[since, until)`, with their milestones.
+    pub fn timelines(&self, airport: Option<&str>, since: i64, until: i64) -> rusqlite::Result<Vec<Timeline>> {
+        let conn = self.reader.lock().unwrap();
+        let airport = airport.map(str::to_ascii_uppercase);
+        let filter = "first_seen < ?1 AND COALESCE(completed_at, last_seen) >= ?2 AND (?3 IS NULL OR airport = ?3)";
+
+        let sql = format!("SELECT {} FROM movements WHERE {} ORDER BY first_seen, id", MOVEMENT_COLUMNS, filter);
+        let movements: Vec<Movement> = conn.prepare(&sql)?
+            .query_map(params![until, since, airport], movement_from_row)?
+            .collect::<rusqlite::Result<_>>()?;
+
+        let sql = format!(
+            "SELECT movement_id, time, milestone, detail FROM milestones
+             WHERE movement_id IN (SELECT id FROM movements WHERE {}) ORDER BY movement_id, time, rowid",
+            filter
+        );
+        let mut milestones: HashMap<i64, Vec<Milestone>> = HashMap::new();
+        let mut statement = conn.prepare(&sql)?;
+        let mut rows = statement.query(params![until, since, airport])?;
+        while let Some(row) = rows.next()? {
+            milestones.entry(row.get(0)?).or_default()
+                .push(Milestone { time: row.get(1)?, milestone: row.get(2)?, detail: row.get(3)? });
+        }
+
+        Ok(movements.into_iter()
+            .map(|movement| Timeline { milestones: milestones.remove(&movement.id).unwrap_or_default(), movement })
+            .collect())
+    }
+}
+
+const MOVEMENT_COLUMNS: &str =
+    "id, airport, icao24, callsign, kind, first_seen, last_seen, stand, completed_at, unconstrained_eta, taxi_out_m, taxi_in_m";
+
+fn movement_from_row(row: &rusqlite::Row) -> rusqlite::Result<Movement> {
+    Ok(Movement {
+        id: row.get(0)?,
+        airport: row.get(1)?,
+        icao24: row.get(2)?,
+        callsign: row.get(3)?,
+        kind: row.get(4)?,
+        first_seen: row.get(5)?,
+        last_seen: row.get(6)?,
+        stand: row.get(7)?,
+        completed_at: row.get(8)?,
+        unconstrained_eta: row.get(9)?,
+        taxi_out_m: row.get(10)?,
+        taxi_in_m: row.get(11)?,
+    })
+}
+
+fn escape_like(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
+/// Applies the migrations the database has not had yet, each in its own transaction.
+fn migrate(conn: &mut Connection) -> rusqlite::Result<()> {
+    let version: usize = conn.pragma_query_value(None, "user_version", |r| r.get(0))?;
+    if version > MIGRATIONS.len() {
+        eprintln!("Movement log schema version {} is newer than this build ({})", version, MIGRATIONS.len());
+    }
+    for (i, sql) in MIGRATIONS.iter().enumerate().skip(version) {
+        let tx = conn.transaction()?;
+        tx.execute_batch(sql)?;
+        tx.pragma_update(None, "user_version", i + 1)?;
+        tx.commit()?;
+        println!("Movement log migrated to schema version {}", i + 1);
+    }
+    Ok(())
+}
+
+/// What the recorder last wrote for an aircraft it is following.
+struct Tracked {
+    movement_id: i64,
+    kind: MovementKind,
+    on_ground: bool,
+    phase: Phase,
+    ground_state: Option<String>,
+    atc_message: Option<String>,
+    advisory: Option<String>,
+    in_block: bool,
+    /// Where an arrival vacated the runway, for its unimpeded taxi-in route.
+    vacated_at: Option<(f64, f64)>,
+    taxi_out_recorded: bool,
+    eta_recorded: bool,
+}
+
+/// Turns traffic cycles into log rows. Keeps the last view per airport and aircraft to
+/// write only what changed.
+struct Recorder {
+    store: Arc<MovementStore>,
+    tracked: HashMap<String, HashMap<String, Tracked>>,
+}
+
+/// Records every cycle from every airport until the process exits.
+pub fn spawn_recorder(state: Arc<AppState>, store: Arc<MovementStore>) {
+    let mut updates = state.updates.subscribe();
+    let mut recorder = Recorder { store, tracked: HashMap::new() };
+    std::thread::spawn(move || loop {
+        match updates.blocking_recv() {
+            Ok(cycle) => {
+                let live = state.live();
+                if let Err(e) = recorder.record(live.airport_data.get(&cycle.airport), &cycle) {
+                    eprintln!("Movement log: failed to record {} cycle: {}", cycle.airport, e);
+                }
+            }
+            Err(broadcast::error::RecvError::Lagged(n)) => eprintln!("Movement log: skipped {} cycle(s)", n),
+            Err(broadcast::error::RecvError::Closed) => return,
+        }
+    });
+}
+
+impl Recorder {
+    fn record(&mut self, ground: Option<&Airport>, cycle: &TrafficCycle) -> rusqlite::Result<()> {
+        let mut conn = self.store.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let tracked = self.tracked.entry(cycle.airport.clone()).or_default();
+        let time = cycle.time;
+
+        for aircraft in cycle.aircraft.iter() {
+            let entry = match tracked.get_mut(&aircraft.icao24) {
+                Some(entry) => entry,
+                None => {
+                    let Some(entry) = start_movement(&tx, &cycle.airport, aircraft, time)? else {
+                        continue;
+                    };
+                    tracked.entry(aircraft.icao24.clone()).or_insert(entry)
+                }
+            };
+            let id = entry.movement_id;
+
+            tx.execute("UPDATE movements SET last_seen = ?, callsign = COALESCE(?, callsign) WHERE id = ?",
+                params![time, callsign(aircraft), id])?;
+
+            if aircraft.phase != entry.phase {
+                tx.execute("INSERT INTO phase_changes (movement_id, time, from_phase, to_phase, confidence) VALUES (?, ?, ?, ?, ?)",
+                    params![id, time, format!("{:?}", entry.phase), format!("{:?}", aircraft.phase), aircraft.phase_confidence])?;
+                entry.phase = aircraft.phase;
+            }
+
+            if aircraft.on_ground != entry.on_ground {
+                let milestone = if aircraft.on_ground { "touchdown" } else { "airborne" };
+                milestone_at(&tx, id, time, milestone, None)?;
+                entry.on_ground = aircraft.on_ground;
+            }
+
+            if aircraft.ground_state != entry.ground_state {
+                if let Some(ground_state) = &aircraft.ground_state {
+                    let (milestone, detail, at) = match (ground_state.as_str(), entry.kind) {
+                        ("OnStand", _) => ("on_stand", aircraft.stand.clone(), time),
+                        ("Pushback", _) => ("off_block", aircraft.stand.clone(), time),
+                        ("Taxiing", MovementKind::Departure) => ("taxi_out", aircraft.taxi_route.as_ref().map(|r| r.destination.clone()), time),
+                        ("Taxiing", MovementKind::Arrival) => ("runway_vacated", None, time),
+                        ("Holding", _) => ("holding", hold_name(aircraft), aircraft.hold_time.unwrap_or(time)),
+                        ("LiningUp", _) => ("line_up", None, time),
+                        ("Takeoff", _) => ("take_off_roll", None, time),
+                        (other, _) => (other, None, time),
+                    };
+                    milestone_at(&tx, id, at, milestone, detail.as_deref())?;
+                    if milestone == "runway_vacated" {
+                        entry.vacated_at = aircraft.latitude.zip(aircraft.longitude);
+                    }
+                }
+                entry.ground_state = aircraft.ground_state.clone();
+            }
+
+            // Inputs to the delay analytics: the arrival's ETA before sequencing and the
+            // departure's planned taxi distance
+            if entry.kind == MovementKind::Arrival && !entry.eta_recorded && !aircraft.on_ground {
+                if let Some(eta) = aircraft.eta {
+                    tx.execute("UPDATE movements SET unconstrained_eta = ? WHERE id = ?", params![eta, id])?;
+                    entry.eta_recorded = true;
+                }
+            }
+            if entry.kind == MovementKind::Departure && !entry.taxi_out_recorded {
+                if let Some(route) = &aircraft.taxi_route {
+                    tx.execute("UPDATE movements SET taxi_out_m = ? WHERE id = ?", params![route.length_m, id])?;
+                    entry.taxi_out_recorded = true;
+                }
+            }
+
+            // Arrivals are not followed onto stand by the ground logic, so in-block is
+            // taken from the position
+            if entry.kind == MovementKind::Arrival && aircraft.on_ground && !entry.in_block
+                && aircraft.velocity.unwrap_or(0.0) < IN_BLOCK_SPEED_KT {
+                let stand = ground.zip(aircraft.latitude.zip(aircraft.longitude))
+                    .and_then(|(g, (lat, lon))| g.find_nearest_stand(lat, lon))
+                    .filter(|(_, distance)| *distance < IN_BLOCK_RADIUS_M);
+                if let Some((stand, _)) = stand {
+                    let graph = ground.map(|g| &g.taxi_graph);
+                    let taxi_in_m = graph.zip(entry.vacated_at)
+                        .and_then(|(graph, (lat, lon))| graph.route(graph.nearest_node(lat, lon)?, graph.stand_node(&stand.name)?))
+                        .map(|route| route.length_m);
+                    milestone_at(&tx, id, time, "in_block", Some(&stand.name))?;
+                    tx.execute("UPDATE movements SET stand = ?, taxi_in_m = ? WHERE id = ?", params![stand.name, taxi_in_m, id])?;
+                    entry.in_block = true;
+                }
+            }
+
+            for (channel, text, last) in [
+                ("atc_message", &aircraft.atc_message, &mut entry.atc_message),
+                ("advisory", &aircraft.advisory, &mut entry.advisory),
+            ] {
+                if text != last {
+                    if let Some(text) = text {
+                        tx.execute("INSERT INTO messages (movement_id, time, channel, text) VALUES (?, ?, ?, ?)",
+                            params![id, time, channel, text])?;
+                    }
+                    *last = text.clone();
+                }
+            }
+        }
+
+        for event in &cycle.events {
+            if let Some(entry) = tracked.get(&event.icao24) {
+                let milestone = match event.kind {
+                    MovementEventKind::GoAround => "go_around",
+                    MovementEventKind::MissedApproach => "missed_approach",
+                    MovementEventKind::TouchAndGo => "touch_and_go",
+                    MovementEventKind::RejectedTakeOff => "rejected_take_off",
+                };
+                milestone_at(&tx, entry.movement_id, event.at, milestone, event.runway.as_deref())?;
+            }
+        }
+
+        for alert in &cycle.alerts {
+            let movement_id = tracked.get(&alert.aircraft[0]).map(|t| t.movement_id);
+            record_alert(&tx, &cycle.airport, movement_id, alert, time)?;
+        }
+
+        // Aircraft no longer tracked have completed their movement
+        let current: HashMap<&str, ()> = cycle.aircraft.iter().map(|a| (a.icao24.as_str(), ())).collect();
+        let gone: Vec<String> = tracked.keys().filter(|k| !current.contains_key(k.as_str())).cloned().collect();
+        for icao24 in gone {
+            if let Some(entry) = tracked.remove(&icao24) {
+                tx.execute("UPDATE movements SET completed_at = last_seen WHERE id = ?", [entry.movement_id])?;
+            }
+        }
+
+        tx.commit()
+    }
+}
+
+/// Opens a movement for an aircraft seen for the first time: a departure if it is on the
+/// ground, an arrival if it is on approach. Overflights are not recorded, nor is an
+/// aircraft parked on stand until it pushes back, as it may just as well have arrived.
+fn start_movement(tx: &Transaction, airport: &str, aircraft: &Aircraft, time: i64) -> rusqlite::Result<Option<Tracked>> {
+    let kind = if aircraft.on_ground {
+        if aircraft.ground_state.as_deref() == Some("OnStand") {
+            return Ok(None);
+        }
+        MovementKind::Departure
+    } else if matches!(aircraft.phase, Phase::Approach | Phase::Final | Phase::Landing) {
+        MovementKind::Arrival
+    } else {
+        return Ok(None);
+    };
+
+    tx.execute(
+        "INSERT INTO movements (airport, icao24, callsign, kind, first_seen, last_seen, stand) VALUES (?, ?, ?, ?, ?, ?, ?)",
+        params![airport, aircraft.icao24, callsign(aircraft), kind.as_str(), time, time, aircraft.stand],
+    )?;
+    let movement_id = tx.last_insert_rowid();
+    let detail = aircraft.ground_state.clone().unwrap_or_else(|| format!("{:?}", aircraft.phase));
+    milestone_at(tx, movement_id, time, "first_seen", Some(&detail))?;
+    tx.execute("INSERT INTO phase_changes (movement_id, time, from_phase, to_phase, confidence) VALUES (?, ?, NULL, ?, ?)",
+        params![movement_id, time, format!("{:?}", aircraft.phase), aircraft.phase_confidence])?;
+
+    Ok(Some(Tracked {
+        movement_id,
+        kind,
+        on_ground: aircraft.on_ground,
+        phase: aircraft.phase,
+        ground_state: None,
+        atc_message: None,
+        advisory: None,
+        in_block: false,
+        vacated_at: None,
+        taxi_out_recorded: false,
+        eta_recorded: false,
+    }))
+}
+
+fn milestone_at(tx: &Transaction, movement_id: i64, time: i64, milestone: &str, detail: Option<&str>) -> rusqlite::Result<()> {
+    tx.execute("INSERT INTO milestones (movement_id, time, milestone, detail) VALUES (?, ?, ?, ?)",
+        params![movement_id, time, milestone, detail])?;
+    Ok(())
+}
+
+fn record_alert(tx: &Transaction, airport: &str, movement_id: Option<i64>, alert: &Alert, time: i64) -> rusqlite::Result<()> {
+    let state = if !alert.is_active() {
+        "cleared"
+    } else if alert.acknowledged.as_ref().is_some_and(|a| a.at == alert.updated_at) {
+        "acknowledged"
+    } else if alert.raised_at == alert.updated_at {
+        "raised"
+    } else {
+        "updated"
+    };
+    tx.execute(
+        "INSERT INTO alerts (alert_id, airport, movement_id, time, state, kind, severity, aircraft, message, acknowledged_by)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        params![
+            alert.id as i64,
+            airport,
+            movement_id,
+            alert.cleared_at.unwrap_or(time),
+            state,
+            serde_name(&alert.kind),
+            serde_name(&alert.severity),
+            serde_json::to_string(&alert.aircraft).unwrap_or_default(),
+            alert.message,
+            alert.acknowledged.as_ref().map(|a| a.by.clone()),
+        ],
+    )?;
+    Ok(())
+}
+
+/// Name of a unit enum variant as it appears in the API, e.g. `loss_of_separation`.
+fn serde_name<T: Serialize>(value: &T) -> String {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::String(name)) => name,
+        _ => String::new(),
+    }
+}
+
+fn callsign(aircraft: &Aircraft) -> Option<String> {
+    aircraft.callsign.as_ref().map(|c| c.trim().to_string()).filter(|c| !c.is_empty())
+}
+
+/// Holding point from the clearance limit, else from the hold instruction.
+fn hold_name(aircraft: &Aircraft) -> Option<String> {
+    aircraft.taxi_route.as_ref().map(|r| r.destination.clone())
+        .or_else(|| aircraft.atc_message.as_deref().and_then(|m| m.strip_prefix("Hold Short ")).map(str::to_string))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A store on a private in-memory database, shared by its writer and reader.
+    fn store(name: &str) -> Arc<MovementStore> {
+        let uri = format!("file:{}?mode=memory&cache=shared", name);
+        let mut conn = Connection::open(&uri).unwrap();
+        migrate(&mut conn).unwrap();
+        let reader = Connection::open(&uri).unwrap();
+        Arc::new(MovementStore { conn: Mutex::new(conn), reader: Mutex::new(reader) })
+    }
+
+    fn cycle(time: i64, aircraft: Vec<Aircraft>) -> TrafficCycle {
+        TrafficCycle { airport: "EGSS".to_string(), time, aircraft: Arc::new(aircraft), events: Vec::new(), alerts: Vec::new() }
+    }
+
+    fn on_ground(ground_state: &str) -> Aircraft {
+        Aircraft {
+            icao24: "aaa001".to_string(),
+            callsign: Some("EZY12 ".to_string()),
+            on_ground: true,
+            phase: Phase::TaxiOut,
+            ground_state: Some(ground_state.to_string()),
+            stand: Some("10".to_string()),
+            ..Aircraft::default()
+        }
+    }
+
+    fn milestones(store: &MovementStore, id: i64) -> Vec<(i64, String, Option<String>)> {
+        store.movement(id).unwrap().unwrap().milestones.into_iter().map(|m| (m.time, m.milestone, m.detail)).collect()
+    }
+
+    #[test]
+    fn migrations_apply_once_and_bump_the_schema_version() {
+        let version = |conn: &Connection| conn.pragma_query_value(None, "user_version", |r| r.get::<_, usize>(0)).unwrap();
+
+        let mut conn = Connection::open_in_memory().unwrap();
+        migrate(&mut conn).unwrap();
+        assert_eq!(version(&conn), MIGRATIONS.len());
+        // A second run has nothing to do; re-applying would fail on the existing tables
+        migrate(&mut conn).unwrap();
+        assert_eq!(version(&conn), MIGRATIONS.len());
+
+        // An older database gets only the migrations it is missing
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(MIGRATIONS[0]).unwrap();
+        conn.pragma_update(None, "user_version", 1).unwrap();
+        migrate(&mut conn).unwrap();
+        assert_eq!(version(&conn), MIGRATIONS.len());
+        conn.execute("INSERT INTO movements (airport, icao24, kind, first_seen, last_seen, taxi_in_m) VALUES ('EGSS', 'aaa001', 'arrival', 0, 0, 850.0)", []).unwrap();
+    }
+
+    #[test]
+    fn a_departure_records_its_milestones_and_completes_when_it_disappears() {
+        let store = store("store_departure");
+        let mut recorder = Recorder { store: store.clone(), tracked: HashMap::new() };
+        let cycles = [
+            cycle(0, vec![on_ground("OnStand")]),
+            cycle(2, vec![Aircraft { velocity: Some(3.0), ..on_ground("Pushback") }]),
+            cycle(4, vec![Aircraft { velocity: Some(12.0), ..on_ground("Taxiing") }]),
+            cycle(6, vec![Aircraft { velocity: Some(80.0), phase: Phase::TakeOff, ..on_ground("Takeoff") }]),
+        ];
+        for cycle in &cycles {
+            recorder.record(None, cycle).unwrap();
+        }
+
+        let movements = store.movements(&MovementQuery::default()).unwrap();
+        assert_eq!(movements.len(), 1);
+        let departure = &movements[0];
+        assert_eq!((departure.kind.as_str(), departure.callsign.as_deref()), ("departure", Some("EZY12")));
+        // Not opened while it sat on stand, where it may as well have been an arrival
+        assert_eq!((departure.first_seen, departure.last_seen, departure.completed_at), (2, 6, None));
+        let detail = |s: &str| Some(s.to_string());
+        assert_eq!(milestones(&store, departure.id), [
+            (2, "first_seen".to_string(), detail("Pushback")),
+            (2, "off_block".to_string(), detail("10")),
+            (4, "taxi_out".to_string(), None),
+            (6, "take_off_roll".to_string(), None),
+        ]);
+
+        recorder.record(None, &cycle(8, Vec::new())).unwrap();
+        assert_eq!(store.movements(&MovementQuery::default()).unwrap()[0].completed_at, Some(6));
+    }
+
+    #[test]
+    fn arrivals_are_recorded_and_overflights_are_not() {
+        let store = store("store_arrivals");
+        let mut recorder = Recorder { store: store.clone(), tracked: HashMap::new() };
+        let airborne = |icao24: &str, phase| Aircraft { icao24: icao24.to_string(), phase, ..Aircraft::default() };
+        recorder.record(None, &cycle(0, vec![airborne("aaa001", Phase::Final), airborne("aaa002", Phase::Cruise)])).unwrap();
+        recorder.record(None, &cycle(2, vec![Aircraft { on_ground: true, ..airborne("aaa001", Phase::Landing) }])).unwrap();
+        recorder.record(None, &cycle(4, Vec::new())).unwrap();
+
+        let movements = store.movements(&MovementQuery::default()).unwrap();
+        assert_eq!(movements.len(), 1);
+        let arrival = &movements[0];
+        assert_eq!((arrival.icao24.as_str(), arrival.kind.as_str(), arrival.completed_at), ("aaa001", "arrival", Some(2)));
+        assert_eq!(milestones(&store, arrival.id)[1], (2, "touchdown".to_string(), None));
+    }
+
+    #[test]
+    fn the_callsign_filter_is_a_literal_prefix() {
+        let store = store("store_callsigns");
+        {
+            let conn = store.conn.lock().unwrap();
+            for (i, callsign) in ["EZY_1", "EZYA1", "EZ%9", "EZ99", "RYR1"].iter().enumerate() {
+                conn.execute("INSERT INTO movements (airport, icao24, callsign, kind, first_seen, last_seen) VALUES ('EGSS', ?, ?, 'departure', ?, ?)",
+                    params![format!("aaa00{}", i), callsign, i, i]).unwrap();
+            }
+        }
+        let callsigns = |prefix: &str| -> Vec<String> {
+            let query = MovementQuery { callsign: Some(prefix.to_string()), ..MovementQuery::default() };
+            store.movements(&query).unwrap().into_iter().filter_map(|m| m.callsign).collect()
+        };
+        assert_eq!(callsigns("ezy_"), ["EZY_1"]);
+        assert_eq!(callsigns("EZ%"), ["EZ%9"]);
+        assert_eq!(callsigns("EZ"), ["EZ99", "EZ%9", "EZYA1", "EZY_1"]);
+    }
+}
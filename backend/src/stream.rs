@@ -0,0 +1,509 @@
+//! Push feeds for clients that would otherwise poll `/api/airports/:code/states`.
+//!
+//! Every airport poller publishes one `TrafficCycle` per update on a shared broadcast
+//! channel. Each WebSocket client keeps the view it last sent per airport, filtered by
+//! its own subscription, and turns every cycle into a delta against it. The `EventFeed` turns the same cycles
+//! into a log of typed events for the Server-Sent Events endpoint.
+
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Query, State};
+use axum::http::HeaderMap;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::Response;
+use futures_util::stream::{self, Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::convert::Infallible;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+use crate::logic::alerts::Alert;
+use crate::logic::events::{MovementEvent, MovementEventKind};
+use crate::models::{Aircraft, Phase};
+use crate::AppState;
+
+/// Cycles buffered per subscriber before a slow client is resynchronised.
+pub const CYCLE_BUFFER: usize = 16;
+
+/// Everything the engine produced in one poll of an airport.
+#[derive(Debug, Clone)]
+pub struct TrafficCycle {
+    pub airport: String,
+    pub time: i64,
+    pub aircraft: Arc<Vec<Aircraft>>,
+    pub events: Vec<MovementEvent>,
+    /// Alerts raised, updated, acknowledged or cleared since the previous cycle.
+    pub alerts: Vec<Alert>,
+}
+
+/// Subscription filters. Query parameters on connect, e.g.
+/// `?airport=EGSS&phase=Final,Approach&bbox=51.7,0.0,52.1,0.5`, or the same fields as a
+/// JSON text message (`{"airport":"EGSS","phase":"Final","bbox":"..."}`) to change them.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct StreamFilter {
+    pub airport: Option<String>,
+    /// Comma-separated phases.
+    pub phase: Option<String>,
+    /// `min_lat,min_lon,max_lat,max_lon`.
+    pub bbox: Option<String>,
+}
+
+impl StreamFilter {
+    fn phases(&self) -> Option<Vec<Phase>> {
+        let phases = self.phase.as_deref()?;
+        Some(
+            phases.split(',')
+                .filter_map(|p| serde_json::from_value(serde_json::Value::String(p.trim().to_string())).ok())
+                .collect(),
+        )
+    }
+
+    fn bbox(&self) -> Option<[f64; 4]> {
+        let values: Vec<f64> = self.bbox.as_deref()?.split(',').filter_map(|v| v.trim().parse().ok()).collect();
+        values.try_into().ok()
+    }
+
+    fn matches_airport(&self, code: &str) -> bool {
+        self.airport.as_deref().is_none_or(|a| a.eq_ignore_ascii_case(code))
+    }
+
+    fn matches(&self, aircraft: &Aircraft, phases: Option<&[Phase]>, bbox: Option<[f64; 4]>) -> bool {
+        let in_phase = phases.is_none_or(|p| p.contains(&aircraft.phase));
+        let in_box = bbox.is_none_or(|[min_lat, min_lon, max_lat, max_lon]| {
+            matches!((aircraft.latitude, aircraft.longitude), (Some(lat), Some(lon))
+                if (min_lat..=max_lat).contains(&lat) && (min_lon..=max_lon).contains(&lon))
+        });
+        in_phase && in_box
+    }
+}
+
+/// An advisory or controller message that changed in this cycle.
+#[derive(Debug, Clone, Serialize)]
+pub struct AdvisoryUpdate {
+    pub icao24: String,
+    pub callsign: Option<String>,
+    pub advisory: Option<String>,
+    pub atc_message: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum StreamMessage<'a> {
+    Snapshot {
+        airport: Option<&'a str>,
+        time: i64,
+        aircraft: Vec<&'a Aircraft>,
+    },
+    Delta {
+        airport: &'a str,
+        time: i64,
+        added: Vec<&'a Aircraft>,
+        updated: Vec<&'a Aircraft>,
+        removed: Vec<String>,
+        events: Vec<&'a MovementEvent>,
+        advisories: Vec<AdvisoryUpdate>,
+        /// Alerts raised, updated, acknowledged or cleared for aircraft in view.
+        alerts: Vec<&'a Alert>,
+    },
+}
+
+pub async fn ws_handler(
+    ws: WebSocketUpgrade,
+    State(state): State<Arc<AppState>>,
+    Query(filter): Query<StreamFilter>,
+) -> Response {
+    let updates = state.updates.subscribe();
+    ws.on_upgrade(move |socket| run_client(socket, state, filter, updates))
+}
+
+async fn run_client(mut socket: WebSocket, state: Arc<AppState>, mut filter: StreamFilter, mut updates: broadcast::Receiver<TrafficCycle>) {
+    // What this client has been sent per airport, as JSON so changes are detected field by field
+    let mut sent: HashMap<String, HashMap<String, serde_json::Value>> = HashMap::new();
+
+    if send_snapshot(&mut socket, &filter, &current_cycles(&state, &filter), &mut sent).await.is_err() {
+        return;
+    }
+
+    loop {
+        tokio::select! {
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Text(text))) => {
+                    match serde_json::from_str::<StreamFilter>(&text) {
+                        Ok(new_filter) => {
+                            filter = new_filter;
+                            sent.clear();
+                            if send_snapshot(&mut socket, &filter, &current_cycles(&state, &filter), &mut sent).await.is_err() {
+                                return;
+                            }
+                        }
+                        Err(e) => {
+                            let error = serde_json::json!({ "type": "error", "message": format!("Invalid filter: {}", e) });
+                            if socket.send(Message::Text(error.to_string())).await.is_err() {
+                                return;
+                            }
+                        }
+                    }
+                }
+                Some(Ok(Message::Close(_))) | None | Some(Err(_)) => return,
+                Some(Ok(_)) => {}
+            },
+            cycle = updates.recv() => match cycle {
+                Ok(cycle) => {
+                    if !filter.matches_airport(&cycle.airport) {
+                        continue;
+                    }
+                    let Some(text) = delta(&filter, &cycle, sent.entry(cycle.airport.clone()).or_default()) else {
+                        continue;
+                    };
+                    if socket.send(Message::Text(text)).await.is_err() {
+                        return;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => {
+                    // Missed cycles cannot be diffed; start again from a snapshot
+                    sent.clear();
+                    if send_snapshot(&mut socket, &filter, &current_cycles(&state, &filter), &mut sent).await.is_err() {
+                        return;
+                    }
+                }
+                Err(broadcast::error::RecvError::Closed) => return,
+            },
+        }
+    }
+}
+
+/// The most recent cycle of each airport the filter selects; airports that have not
+/// completed a poll yet are left out.
+fn current_cycles(state: &AppState, filter: &StreamFilter) -> Vec<TrafficCycle> {
+    state.monitors.read().unwrap().values()
+        .filter(|m| filter.matches_airport(&m.code))
+        .filter_map(|m| m.last_cycle.lock().unwrap().clone())
+        .collect()
+}
+
+async fn send_snapshot(
+    socket: &mut WebSocket,
+    filter: &StreamFilter,
+    cycles: &[TrafficCycle],
+    sent: &mut HashMap<String, HashMap<String, serde_json::Value>>,
+) -> Result<(), axum::Error> {
+    let (phases, bbox) = (filter.phases(), filter.bbox());
+    let mut aircraft: Vec<&Aircraft> = Vec::new();
+    for cycle in cycles {
+        let view = sent.entry(cycle.airport.clone()).or_default();
+        for a in cycle.aircraft.iter().filter(|a| filter.matches(a, phases.as_deref(), bbox)) {
+            view.insert(a.icao24.clone(), serde_json::to_value(a).unwrap_or_default());
+            aircraft.push(a);
+        }
+    }
+
+    let message = StreamMessage::Snapshot {
+        airport: filter.airport.as_deref(),
+        time: cycles.iter().map(|c| c.time).max().unwrap_or_else(|| chrono::Utc::now().timestamp()),
+        aircraft,
+    };
+    socket.send(Message::Text(serde_json::to_string(&message).unwrap_or_default())).await
+}
+
+/// Diffs a cycle against what the client was last sent. Aircraft leaving the filter
+/// count as removed. Returns nothing when the client has nothing new to see.
+fn delta(filter: &StreamFilter, cycle: &TrafficCycle, sent: &mut HashMap<String, serde_json::Value>) -> Option<String> {
+    let (phases, bbox) = (filter.phases(), filter.bbox());
+    let visible: Vec<&Aircraft> = cycle.aircraft.iter().filter(|a| filter.matches(a, phases.as_deref(), bbox)).collect();
+
+    let mut added = Vec::new();
+    let mut updated = Vec::new();
+    let mut advisories = Vec::new();
+    let mut seen: HashMap<String, serde_json::Value> = HashMap::new();
+
+    for aircraft in visible {
+        let value = serde_json::to_value(aircraft).unwrap_or_default();
+        match sent.get(&aircraft.icao24) {
+            None => added.push(aircraft),
+            Some(old) if *old != value => {
+                if old.get("advisory") != value.get("advisory") || old.get("atc_message") != value.get("atc_message") {
+                    advisories.push(AdvisoryUpdate {
+                        icao24: aircraft.icao24.clone(),
+                        callsign: aircraft.callsign.clone(),
+                        advisory: aircraft.advisory.clone(),
+                        atc_message: aircraft.atc_message.clone(),
+                    });
+                }
+                updated.push(aircraft);
+            }
+            Some(_) => {}
+        }
+        seen.insert(aircraft.icao24.clone(), value);
+    }
+
+    let removed: Vec<String> = sent.keys().filter(|k| !seen.contains_key(*k)).cloned().collect();
+    let events: Vec<&MovementEvent> = cycle.events.iter().filter(|e| seen.contains_key(&e.icao24)).collect();
+    // Aircraft that just left the view still get their alerts cleared
+    let alerts: Vec<&Alert> = cycle.alerts.iter()
+        .filter(|a| a.aircraft.iter().any(|i| seen.contains_key(i) || sent.contains_key(i)))
+        .collect();
+    *sent = seen;
+
+    if added.is_empty() && updated.is_empty() && removed.is_empty() && events.is_empty() && alerts.is_empty() {
+        return None;
+    }
+    let message = StreamMessage::Delta {
+        airport: &cycle.airport,
+        time: cycle.time,
+        added,
+        updated,
+        removed,
+        events,
+        advisories,
+        alerts,
+    };
+    serde_json::to_string(&message).ok()
+}
+
+/// Events kept for clients resuming with `Last-Event-ID`.
+pub const FEED_HISTORY: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FeedEventKind {
+    AtcMessage,
+    Advisory,
+    GroundState,
+    Emergency,
+    GoAround,
+    MissedApproach,
+    TouchAndGo,
+    RejectedTakeOff,
+    Alert,
+}
+
+impl FeedEventKind {
+    fn name(self) -> &'static str {
+        match self {
+            FeedEventKind::AtcMessage => "atc_message",
+            FeedEventKind::Advisory => "advisory",
+            FeedEventKind::GroundState => "ground_state",
+            FeedEventKind::Emergency => "emergency",
+            FeedEventKind::GoAround => "go_around",
+            FeedEventKind::MissedApproach => "missed_approach",
+            FeedEventKind::TouchAndGo => "touch_and_go",
+            FeedEventKind::RejectedTakeOff => "rejected_take_off",
+            FeedEventKind::Alert => "alert",
+        }
+    }
+}
+
+impl From<MovementEventKind> for FeedEventKind {
+    fn from(kind: MovementEventKind) -> Self {
+        match kind {
+            MovementEventKind::GoAround => FeedEventKind::GoAround,
+            MovementEventKind::MissedApproach => FeedEventKind::MissedApproach,
+            MovementEventKind::TouchAndGo => FeedEventKind::TouchAndGo,
+            MovementEventKind::RejectedTakeOff => FeedEventKind::RejectedTakeOff,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FeedEvent {
+    pub id: u64,
+    pub time: i64,
+    pub airport: String,
+    pub kind: FeedEventKind,
+    pub icao24: String,
+    pub callsign: Option<String>,
+    pub message: Option<String>,
+    /// The alert as it now stands, for `alert` events.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alert: Option<Alert>,
+}
+
+/// Numbered log of things the engine changed. Ids increase by one per event and
+/// restart with the process, so a client resuming from another run is sent a reset.
+pub struct EventFeed {
+    next_id: u64,
+    history: VecDeque<FeedEvent>,
+    sender: broadcast::Sender<FeedEvent>,
+}
+
+impl EventFeed {
+    pub fn new() -> Self {
+        EventFeed {
+            next_id: 1,
+            history: VecDeque::new(),
+            sender: broadcast::channel(FEED_HISTORY).0,
+        }
+    }
+
+    fn publish(&mut self, cycle: &TrafficCycle, kind: FeedEventKind, aircraft: &Aircraft, message: Option<String>) {
+        self.push(FeedEvent {
+            id: 0,
+            time: cycle.time,
+            airport: cycle.airport.clone(),
+            kind,
+            icao24: aircraft.icao24.clone(),
+            callsign: aircraft.callsign.clone(),
+            message,
+            alert: None,
+        });
+    }
+
+    fn push(&mut self, mut event: FeedEvent) {
+        event.id = self.next_id;
+        self.next_id += 1;
+        self.history.push_back(event.clone());
+        while self.history.len() > FEED_HISTORY {
+            self.history.pop_front();
+        }
+        // No subscribers is not an error
+        let _ = self.sender.send(event);
+    }
+
+    /// Emits events for what changed since the previous cycle: new controller messages,
+    /// advisories and ground states, emergency squawks, the cycle's movement events and
+    /// alert changes.
+    pub fn record_cycle(&mut self, previous: Option<&TrafficCycle>, cycle: &TrafficCycle) {
+        let previous: HashMap<&str, &Aircraft> = previous
+            .filter(|p| p.airport == cycle.airport)
+            .map(|p| p.aircraft.iter().map(|a| (a.icao24.as_str(), a)).collect())
+            .unwrap_or_default();
+        let is_emergency = |squawk: Option<&str>| matches!(squawk, Some("7500" | "7600" | "7700"));
+
+        for aircraft in cycle.aircraft.iter() {
+            let old = previous.get(aircraft.icao24.as_str());
+            let changed = |field: fn(&Aircraft) -> &Option<String>| {
+                field(aircraft).is_some() && old.is_none_or(|o| field(o) != field(aircraft))
+            };
+
+            if is_emergency(aircraft.squawk.as_deref()) && old.is_none_or(|o| o.squawk != aircraft.squawk) {
+                let message = format!("Squawk {}", aircraft.squawk.as_deref().unwrap_or_default());
+                self.publish(cycle, FeedEventKind::Emergency, aircraft, Some(message));
+            }
+            if changed(|a| &a.ground_state) {
+                self.publish(cycle, FeedEventKind::GroundState, aircraft, aircraft.ground_state.clone());
+            }
+            if changed(|a| &a.atc_message) {
+                self.publish(cycle, FeedEventKind::AtcMessage, aircraft, aircraft.atc_message.clone());
+            }
+            if changed(|a| &a.advisory) {
+                self.publish(cycle, FeedEventKind::Advisory, aircraft, aircraft.advisory.clone());
+            }
+        }
+
+        for event in &cycle.events {
+            if let Some(aircraft) = cycle.aircraft.iter().find(|a| a.icao24 == event.icao24) {
+                let message = event.runway.as_ref().map(|r| format!("Runway {}", r));
+                self.publish(cycle, event.kind.into(), aircraft, message);
+            }
+        }
+
+        for alert in &cycle.alerts {
+            self.push(FeedEvent {
+                id: 0,
+                time: cycle.time,
+                airport: cycle.airport.clone(),
+                kind: FeedEventKind::Alert,
+                icao24: alert.aircraft[0].clone(),
+                callsign: alert.callsign.clone(),
+                message: Some(alert.message.clone()),
+                alert: Some(alert.clone()),
+            });
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct FeedQuery {
+    pub airport: Option<String>,
+    /// Alternative to the `Last-Event-ID` header for clients that cannot set headers.
+    pub last_event_id: Option<u64>,
+}
+
+/// What the SSE feed sends: an event, or a `reset` telling the client that it missed
+/// events and should reload state from `/api/airports/:code/states`.
+enum FeedItem {
+    Event(Box<FeedEvent>),
+    Reset { id: Option<u64>, reason: String },
+}
+
+impl FeedItem {
+    fn matches_airport(&self, airport: Option<&str>) -> bool {
+        match self {
+            FeedItem::Event(e) => airport.is_none_or(|a| a.eq_ignore_ascii_case(&e.airport)),
+            FeedItem::Reset { .. } => true,
+        }
+    }
+
+    fn into_sse(self) -> Event {
+        match self {
+            FeedItem::Event(e) => Event::default()
+                .id(e.id.to_string())
+                .event(e.kind.name())
+                .data(serde_json::to_string(&e).unwrap_or_default()),
+            FeedItem::Reset { id, reason } => {
+                let event = Event::default().event("reset").data(serde_json::json!({ "reason": reason }).to_string());
+                match id {
+                    Some(id) => event.id(id.to_string()),
+                    None => event,
+                }
+            }
+        }
+    }
+}
+
+/// Server-Sent Events feed. Events after the `Last-Event-ID` still in the history are
+/// replayed first, then live events follow. A `Last-Event-ID` from another run of the
+/// server, or older than the history, gets a `reset` instead of a replay, as does a
+/// client that falls too far behind.
+pub async fn sse_handler(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<FeedQuery>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let last_id = headers.get("last-event-id")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .or(query.last_event_id)
+        .unwrap_or(0);
+
+    // Subscribe and read the backlog under one lock so nothing falls in between
+    let (backlog, resume_after, updates) = {
+        let feed = state.feed.lock().unwrap();
+        let latest = feed.next_id - 1;
+        let reason = if last_id > latest {
+            Some("Last-Event-ID is from an earlier run of the server")
+        } else if last_id > 0 && feed.history.front().is_some_and(|e| e.id > last_id + 1) {
+            Some("Last-Event-ID is older than the event history")
+        } else {
+            None
+        };
+        let backlog: Vec<FeedItem> = match reason {
+            Some(reason) => vec![FeedItem::Reset { id: Some(latest), reason: reason.to_string() }],
+            None => feed.history.iter().filter(|e| e.id > last_id).map(|e| FeedItem::Event(Box::new(e.clone()))).collect(),
+        };
+        // Everything up to the latest event is in the backlog or covered by the reset
+        (backlog, latest, feed.sender.subscribe())
+    };
+
+    let live = stream::unfold(updates, move |mut updates| async move {
+        loop {
+            match updates.recv().await {
+                Ok(event) if event.id > resume_after => return Some((FeedItem::Event(Box::new(event)), updates)),
+                Ok(_) => continue,
+                Err(broadcast::error::RecvError::Lagged(n)) => {
+                    let reason = format!("Missed {} event(s) while falling behind", n);
+                    return Some((FeedItem::Reset { id: None, reason }, updates));
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    });
+
+    let airport = query.airport;
+    let events = stream::iter(backlog)
+        .chain(live)
+        .filter(move |item| std::future::ready(item.matches_airport(airport.as_deref())))
+        .map(|item| Ok(item.into_sse()));
+
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
@@ -0,0 +1,349 @@
+use chrono::{DateTime, Datelike, Duration, Months, NaiveDate, TimeZone, Utc};
+
+use super::{CloudLayer, Conditions, Wind};
+
+const WEATHER_CODES: [&str; 31] = [
+    "MI", "BC", "PR", "DR", "BL", "SH", "TS", "FZ", "DZ", "RA", "SN", "SG", "IC", "PL", "GR", "GS", "UP", "BR", "FG", "FU",
+    "VA", "DU", "SA", "HZ", "PY", "PO", "SQ", "FC", "SS", "DS", "NSW",
+];
+
+/// Reads the condition groups shared by METARs and TAF periods, returning how many
+/// tokens were consumed. Stops at the first token that is not a condition group.
+pub fn parse_conditions(tokens: &[&str], conditions: &mut Conditions) -> usize {
+    let mut i = 0;
+    while i < tokens.len() {
+        let token = tokens[i];
+
+        if let Some(wind) = parse_wind(token) {
+            conditions.wind = Some(wind);
+        } else if let Some((from, to)) = parse_variable_direction(token) {
+            if let Some(wind) = conditions.wind.as_mut() {
+                wind.variable_from = Some(from);
+                wind.variable_to = Some(to);
+            }
+        } else if token == "CAVOK" {
+            conditions.cavok = true;
+            conditions.visibility_m = Some(10_000);
+        } else if let Some(metres) = parse_visibility(token) {
+            conditions.visibility_m = Some(metres);
+        } else if let Some(miles) = parse_statute_miles(tokens, i) {
+            // "1 1/2SM" spans two tokens
+            if !token.ends_with("SM") {
+                i += 1;
+            }
+            conditions.visibility_m = Some((miles * 1609.344).round() as u32);
+        } else if is_directional_visibility(token) {
+            // Minimum visibility with a direction, e.g. 4000NE; the prevailing value is kept
+        } else if let Some(layer) = parse_cloud(token) {
+            conditions.clouds.push(layer);
+        } else if matches!(token, "NSC" | "NCD" | "SKC" | "CLR") {
+            conditions.clouds.clear();
+        } else if is_weather(token) {
+            conditions.weather.push(token.to_string());
+        } else {
+            break;
+        }
+        i += 1;
+    }
+    i
+}
+
+/// `24012KT`, `24012G25KT`, `VRB03KT`, `05005MPS`.
+pub fn parse_wind(token: &str) -> Option<Wind> {
+    let (body, factor) = if let Some(b) = token.strip_suffix("KT") {
+        (b, 1.0)
+    } else if let Some(b) = token.strip_suffix("MPS") {
+        (b, 1.943_84)
+    } else if let Some(b) = token.strip_suffix("KMH") {
+        (b, 0.539_957)
+    } else {
+        return None;
+    };
+    if body.len() < 5 || !body.is_ascii() {
+        return None;
+    }
+
+    let (direction, rest) = body.split_at(3);
+    let direction = match direction {
+        "VRB" => None,
+        d => {
+            let d: u16 = d.parse().ok()?;
+            if d > 360 {
+                return None;
+            }
+            Some(d)
+        }
+    };
+    let (speed, gust) = match rest.split_once('G') {
+        Some((s, g)) => (s, Some(g)),
+        None => (rest, None),
+    };
+    let to_knots = |v: &str| v.parse::<f64>().ok().map(|v| (v * factor).round() as u32);
+
+    Some(Wind {
+        direction,
+        speed_kt: to_knots(speed)?,
+        gust_kt: match gust {
+            Some(g) => Some(to_knots(g)?),
+            None => None,
+        },
+        variable_from: None,
+        variable_to: None,
+    })
+}
+
+/// `200V270`.
+fn parse_variable_direction(token: &str) -> Option<(u16, u16)> {
+    let (from, to) = token.split_once('V')?;
+    if from.len() != 3 || to.len() != 3 {
+        return None;
+    }
+    Some((from.parse().ok()?, to.parse().ok()?))
+}
+
+/// Four-digit metres, with `9999` meaning 10 km or more and an optional `NDV` suffix.
+fn parse_visibility(token: &str) -> Option<u32> {
+    let token = token.strip_suffix("NDV").unwrap_or(token);
+    if token.len() != 4 || !token.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    match token.parse().ok()? {
+        9999 => Some(10_000),
+        v => Some(v),
+    }
+}
+
+/// `10SM`, `P6SM`, `M1/4SM`, `1/2SM`, or a whole number followed by a fraction (`1 1/2SM`).
+fn parse_statute_miles(tokens: &[&str], i: usize) -> Option<f64> {
+    let fraction = |s: &str| -> Option<f64> {
+        let s = s.trim_start_matches(['P', 'M']);
+        match s.split_once('/') {
+            Some((n, d)) => Some(n.parse::<f64>().ok()? / d.parse::<f64>().ok()?),
+            None => s.parse().ok(),
+        }
+    };
+
+    let token = tokens[i];
+    if let Some(value) = token.strip_suffix("SM") {
+        return fraction(value);
+    }
+    let next = tokens.get(i + 1)?.strip_suffix("SM")?;
+    if token.len() == 1 && next.contains('/') {
+        return Some(token.parse::<f64>().ok()? + fraction(next)?);
+    }
+    None
+}
+
+fn is_directional_visibility(token: &str) -> bool {
+    token.len() > 4
+        && token.is_ascii()
+        && token[..4].chars().all(|c| c.is_ascii_digit())
+        && token[4..].chars().all(|c| matches!(c, 'N' | 'S' | 'E' | 'W'))
+}
+
+/// `BKN012`, `OVC004CB`, `FEW///TCU`, `VV002`.
+fn parse_cloud(token: &str) -> Option<CloudLayer> {
+    let (cover, rest) = ["FEW", "SCT", "BKN", "OVC", "VV"]
+        .iter()
+        .find_map(|c| token.strip_prefix(c).map(|rest| (*c, rest)))?;
+    if rest.len() < 3 || !rest.is_ascii() {
+        return None;
+    }
+    let (height, kind) = rest.split_at(3);
+    if !height.chars().all(|c| c.is_ascii_digit() || c == '/') || !matches!(kind, "" | "CB" | "TCU" | "///") {
+        return None;
+    }
+
+    Some(CloudLayer {
+        cover: cover.to_string(),
+        base_ft: height.parse::<u32>().ok().map(|h| h * 100),
+        convective: match kind {
+            "CB" | "TCU" => Some(kind.to_string()),
+            _ => None,
+        },
+    })
+}
+
+/// Present weather such as `-RA`, `+TSRA`, `VCSH`, `FZFG` or `BCFG`.
+fn is_weather(token: &str) -> bool {
+    let body = token.trim_start_matches(['+', '-']);
+    let body = body.strip_prefix("VC").unwrap_or(body);
+    if body == "NSW" {
+        return true;
+    }
+    !body.is_empty()
+        && body.len().is_multiple_of(2)
+        && body.as_bytes().chunks(2).all(|c| WEATHER_CODES.contains(&std::str::from_utf8(c).unwrap_or("")))
+}
+
+/// Temperature and dew point, `12/08` or `M02/M05`.
+pub fn parse_temperature(token: &str) -> Option<(i32, Option<i32>)> {
+    let (t, d) = token.split_once('/')?;
+    let value = |s: &str| -> Option<i32> {
+        match s.strip_prefix('M') {
+            Some(v) if v.len() == 2 => v.parse::<i32>().ok().map(|v| -v),
+            None if s.len() == 2 => s.parse().ok(),
+            _ => None,
+        }
+    };
+    Some((value(t)?, value(d)))
+}
+
+/// `Q1013` in hectopascals or `A2992` in hundredths of an inch of mercury.
+pub fn parse_pressure(token: &str) -> Option<u32> {
+    if let Some(q) = token.strip_prefix('Q') {
+        return q.parse().ok().filter(|_| q.len() == 4);
+    }
+    let a = token.strip_prefix('A').filter(|a| a.len() == 4)?;
+    let inches = a.parse::<f64>().ok()? / 100.0;
+    Some((inches * 33.863_9).round() as u32)
+}
+
+/// Resolves a day-of-month/hour/minute group to the matching time nearest to `now`,
+/// since reports only carry the day. Hour 24 is accepted as midnight at the end of the day.
+pub fn resolve_time(day: u32, hour: u32, minute: u32, now: DateTime<Utc>) -> Option<i64> {
+    if !(1..=31).contains(&day) || hour > 24 || minute > 59 {
+        return None;
+    }
+
+    let this_month = NaiveDate::from_ymd_opt(now.year(), now.month(), 1)?;
+    [this_month.checked_sub_months(Months::new(1)), Some(this_month), this_month.checked_add_months(Months::new(1))]
+        .into_iter()
+        .flatten()
+        .filter_map(|month| NaiveDate::from_ymd_opt(month.year(), month.month(), day))
+        .filter_map(|date| {
+            let midnight = Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0)?);
+            Some(midnight + Duration::hours(hour as i64) + Duration::minutes(minute as i64))
+        })
+        .min_by_key(|t| (*t - now).num_seconds().abs())
+        .map(|t| t.timestamp())
+}
+
+/// `181150Z`.
+pub fn parse_issue_time(token: &str, now: DateTime<Utc>) -> Option<i64> {
+    let digits = token.strip_suffix('Z')?;
+    if digits.len() != 6 || !digits.is_ascii() {
+        return None;
+    }
+    resolve_time(digits[0..2].parse().ok()?, digits[2..4].parse().ok()?, digits[4..6].parse().ok()?, now)
+}
+
+/// `1812/1918` validity or change period.
+pub fn parse_period(token: &str, now: DateTime<Utc>) -> Option<(i64, i64)> {
+    let (from, to) = token.split_once('/')?;
+    if from.len() != 4 || to.len() != 4 || !token.is_ascii() {
+        return None;
+    }
+    let time = |s: &str| resolve_time(s[0..2].parse().ok()?, s[2..4].parse().ok()?, 0, now);
+    let (from, mut to) = (time(from)?, time(to)?);
+    if to < from {
+        // Nearest-match picked the wrong month for the end of a period spanning a month end
+        to = time_in_following_month(to);
+    }
+    Some((from, to))
+}
+
+fn time_in_following_month(t: i64) -> i64 {
+    DateTime::from_timestamp(t, 0)
+        .and_then(|t| t.checked_add_months(Months::new(1)))
+        .map(|t| t.timestamp())
+        .unwrap_or(t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 10, 18, 12, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn wind_groups() {
+        let cases = [
+            ("24012KT", Some((Some(240), 12, None))),
+            ("24012G25KT", Some((Some(240), 12, Some(25)))),
+            ("VRB03KT", Some((None, 3, None))),
+            ("05005MPS", Some((Some(50), 10, None))),
+            ("360100KT", Some((Some(360), 100, None))),
+            ("37010KT", None),
+            ("2401KT", None),
+            ("2é012KT", None),
+            ("24012", None),
+        ];
+        for (token, expected) in cases {
+            let wind = parse_wind(token).map(|w| (w.direction, w.speed_kt, w.gust_kt));
+            assert_eq!(wind, expected, "{}", token);
+        }
+    }
+
+    #[test]
+    fn cloud_groups() {
+        let cases = [
+            ("BKN012", Some(("BKN", Some(1200), None))),
+            ("OVC004CB", Some(("OVC", Some(400), Some("CB")))),
+            ("FEW///TCU", Some(("FEW", None, Some("TCU")))),
+            ("VV002", Some(("VV", Some(200), None))),
+            ("VV///", Some(("VV", None, None))),
+            ("SCT01", None),
+            ("BKN012XX", None),
+            ("BKNé12", None),
+        ];
+        for (token, expected) in cases {
+            let layer = parse_cloud(token);
+            let layer = layer.as_ref().map(|l| (l.cover.as_str(), l.base_ft, l.convective.as_deref()));
+            assert_eq!(layer, expected, "{}", token);
+        }
+    }
+
+    #[test]
+    fn visibility_groups() {
+        let cases: [(&[&str], usize, Option<u32>, bool); 8] = [
+            (&["9999"], 1, Some(10_000), false),
+            (&["0350"], 1, Some(350), false),
+            (&["CAVOK"], 1, Some(10_000), true),
+            (&["10SM"], 1, Some(16_093), false),
+            (&["1", "1/2SM"], 2, Some(2414), false),
+            (&["4000NE"], 1, None, false),
+            (&["é000NE"], 0, None, false),
+            (&["99999"], 0, None, false),
+        ];
+        for (tokens, consumed, visibility, cavok) in cases {
+            let mut conditions = Conditions::default();
+            assert_eq!(parse_conditions(tokens, &mut conditions), consumed, "{:?}", tokens);
+            assert_eq!(conditions.visibility_m, visibility, "{:?}", tokens);
+            assert_eq!(conditions.cavok, cavok, "{:?}", tokens);
+        }
+    }
+
+    #[test]
+    fn issue_times_and_periods() {
+        let noon = now().timestamp();
+        assert_eq!(parse_issue_time("181150Z", now()), Some(noon - 600));
+        assert_eq!(parse_issue_time("171150Z", now()), Some(noon - 86400 - 600));
+        for token in ["181150", "18115Z", "1é150Z", "321150Z", "181160Z"] {
+            assert_eq!(parse_issue_time(token, now()), None, "{}", token);
+        }
+
+        assert_eq!(parse_period("1812/1918", now()), Some((noon, noon + 30 * 3600)));
+        assert_eq!(parse_period("1812/1924", now()), Some((noon, noon + 36 * 3600)));
+        for token in ["1812-1918", "181/1918", "1é2/1918", "1812/19é"] {
+            assert_eq!(parse_period(token, now()), None, "{}", token);
+        }
+    }
+
+    #[test]
+    fn weather_temperature_and_pressure() {
+        for (token, weather) in [("-RA", true), ("+TSRA", true), ("VCSH", true), ("FZFG", true), ("NSW", true), ("XX", false), ("-é", false)] {
+            assert_eq!(is_weather(token), weather, "{}", token);
+        }
+        assert_eq!(parse_temperature("12/08"), Some((12, Some(8))));
+        assert_eq!(parse_temperature("M02/M05"), Some((-2, Some(-5))));
+        assert_eq!(parse_temperature("12/"), Some((12, None)));
+        assert_eq!(parse_temperature("1é/08"), None);
+        assert_eq!(parse_pressure("Q1013"), Some(1013));
+        assert_eq!(parse_pressure("A2992"), Some(1013));
+        assert_eq!(parse_pressure("Q101"), None);
+        assert_eq!(parse_pressure("Qé013"), None);
+    }
+}
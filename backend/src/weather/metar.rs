@@ -0,0 +1,146 @@
+use chrono::{DateTime, Utc};
+
+use super::groups::{parse_conditions, parse_issue_time, parse_pressure, parse_temperature};
+use super::{Conditions, Metar, RunwayVisualRange};
+
+/// Parses a single METAR or SPECI, with or without the leading report type.
+///
+/// `METAR EGSS 181150Z 24012G22KT 200V270 9999 -RA FEW012 BKN030 12/08 Q1013 NOSIG`
+///
+/// Trend groups and remarks are not decoded. Returns `None` for `NIL` reports or when
+/// the station and issue time cannot be found.
+pub fn parse_metar(raw: &str, now: DateTime<Utc>) -> Option<Metar> {
+    let raw = raw.trim().trim_end_matches('=').trim();
+    let tokens: Vec<&str> = raw.split_whitespace().collect();
+    let mut i = 0;
+
+    while matches!(tokens.get(i), Some(&("METAR" | "SPECI" | "COR"))) {
+        i += 1;
+    }
+    let station = tokens.get(i).filter(|s| s.len() == 4 && s.chars().all(|c| c.is_ascii_alphanumeric()))?;
+    let observed_at = parse_issue_time(tokens.get(i + 1)?, now)?;
+    i += 2;
+
+    let mut metar = Metar {
+        raw: raw.to_string(),
+        station: station.to_string(),
+        observed_at,
+        conditions: Conditions::default(),
+        rvr: Vec::new(),
+        temperature_c: None,
+        dewpoint_c: None,
+        qnh_hpa: None,
+    };
+
+    while i < tokens.len() {
+        let token = tokens[i];
+        match token {
+            "NIL" => return None,
+            "RMK" | "NOSIG" | "BECMG" | "TEMPO" => break,
+            "AUTO" | "COR" => {}
+            _ => {
+                let consumed = parse_conditions(&tokens[i..], &mut metar.conditions);
+                if consumed > 0 {
+                    i += consumed;
+                    continue;
+                }
+                if let Some(rvr) = parse_rvr(token) {
+                    metar.rvr.push(rvr);
+                } else if let Some((t, d)) = parse_temperature(token) {
+                    metar.temperature_c = Some(t);
+                    metar.dewpoint_c = d;
+                } else if let Some(qnh) = parse_pressure(token) {
+                    metar.qnh_hpa = Some(qnh);
+                }
+                // Anything else (recent weather, wind shear, runway state) is skipped
+            }
+        }
+        i += 1;
+    }
+
+    Some(metar)
+}
+
+/// `R22/0600`, `R04/P2000`, `R27L/0550V0800U`, `R22/M0050N`. The lowest value is kept.
+fn parse_rvr(token: &str) -> Option<RunwayVisualRange> {
+    let (runway, value) = token.strip_prefix('R')?.split_once('/')?;
+    if !runway.get(..2).is_some_and(|r| r.chars().all(|c| c.is_ascii_digit())) {
+        return None;
+    }
+    let digits: String = value.trim_start_matches(['P', 'M']).chars().take_while(|c| c.is_ascii_digit()).collect();
+    if digits.len() != 4 {
+        return None;
+    }
+
+    Some(RunwayVisualRange {
+        runway: runway.to_string(),
+        metres: digits.parse().ok()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn parse(raw: &str) -> Option<Metar> {
+        parse_metar(raw, Utc.with_ymd_and_hms(2026, 10, 18, 12, 0, 0).unwrap())
+    }
+
+    #[test]
+    fn decodes_a_full_report() {
+        let metar = parse("METAR EGSS 181150Z 24012G22KT 200V270 9999 -RA FEW012 BKN030 12/08 Q1013 NOSIG=").unwrap();
+        assert_eq!(metar.station, "EGSS");
+        let wind = metar.conditions.wind.as_ref().unwrap();
+        assert_eq!((wind.direction, wind.speed_kt, wind.gust_kt), (Some(240), 12, Some(22)));
+        assert_eq!((wind.variable_from, wind.variable_to), (Some(200), Some(270)));
+        assert_eq!(metar.conditions.visibility_m, Some(10_000));
+        assert_eq!(metar.conditions.weather, vec!["-RA"]);
+        assert_eq!(metar.ceiling_ft(), Some(3000));
+        assert_eq!((metar.temperature_c, metar.dewpoint_c, metar.qnh_hpa), (Some(12), Some(8), Some(1013)));
+    }
+
+    #[test]
+    fn visibility_ceiling_and_rvr() {
+        // (report, visibility, ceiling, lowest RVR, CAVOK)
+        let cases = [
+            ("METAR EGSS 180620Z 22003KT 0150 R22/0350 R04/P2000 FG VV001 08/08 Q1020", Some(150), Some(100), Some(350), false),
+            ("METAR EGSS 180620Z 22003KT 0200 R27L/0550V0800U FG VV/// 08/08 Q1020", Some(200), None, Some(550), false),
+            ("METAR EGSS 180620Z 22003KT 0100 R22/M0050N FG OVC002 08/08 Q1020", Some(100), Some(200), Some(50), false),
+            ("METAR EGSS 181150Z 24005KT CAVOK 15/10 Q1020", Some(10_000), None, None, true),
+            ("SPECI EGSS 181150Z 24005KT 3000 BR SCT004 BKN008 OVC012 10/09 Q1008", Some(3000), Some(800), None, false),
+            ("METAR EGSS 181150Z 24005KT 1 1/2SM BR BKN005 10/09 A2992", Some(2414), Some(500), None, false),
+        ];
+        for (raw, visibility, ceiling, rvr, cavok) in cases {
+            let metar = parse(raw).unwrap();
+            assert_eq!(metar.conditions.visibility_m, visibility, "{}", raw);
+            assert_eq!(metar.ceiling_ft(), ceiling, "{}", raw);
+            assert_eq!(metar.min_rvr_m(), rvr, "{}", raw);
+            assert_eq!(metar.conditions.cavok, cavok, "{}", raw);
+        }
+    }
+
+    #[test]
+    fn malformed_groups_are_skipped() {
+        let metar = parse("METAR EGSS 181150Z 2é012KT Ré2/0600 R2/0600 R22/06 BKNé12 1é150Z 12/08 Q1013").unwrap();
+        assert!(metar.conditions.wind.is_none());
+        assert!(metar.rvr.is_empty());
+        assert!(metar.conditions.clouds.is_empty());
+        assert_eq!((metar.temperature_c, metar.qnh_hpa), (Some(12), Some(1013)));
+    }
+
+    #[test]
+    fn unusable_reports_are_rejected() {
+        for raw in [
+            "",
+            "METAR",
+            "METAR EGSS 181150Z NIL",
+            "METAR EGSSX 181150Z 24012KT 9999",
+            "METAR EGSé 181150Z 24012KT 9999",
+            "METAR EGSS 1é150Z 24012KT 9999",
+            "METAR EGSS 24012KT 9999",
+        ] {
+            assert!(parse(raw).is_none(), "{}", raw);
+        }
+    }
+}
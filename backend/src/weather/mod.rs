@@ -0,0 +1,202 @@
+//! METAR/TAF decoding and the local weather feed.
+//!
+//! Reports come either from a text file of raw reports (one per line, TAF continuation
+//! lines indented) or from a local HTTP stand-in answering the aviationweather.gov
+//! style `/api/data/metar?ids=EGSS` and `/api/data/taf?ids=EGSS` with raw text.
+
+mod groups;
+pub mod metar;
+pub mod taf;
+
+use chrono::{DateTime, Utc};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Wind {
+    /// Degrees true; `None` for variable (`VRB`) wind.
+    pub direction: Option<u16>,
+    pub speed_kt: u32,
+    pub gust_kt: Option<u32>,
+    pub variable_from: Option<u16>,
+    pub variable_to: Option<u16>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CloudLayer {
+    /// `FEW`, `SCT`, `BKN`, `OVC` or `VV` (vertical visibility).
+    pub cover: String,
+    pub base_ft: Option<u32>,
+    /// `CB` or `TCU`.
+    pub convective: Option<String>,
+}
+
+/// Groups shared by a METAR and each TAF period.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Conditions {
+    pub wind: Option<Wind>,
+    pub visibility_m: Option<u32>,
+    pub cavok: bool,
+    pub weather: Vec<String>,
+    pub clouds: Vec<CloudLayer>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunwayVisualRange {
+    pub runway: String,
+    pub metres: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Metar {
+    pub raw: String,
+    pub station: String,
+    pub observed_at: i64,
+    #[serde(flatten)]
+    pub conditions: Conditions,
+    pub rvr: Vec<RunwayVisualRange>,
+    pub temperature_c: Option<i32>,
+    pub dewpoint_c: Option<i32>,
+    pub qnh_hpa: Option<u32>,
+}
+
+impl Metar {
+    /// Base of the lowest broken or overcast layer, or the vertical visibility.
+    pub fn ceiling_ft(&self) -> Option<u32> {
+        self.conditions.clouds.iter()
+            .filter(|c| matches!(c.cover.as_str(), "BKN" | "OVC" | "VV"))
+            .filter_map(|c| c.base_ft)
+            .min()
+    }
+
+    /// Lowest RVR reported on any runway.
+    pub fn min_rvr_m(&self) -> Option<u32> {
+        self.rvr.iter().map(|r| r.metres).min()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TafChange {
+    Base,
+    From,
+    Becoming,
+    Temporary,
+    Probable(u8),
+    ProbableTemporary(u8),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TafPeriod {
+    pub change: TafChange,
+    pub from: i64,
+    pub to: i64,
+    #[serde(flatten)]
+    pub conditions: Conditions,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Taf {
+    pub raw: String,
+    pub station: String,
+    pub issued_at: i64,
+    pub valid_from: i64,
+    pub valid_to: i64,
+    pub periods: Vec<TafPeriod>,
+}
+
+/// Latest decoded reports for one airport.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AirportWeather {
+    pub metar: Option<Metar>,
+    pub taf: Option<Taf>,
+    pub fetched_at: i64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WeatherSource {
+    File(PathBuf),
+    Http(String),
+}
+
+impl WeatherSource {
+    /// `http://...` selects the HTTP stand-in; anything else is a file path.
+    pub fn from_setting(setting: &str) -> Self {
+        if setting.starts_with("http://") || setting.starts_with("https://") {
+            WeatherSource::Http(setting.trim_end_matches('/').to_string())
+        } else {
+            WeatherSource::File(PathBuf::from(setting))
+        }
+    }
+}
+
+pub struct WeatherClient {
+    client: Client,
+    source: WeatherSource,
+}
+
+impl WeatherClient {
+    pub fn new(source: WeatherSource) -> Self {
+        WeatherClient { client: Client::new(), source }
+    }
+
+    pub async fn fetch(&self, icao: &str) -> Result<AirportWeather, Box<dyn Error>> {
+        let text = match &self.source {
+            WeatherSource::File(path) => tokio::fs::read_to_string(path).await?,
+            WeatherSource::Http(base) => {
+                let mut text = String::new();
+                for product in ["metar", "taf"] {
+                    let url = format!("{}/api/data/{}?ids={}", base, product, icao);
+                    text.push_str(&self.client.get(&url).send().await?.error_for_status()?.text().await?);
+                    text.push('\n');
+                }
+                text
+            }
+        };
+
+        let now = Utc::now();
+        let (metar, taf) = decode_reports(&text, icao, now);
+        Ok(AirportWeather { metar, taf, fetched_at: now.timestamp() })
+    }
+}
+
+/// Picks the latest METAR and TAF for `icao` out of a block of raw reports.
+pub fn decode_reports(text: &str, icao: &str, now: DateTime<Utc>) -> (Option<Metar>, Option<Taf>) {
+    let mut metar: Option<Metar> = None;
+    let mut taf: Option<Taf> = None;
+
+    for report in split_reports(text) {
+        if report.starts_with("TAF") {
+            if let Some(t) = taf::parse_taf(&report, now).filter(|t| t.station.eq_ignore_ascii_case(icao)) {
+                if taf.as_ref().is_none_or(|old| t.issued_at >= old.issued_at) {
+                    taf = Some(t);
+                }
+            }
+        } else if let Some(m) = metar::parse_metar(&report, now).filter(|m| m.station.eq_ignore_ascii_case(icao)) {
+            if metar.as_ref().is_none_or(|old| m.observed_at >= old.observed_at) {
+                metar = Some(m);
+            }
+        }
+    }
+
+    (metar, taf)
+}
+
+/// A report starts on an unindented line; indented lines continue it.
+fn split_reports(text: &str) -> Vec<String> {
+    let mut reports: Vec<String> = Vec::new();
+    for line in text.lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match reports.last_mut() {
+            Some(report) if line.starts_with([' ', '\t']) => {
+                report.push(' ');
+                report.push_str(line.trim());
+            }
+            _ => reports.push(line.trim().to_string()),
+        }
+    }
+    reports
+}
@@ -0,0 +1,120 @@
+use chrono::{DateTime, Utc};
+
+use super::groups::{parse_conditions, parse_issue_time, parse_period, resolve_time};
+use super::{Taf, TafChange, TafPeriod};
+
+/// Parses a TAF into its base forecast and change groups (`FM`, `BECMG`, `TEMPO`, `PROB30/40`).
+///
+/// `TAF EGSS 181100Z 1812/1918 24012KT 9999 SCT030 TEMPO 1814/1818 4000 SHRA BECMG 1900/1903 30008KT`
+pub fn parse_taf(raw: &str, now: DateTime<Utc>) -> Option<Taf> {
+    let raw = raw.trim().trim_end_matches('=').trim();
+    let tokens: Vec<&str> = raw.split_whitespace().collect();
+    let mut i = 0;
+
+    while matches!(tokens.get(i), Some(&("TAF" | "AMD" | "COR"))) {
+        i += 1;
+    }
+    let station = tokens.get(i).filter(|s| s.len() == 4 && s.chars().all(|c| c.is_ascii_alphanumeric()))?;
+    let issued_at = parse_issue_time(tokens.get(i + 1)?, now)?;
+    let (valid_from, valid_to) = parse_period(tokens.get(i + 2)?, now)?;
+    i += 3;
+
+    let mut periods = vec![TafPeriod { change: TafChange::Base, from: valid_from, to: valid_to, conditions: Default::default() }];
+
+    while i < tokens.len() {
+        let token = tokens[i];
+        if token == "RMK" {
+            break;
+        }
+
+        if let Some(period) = parse_change(&tokens[i..], valid_to, now) {
+            i += period.1;
+            periods.push(period.0);
+            continue;
+        }
+
+        let current = periods.last_mut().expect("base period");
+        let consumed = parse_conditions(&tokens[i..], &mut current.conditions);
+        // Temperature forecasts (TX/TN), icing and turbulence groups are skipped
+        i += consumed.max(1);
+    }
+
+    // An FM group runs until the next FM group or the end of the validity
+    let fm_starts: Vec<i64> = periods.iter().filter(|p| p.change == TafChange::From).map(|p| p.from).collect();
+    for period in periods.iter_mut().filter(|p| p.change == TafChange::From) {
+        period.to = fm_starts.iter().copied().filter(|&s| s > period.from).min().unwrap_or(valid_to);
+    }
+
+    Some(Taf {
+        raw: raw.to_string(),
+        station: station.to_string(),
+        issued_at,
+        valid_from,
+        valid_to,
+        periods,
+    })
+}
+
+/// Reads a change indicator and its period, returning the new period and the tokens used.
+fn parse_change(tokens: &[&str], valid_to: i64, now: DateTime<Utc>) -> Option<(TafPeriod, usize)> {
+    let empty = |change, (from, to)| TafPeriod { change, from, to, conditions: Default::default() };
+
+    if let Some(fm) = tokens[0].strip_prefix("FM").filter(|f| f.len() == 6 && f.chars().all(|c| c.is_ascii_digit())) {
+        let from = resolve_time(fm[0..2].parse().ok()?, fm[2..4].parse().ok()?, fm[4..6].parse().ok()?, now)?;
+        return Some((empty(TafChange::From, (from, valid_to)), 1));
+    }
+
+    match tokens {
+        ["BECMG", period, ..] => Some((empty(TafChange::Becoming, parse_period(period, now)?), 2)),
+        ["TEMPO", period, ..] => Some((empty(TafChange::Temporary, parse_period(period, now)?), 2)),
+        [prob, "TEMPO", period, ..] if prob.starts_with("PROB") => {
+            let probability = prob[4..].parse().ok()?;
+            Some((empty(TafChange::ProbableTemporary(probability), parse_period(period, now)?), 3))
+        }
+        [prob, period, ..] if prob.starts_with("PROB") => {
+            let probability = prob[4..].parse().ok()?;
+            Some((empty(TafChange::Probable(probability), parse_period(period, now)?), 2))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 10, 18, 12, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn decodes_change_groups() {
+        let raw = "TAF EGSS 181100Z 1812/1918 24012KT 9999 SCT030 TEMPO 1814/1818 4000 SHRA \
+                   BECMG 1900/1903 30008KT PROB30 TEMPO 1904/1908 0800 FG VV002 FM191200 27010KT CAVOK";
+        let taf = parse_taf(raw, now()).unwrap();
+        let changes: Vec<TafChange> = taf.periods.iter().map(|p| p.change).collect();
+        assert_eq!(changes, vec![
+            TafChange::Base,
+            TafChange::Temporary,
+            TafChange::Becoming,
+            TafChange::ProbableTemporary(30),
+            TafChange::From,
+        ]);
+        assert_eq!(taf.periods[1].conditions.visibility_m, Some(4000));
+        assert_eq!(taf.periods[3].conditions.clouds[0].base_ft, Some(200));
+        assert!(taf.periods[4].conditions.cavok);
+        assert_eq!(taf.periods[4].to, taf.valid_to);
+    }
+
+    #[test]
+    fn malformed_tafs() {
+        for raw in ["TAF EGSS 181100Z 18é2/1918 24012KT", "TAF EGSS 1é100Z 1812/1918 24012KT", "TAF EGSS"] {
+            assert!(parse_taf(raw, now()).is_none(), "{}", raw);
+        }
+        // Bad groups inside the forecast are skipped
+        let taf = parse_taf("TAF EGSS 181100Z 1812/1918 2é012KT PROBé0 FMé91200 9999", now()).unwrap();
+        assert_eq!(taf.periods.len(), 1);
+        assert_eq!(taf.periods[0].conditions.visibility_m, Some(10_000));
+    }
+}
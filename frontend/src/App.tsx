@@ -45,10 +45,9 @@ function App() {
   useEffect(() => {
     // Poll loop
     const fetchData = async () => {
-      // If no airport selected, don't fetch or fetch empty? 
-      // Actually backend returns empty if no airport selected.
+      // Every configured airport is monitored; fetch the one being viewed
       try {
-        const response = await fetch('http://localhost:3000/api/states');
+        const response = await fetch(`http://localhost:3000/api/airports/${activeAirport}/states`);
         if (!response.ok) {
           console.warn("Backend not reachable");
           return;
@@ -80,17 +79,10 @@ function App() {
     }
   }, [activeAirport]);
 
-  const selectAirport = async (code: string) => {
-    try {
-      await fetch('http://localhost:3000/api/airport', {
-        method: 'POST',
-        headers: { 'Content-Type': 'application/json' },
-        body: JSON.stringify({ code })
-      });
-      setActiveAirport(code);
-    } catch (e) {
-      console.error("Failed to set airport", e);
-    }
+  const selectAirport = (code: string) => {
+    // Switching view is local; the backend keeps polling every airport
+    setAircraft([]);
+    setActiveAirport(code);
   };
 
   // AIRPORT SELECTION MODAL
@@ -3,7 +3,6 @@ import { MapContainer, TileLayer, Marker, Popup, Polyline, CircleMarker, useMap,
 import 'leaflet/dist/leaflet.css';
 import type { Aircraft } from '../types';
 import L from 'leaflet';
-import airportDataRaw from '../airport_data.json';
 
 // Bearing calculation with wraparound handling
 const calcBearing = (lat1: number, lon1: number, lat2: number, lon2: number) => {
@@ -106,13 +105,14 @@ const MapComponent: React.FC<MapProps> = ({ aircraft, activeAirport, onSelect })
     const [showHolds, setShowHolds] = useState(true);
 
     useEffect(() => {
-        // @ts-ignore
-        const data = airportDataRaw[activeAirport];
-        if (data) {
-            setMapData(data);
-        } else {
-            setMapData({ taxiways: [], holds: [], stands: [] });
-        }
+        // Layout is built by the backend from the EuroScope sector files
+        setMapData({ taxiways: [], holds: [], stands: [] });
+        fetch(`http://localhost:3000/api/airports/${activeAirport}/layout`)
+            .then(res => res.ok ? res.json() : null)
+            .then(data => {
+                if (data) setMapData(data);
+            })
+            .catch(err => console.error("Failed to load airport layout", err));
     }, [activeAirport]);
 
     // Trail Logic
@@ -8,6 +8,7 @@ export type Phase =
     | "Climb"
     | "Cruise"
     | "Descent"
+    | "Holding"
     | "Approach"
     | "Final"
     | "Landing"